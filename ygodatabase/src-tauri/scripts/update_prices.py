@@ -6,8 +6,11 @@ What it does:
 1. Fetches all YuGiOh groups (sets) from tcgcsv.com/tcgplayer/2/groups
 2. For each group, fetches products + prices in parallel (capped workers)
 3. Joins on (set_code, rarity) → card_sets rows in your DB
-4. Converts USD → EUR using live exchange rate from frankfurter.app
-5. Writes the new price (as a decimal string) back to set_price
+4. Refreshes the local exchange_rates table from frankfurter.app
+5. Writes the new price (as a decimal string, in USD) back to set_price
+   and marks price_currency = 'USD' — conversion happens in the app
+6. Appends changed prices to price_history (the app snapshots the
+   collection value once the sync is done)
 
 Usage:
     python update_prices.py --db path/to/cards.db
@@ -19,7 +22,7 @@ Options:
 
 Limitations:
     - tcgcsv.com prices are TCGplayer market prices (USD), not Cardmarket (EUR).
-      We convert using the live USD/EUR rate — close enough for collection value.
+      The app converts them with the stored rates — close enough for collection value.
     - Only Near Mint prices are returned (tcgcsv does not expose per-condition data).
     - Groups/products are cached in memory for the run; re-run to refresh.
 """
@@ -52,15 +55,25 @@ def get_json(url: str, retries: int = 3) -> dict | list | None:
                 return None
             time.sleep(1.5 ** attempt)
 
-def get_exchange_rate() -> float:
-    """Fetch live USD→EUR rate from frankfurter.app (free, no key needed)."""
-    data = get_json("https://api.frankfurter.app/latest?from=USD&to=EUR")
-    if data and "rates" in data and "EUR" in data["rates"]:
-        rate = data["rates"]["EUR"]
-        print(f"Exchange rate: 1 USD = {rate:.4f} EUR", flush=True)
-        return rate
-    print("WARN: Could not fetch exchange rate, using fallback 0.92", flush=True)
-    return 0.92
+def fetch_exchange_rates() -> tuple[str, dict[str, float]] | None:
+    """Fetch the latest EUR-based rates from frankfurter.app (free, no key needed).
+    Returns (rate_date, {currency: units per 1 EUR}) or None when offline."""
+    data = get_json("https://api.frankfurter.app/latest")
+    if data and "rates" in data and "date" in data:
+        rates = data["rates"]
+        if "USD" in rates:
+            print(f"Exchange rates of {data['date']}: 1 EUR = {rates['USD']:.4f} USD "
+                  f"({len(rates)} currencies)", flush=True)
+        return data["date"], rates
+    print("WARN: Could not fetch exchange rates, keeping the stored ones", flush=True)
+    return None
+
+def store_exchange_rates(conn: sqlite3.Connection, rate_date: str, rates: dict[str, float]):
+    """Keeps one row per currency and rate date, so older rates stay available."""
+    conn.executemany(
+        "INSERT OR REPLACE INTO exchange_rates (currency, rate, rate_date) VALUES (?, ?, ?)",
+        [(cur, rate, rate_date) for cur, rate in rates.items()]
+    )
 
 # ── Fetching ─────────────────────────────────────────────────────────────────
 
@@ -144,6 +157,66 @@ def build_rarity_index(db_index: dict) -> dict[tuple[str,str], list]:
         out[(code, normalize_rarity(rarity))] = entries
     return out
 
+def ensure_history_tables(conn: sqlite3.Connection):
+    """Same definitions as create_app_tables() in src/db.rs — this script may
+    run before the app has ever started. Only the tables written here are
+    created; everything else (sealed products, graded slabs, the value
+    history) belongs to the app."""
+    conn.executescript("""
+        CREATE TABLE IF NOT EXISTS price_history (
+            card_id     INTEGER NOT NULL,
+            set_code    TEXT NOT NULL,
+            set_rarity  TEXT NOT NULL,
+            artwork     INTEGER NOT NULL DEFAULT 0,
+            price       REAL NOT NULL,
+            recorded_on TEXT NOT NULL,
+            PRIMARY KEY (card_id, set_code, set_rarity, artwork, recorded_on)
+        );
+        CREATE TABLE IF NOT EXISTS exchange_rates (
+            currency  TEXT NOT NULL,
+            rate      REAL NOT NULL,
+            rate_date TEXT NOT NULL,
+            PRIMARY KEY (currency, rate_date)
+        );
+    """)
+    for table, col, defn in [
+        ("card_sets",     "price_currency", "TEXT"),
+        ("price_history", "currency",       "TEXT NOT NULL DEFAULT 'EUR'"),
+    ]:
+        try:
+            conn.execute(f"ALTER TABLE {table} ADD COLUMN {col} {defn}")
+        except sqlite3.OperationalError:
+            pass  # already exists
+
+def record_history(conn: sqlite3.Connection) -> int:
+    """Stores today's price for every printing whose price or currency differs
+    from its latest recorded one (history is change-only: a printing's price
+    holds until its next entry).
+    Returns the number of history rows written."""
+    ensure_history_tables(conn)
+    cur = conn.execute("""
+        INSERT OR REPLACE INTO price_history
+            (card_id, set_code, set_rarity, artwork, price, currency, recorded_on)
+        SELECT cs.card_id, cs.set_code, cs.set_rarity, COALESCE(cs.artwork, 0),
+               CAST(cs.set_price AS REAL), COALESCE(cs.price_currency, 'USD'), date('now')
+        FROM card_sets cs
+        WHERE cs.set_code IS NOT NULL AND cs.set_rarity IS NOT NULL
+          AND CAST(cs.set_price AS REAL) > 0
+          AND NOT EXISTS (
+              SELECT 1 FROM price_history ph
+              WHERE ph.card_id = cs.card_id AND ph.set_code = cs.set_code
+                AND ph.set_rarity = cs.set_rarity AND ph.artwork = COALESCE(cs.artwork, 0)
+                AND ph.price = CAST(cs.set_price AS REAL)
+                AND ph.currency = COALESCE(cs.price_currency, 'USD')
+                AND ph.recorded_on = (
+                    SELECT MAX(p2.recorded_on) FROM price_history p2
+                    WHERE p2.card_id = ph.card_id AND p2.set_code = ph.set_code
+                      AND p2.set_rarity = ph.set_rarity AND p2.artwork = ph.artwork
+                )
+          )
+    """)
+    return cur.rowcount
+
 # ── Main ─────────────────────────────────────────────────────────────────────
 
 def main():
@@ -165,8 +238,8 @@ def main():
     norm_index = build_rarity_index(db_index)
     print(f"  {len(db_index)} distinct (set_code, rarity) entries in DB", flush=True)
 
-    print("\nFetching exchange rate...", flush=True)
-    rate = get_exchange_rate()
+    print("\nFetching exchange rates...", flush=True)
+    rates = fetch_exchange_rates()
 
     print("\nFetching YuGiOh groups from tcgcsv.com...", flush=True)
     groups = fetch_groups()
@@ -176,7 +249,7 @@ def main():
 
     print(f"\nFetching products + prices for {len(groups)} groups ({args.workers} workers)...", flush=True)
 
-    updates: list[tuple[str, int]] = []   # [(new_price_str, rowid)]
+    updates: list[tuple[str, int]] = []   # [(new_price_str, rowid)], USD
     matched = 0
     unmatched = 0
 
@@ -192,8 +265,7 @@ def main():
             for row in rows:
                 code   = row["number"]
                 rarity = row["rarity"]
-                usd    = row["usd"]
-                eur    = round(usd * rate, 2)
+                usd    = round(row["usd"], 2)
 
                 # Try exact match first
                 key = (code, rarity)
@@ -206,7 +278,7 @@ def main():
 
                 if entries:
                     for (rowid, _) in entries:
-                        updates.append((f"{eur:.2f}", rowid))
+                        updates.append((f"{usd:.2f}", rowid))
                     matched += 1
                 else:
                     unmatched += 1
@@ -217,10 +289,10 @@ def main():
         print("Dry run — not writing to DB", flush=True)
         # Show a sample
         for price_str, rowid in updates[:10]:
-            print(f"  rowid={rowid}  new_price={price_str} EUR", flush=True)
+            print(f"  rowid={rowid}  new_price={price_str} USD", flush=True)
         return
 
-    if not updates:
+    if not updates and not rates:
         print("Nothing to update.", flush=True)
         return
 
@@ -228,12 +300,16 @@ def main():
     conn = sqlite3.connect(db_path)
     conn.execute("BEGIN")
     try:
+        ensure_history_tables(conn)
+        if rates:
+            store_exchange_rates(conn, *rates)
         conn.executemany(
-            "UPDATE card_sets SET set_price = ? WHERE rowid = ?",
+            "UPDATE card_sets SET set_price = ?, price_currency = 'USD' WHERE rowid = ?",
             updates
         )
+        history = record_history(conn)
         conn.commit()
-        print(f"Done. {len(updates)} rows updated.", flush=True)
+        print(f"Done. {len(updates)} rows updated, {history} price changes recorded.", flush=True)
     except Exception as e:
         conn.rollback()
         print(f"ERROR during write: {e}", flush=True)
@@ -7,7 +7,8 @@ Phases:
   2. Cards  — upsert card data from YGOPRODeck (never drops tables)
   3. Images — download missing card images
   4. Sets   — upsert set entries (preserves collection_amount & artwork)
-  5. Prices — run update_prices.py (tcgcsv → per-set EUR prices)
+  5. Prices — run update_prices.py (tcgcsv → per-set USD prices, plus the
+              exchange rates the app converts them with)
   6. Thumbs — generate missing WebP thumbnails
 
 All scripts (update_prices.py, generate_thumbnails.py) are expected in the
@@ -112,6 +113,7 @@ def ensure_schema(conn: sqlite3.Connection):
         ("cards",     "has_effect",     "INTEGER"),
         ("cards",     "treated_as",     "TEXT"),
         ("card_sets", "artwork",        "INTEGER DEFAULT 0"),
+        ("card_sets", "price_currency", "TEXT"),
     ]:
         try:
             conn.execute(f"ALTER TABLE {table} ADD COLUMN {col} {defn}")
@@ -328,12 +330,14 @@ def sync_cards(conn: sqlite3.Connection):
     for card in cards:
         card_id = card["id"]
         for s in card.get("card_sets", []):
+            # YGOPRODeck set prices are TCGplayer USD prices
             conn2.execute("""
-                INSERT INTO card_sets (card_id, set_name, set_code, set_rarity, set_price, collection_amount, artwork)
-                VALUES (?,?,?,?,?,0,0)
+                INSERT INTO card_sets (card_id, set_name, set_code, set_rarity, set_price, collection_amount, artwork, price_currency)
+                VALUES (?,?,?,?,?,0,0,'USD')
                 ON CONFLICT(card_id, set_code, set_rarity, artwork) DO UPDATE SET
                     set_name=excluded.set_name,
-                    set_price=excluded.set_price
+                    set_price=excluded.set_price,
+                    price_currency=excluded.price_currency
             """, (card_id, s.get("set_name"), s.get("set_code"), s.get("set_rarity"), s.get("set_price")))
         for p in card.get("card_prices", []):
             conn2.execute("""
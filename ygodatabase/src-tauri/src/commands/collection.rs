@@ -1,96 +1,295 @@
 use std::collections::HashMap;
-use rusqlite::named_params;
+use rusqlite::{named_params, Connection, OptionalExtension};
 
+use crate::currency::{latest_date, round2, ExchangeRates, BASE_CURRENCY};
+use crate::commands::graded::load_graded;
+use crate::commands::loans::load_loans;
+use crate::commands::profit::{clamp_lots, undo_ledger};
+use crate::commands::storage::{clamp_stored, load_stored};
+use crate::commands::tags::{card_notes, card_tags};
+use crate::commands::trade::clamp_trade_amount;
+use crate::commands::wishlist::{fulfil_wishlist, unfulfil_wishlist};
 use crate::db::{open_db, normalize_img_path, normalize_thumb_path};
-use crate::models::{CardDetail, CardSet, CardSetRarity, CardStub, RawDetailRow, RawStubRow};
+use crate::filters::{CardFilters, SqlFilter};
+use crate::models::{
+    CardDetail, CardFacets, CardPage, CardSet, CardSetRarity, CardStub, FacetCount, LinkMarker,
+    PrintingKey, RawDetailRow, RawStubRow,
+};
+use crate::query::{parse_query, QueryError};
 
 // ---------------------------------------------------------------------------
 // Stubs
 // ---------------------------------------------------------------------------
+/// Every stub matching `filters`, in `sort` order (see stub_order).
 #[tauri::command]
 pub fn load_card_stubs(
-    name: Option<String>,
-    set: Option<String>,
-    category: Option<String>,   // "monster" | "spell" | "trap"
-    frame_type: Option<String>,   // exact DB frameType (subcategory); overrides category
-    attribute: Option<String>,
-    race: Option<String>,
-    level: Option<i64>,
-    scale: Option<i64>,
-    atk: Option<i64>,
-    def: Option<i64>,
-    ban_status: Option<String>,
-    archetype: Option<String>,
-    genesys_points_min: Option<i64>,
-    genesys_points_max: Option<i64>,
-    format: Option<String>,    // active format for card pool + ban key filtering
+    filters: Option<CardFilters>,
     sort: Option<String>,
 ) -> Result<Vec<CardStub>, String> {
     let conn = open_db()?;
+    let filter = filters.unwrap_or_default().to_sql();
 
-    // Map format name → ban_info JSON key
-    let ban_key = match format.as_deref().unwrap_or("") {
-        "TCG"            => "ban_tcg",
-        "OCG"            => "ban_ocg",
-        "Master Duel"    => "ban_ocg",
-        "GOAT"           => "ban_goat",
-        "OCG GOAT"       => "ban_goat",
-        "Edison"         => "ban_tcg",
-        "Common Charity" => "ban_tcg",
-        "Duel Links"     => "ban_tcg",
-        _                => "ban_tcg", // default / genesys (ban_status unused for genesys)
-    };
-
-    // Card pool filter: when a non-genesys format is active, restrict to cards
-    // that exist in that format's card pool (formats column is a JSON array).
-    let pool_clause = if let Some(ref fmt) = format {
-        if fmt != "Genesys" {
-            format!("AND c.formats IS NOT NULL AND c.formats LIKE '%{}%'",
-                fmt.replace('\'', "''"))
-        } else {
-            String::new() // Genesys uses the full card pool
+    let sql = format!("
+        SELECT {STUB_COLUMNS}
+        {STUB_FROM}
+        WHERE {}
+        ORDER BY {}
+    ", filter.clause, stub_order(sort.as_deref(), false)?);
+
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(filter.params_ref().as_slice(), stub_row)
+        .map_err(|e| e.to_string())?;
+    collapse_stubs(rows)
+}
+
+/// One page of stubs for the given filters, plus the total stub count and
+/// facet counts over all matches. `offset` / `limit` count stubs (one per
+/// card artwork), in the same order load_card_stubs returns them;
+/// `limit` defaults to 100 and is capped at 1000.
+#[tauri::command]
+pub fn search_card_stubs(
+    filters: Option<CardFilters>,
+    sort: Option<String>,
+    offset: Option<i64>,
+    limit: Option<i64>,
+) -> Result<CardPage, String> {
+    let conn = open_db()?;
+    let filter = filters.unwrap_or_default().to_sql();
+    stub_page(&conn, &filter, sort.as_deref(), offset, limit)
+}
+
+/// Same as search_card_stubs, but `query` is written in the search box
+/// language (see query.rs), e.g. `atk>=2500 attr:dark -banned:tcg`.
+/// `filters` still applies on top, which is how the format pool is kept.
+/// A query that does not parse is returned as an error naming the column.
+#[tauri::command]
+pub fn search_card_query(
+    query: String,
+    filters: Option<CardFilters>,
+    sort: Option<String>,
+    offset: Option<i64>,
+    limit: Option<i64>,
+) -> Result<CardPage, String> {
+    let filter = card_filter(&filters.unwrap_or_default(), Some(&query))?;
+    let conn = open_db()?;
+    stub_page(&conn, &filter, sort.as_deref(), offset, limit)
+}
+
+/// Validates a search box query without running it, so the UI can mark the
+/// bad token as the user types. None when the query is fine.
+#[tauri::command]
+pub fn check_card_query(query: String) -> Option<QueryError> {
+    parse_query(&query, None).err()
+}
+
+/// `filters` ANDed with a search box `query`, when there is one.
+pub fn card_filter(filters: &CardFilters, query: Option<&str>) -> Result<SqlFilter, String> {
+    let filter = filters.to_sql();
+    match query.map(str::trim).filter(|q| !q.is_empty()) {
+        Some(q) => {
+            let parsed = parse_query(q, filters.format.as_deref()).map_err(|e| e.to_string())?;
+            Ok(filter.and(parsed))
         }
-    } else {
-        String::new()
-    };
-
-    // Ban status clause uses the correct key for the active format
-    let ban_key_path = format!("$.{}", ban_key);
-    const MONSTER_FRAMES: &[&str] = &[
-        "normal", "effect", "ritual", "fusion", "synchro", "xyz", "link",
-        "normal_pendulum", "effect_pendulum", "ritual_pendulum",
-        "fusion_pendulum", "synchro_pendulum", "xyz_pendulum",
-    ];
-
-    let frame_clause = if let Some(ref ft) = frame_type {
-        format!("AND c.frameType = '{}'", ft.replace('\'', "''"))
-    } else if let Some(ref cat) = category {
-        match cat.as_str() {
-            "monster" => {
-                let list = MONSTER_FRAMES.iter()
-                    .map(|f| format!("'{}'", f))
-                    .collect::<Vec<_>>().join(", ");
-                format!("AND c.frameType IN ({})", list)
+        None => Ok(filter),
+    }
+}
+
+/// The search_card_stubs page for an already built filter.
+pub fn stub_page(
+    conn: &Connection,
+    filter: &SqlFilter,
+    sort: Option<&str>,
+    offset: Option<i64>,
+    limit: Option<i64>,
+) -> Result<CardPage, String> {
+    let offset = offset.unwrap_or(0).max(0);
+    let limit = limit.unwrap_or(100).clamp(1, 1000);
+
+    let mut params = filter.params_ref();
+    params.push((":offset", &offset));
+    params.push((":limit", &limit));
+
+    // Rank the matching stubs, keep one page of them, then fetch their rows
+    // (the filter is applied again so rarities only list matching sets).
+    let sql = format!("
+        WITH page AS (
+            SELECT c.id AS page_id,
+                   ci.image_id AS page_image_id,
+                   ROW_NUMBER() OVER (ORDER BY {}) AS page_rank
+            {STUB_FROM}
+            WHERE {where_clause}
+            GROUP BY c.id, ci.image_id
+            ORDER BY page_rank
+            LIMIT :limit OFFSET :offset
+        )
+        SELECT {STUB_COLUMNS}
+        FROM page
+        JOIN cards c ON c.id = page.page_id
+        LEFT JOIN card_images ci
+            ON ci.card_id = c.id
+            AND ci.image_id IS page.page_image_id
+        LEFT JOIN card_sets cs
+            ON cs.card_id = c.id
+            AND COALESCE(cs.artwork, 0) = (ci.image_id - c.id)
+        WHERE {where_clause}
+        ORDER BY page.page_rank, {}
+    ", stub_order(sort, true)?, stub_order(sort, false)?,
+       where_clause = filter.clause);
+
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params.as_slice(), stub_row)
+        .map_err(|e| e.to_string())?;
+    let stubs = collapse_stubs(rows)?;
+
+    // Total and facets in one pass over the grouped matches
+    let sql = format!("
+        WITH matches AS (
+            SELECT c.frameType AS frame_type, c.attribute, c.race
+            {STUB_FROM}
+            WHERE {}
+            GROUP BY c.id, ci.image_id
+        )
+        SELECT 'total' AS facet, NULL AS value, COUNT(*) AS n FROM matches
+        UNION ALL
+        SELECT 'frame_type', frame_type, COUNT(*) FROM matches GROUP BY frame_type
+        UNION ALL
+        SELECT 'attribute', attribute, COUNT(*) FROM matches GROUP BY attribute
+        UNION ALL
+        SELECT 'race', race, COUNT(*) FROM matches GROUP BY race
+        ORDER BY facet, n DESC, value
+    ", filter.clause);
+
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(filter.params_ref().as_slice(), |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?, row.get::<_, i64>(2)?))
+        })
+        .map_err(|e| e.to_string())?;
+
+    let mut total = 0;
+    let mut facets = CardFacets::default();
+    for r in rows {
+        let (facet, value, count) = r.map_err(|e| e.to_string())?;
+        let bucket = match facet.as_str() {
+            "frame_type" => &mut facets.frame_types,
+            "attribute" => &mut facets.attributes,
+            "race" => &mut facets.races,
+            _ => {
+                total = count;
+                continue;
             }
-            "spell" => "AND c.frameType = 'spell'".to_string(),
-            "trap"  => "AND c.frameType = 'trap'".to_string(),
-            _       => String::new(),
-        }
-    } else {
-        String::new()
-    };
-
-    // ── ORDER BY ─────────────────────────────────────────────────────────────
-    // "set"  → sort by set_code ascending (groups cards within a set by their
-    //           collector number, which is embedded in the code e.g. DUNE-EN056)
-    // "type" → monster / spell / trap bucket first, then frameType order within
-    //          monsters (normal < effect < ritual < fusion < fusion_pendulum <
-    //          synchro < synchro_pendulum < xyz < xyz_pendulum < link),
-    //          then level/rank/rating DESC, then name ASC.
-    //          Spells and traps sort only by name ASC.
-    let order_clause = match sort.as_deref().unwrap_or("type") {
-        "set" => "ORDER BY cs.set_code ASC, c.name ASC".to_string(),
-        _ => "ORDER BY
+        };
+        bucket.push(FacetCount { value, count });
+    }
+
+    Ok(CardPage { stubs, total, offset, limit, facets })
+}
+
+// One row per (card, artwork, set row). card_images is joined so each image
+// gets its own stub, then card_sets filtered to that artwork index.
+const STUB_FROM: &str = "
+        FROM cards c
+        LEFT JOIN card_images ci ON ci.card_id = c.id
+        LEFT JOIN card_sets cs
+            ON cs.card_id = c.id
+            AND COALESCE(cs.artwork, 0) = (ci.image_id - c.id)
+";
+
+const STUB_COLUMNS: &str = "
+            c.id,
+            c.name,
+            c.type,
+            c.has_alt_art,
+            ci.image_id,
+            ci.local_path,
+            c.frameType,
+            cs.set_rarity,
+            cs.collection_amount,
+            c.level,
+            cs.set_code,
+            COALESCE(c.genesys_points, 0) as genesys_points,
+            (ci.image_id - c.id) as artwork_index,
+            (SELECT json_group_array(name) FROM (
+                SELECT t.name FROM card_tags ct
+                JOIN tags t ON t.id = ct.tag_id
+                WHERE ct.card_id = c.id
+                ORDER BY t.name COLLATE NOCASE
+            )) as tags
+";
+
+// ── ORDER BY ─────────────────────────────────────────────────────────────────
+// `sort` is a comma-separated list of keys, each optionally followed by
+// ":asc" / ":desc" (default asc), e.g. "atk:desc,name". Later keys break ties
+// of earlier ones; card id and artwork always come last so paging is stable.
+// "set"  → sort by set_code ascending (groups cards within a set by their
+//           collector number, which is embedded in the code e.g. DUNE-EN056)
+// "type" → monster / spell / trap bucket first, then frameType order within
+//          monsters (normal < effect < ritual < fusion < fusion_pendulum <
+//          synchro < synchro_pendulum < xyz < xyz_pendulum < link),
+//          then level/rank/rating DESC, then name ASC.
+//          Spells and traps sort only by name ASC. The default.
+// "name", "atk", "def", "genesys"
+// "level"    → level / rank, or link rating for Link monsters
+// "date"     → TCG release date, else OCG; also "tcg_date" and "ocg_date"
+// "price"    → cheapest priced printing of the artwork, converted to EUR
+// "owned"    → copies owned of the artwork
+// "added"    → last time copies of the artwork were added to the collection
+// Missing values (ATK of a spell, unpriced cards, ...) sort last either way.
+// `grouped` gives the terms for a query grouped by (card, artwork), where
+// set-level columns need an aggregate.
+pub fn stub_order(sort: Option<&str>, grouped: bool) -> Result<String, String> {
+    let mut terms = Vec::new();
+    for part in sort.unwrap_or("type").split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let (key, dir) = match part.split_once(':') {
+            Some((key, dir)) => (key.trim(), dir.trim().to_lowercase()),
+            None => (part, "asc".to_string()),
+        };
+        let dir = match dir.as_str() {
+            "asc" => "ASC",
+            "desc" => "DESC",
+            _ => return Err(format!("Unknown sort direction '{}' (use asc or desc)", dir)),
+        };
+        let expr = match key {
+            "type" => {
+                terms.push(format!("{TYPE_ORDER} {dir}, COALESCE(c.level, c.linkval, 0) DESC, c.name ASC"));
+                continue;
+            }
+            "set" => {
+                // Ungrouped, a stub is placed by its first row: the lowest
+                // code ascending, the highest descending.
+                let code = match (grouped, dir) {
+                    (false, _) => "cs.set_code",
+                    (true, "ASC") => "MIN(cs.set_code)",
+                    (true, _) => "MAX(cs.set_code)",
+                };
+                terms.push(format!("{code} {dir} NULLS LAST, c.name ASC"));
+                continue;
+            }
+            "name" => "c.name",
+            "atk" => "c.atk",
+            "def" => "c.def",
+            "level" => "COALESCE(c.level, c.linkval)",
+            "genesys" => "COALESCE(c.genesys_points, 0)",
+            "date" => "COALESCE(c.tcg_date, c.ocg_date)",
+            "tcg_date" => "c.tcg_date",
+            "ocg_date" => "c.ocg_date",
+            "price" => ORDER_PRICE,
+            "owned" => ORDER_OWNED,
+            "added" => ORDER_ADDED,
+            _ => return Err(format!("Unknown sort key '{}'", key)),
+        };
+        terms.push(format!("{expr} {dir} NULLS LAST"));
+    }
+    if terms.is_empty() {
+        return stub_order(None, grouped);
+    }
+    terms.push("c.id ASC, ci.image_id ASC".to_string());
+    Ok(terms.join(", "))
+}
+
+const TYPE_ORDER: &str = "
             CASE c.frameType
                 WHEN 'normal' THEN 100
                 WHEN 'effect' THEN 110
@@ -108,97 +307,65 @@ pub fn load_card_stubs(
                 WHEN 'spell' THEN 300
                 WHEN 'trap' THEN 400
                 ELSE 500
-            END ASC,
-            COALESCE(c.level, c.linkval, 0) DESC,
-            c.name ASC".to_string(),
-    };
+            END";
 
-    let sql = format!("
-        SELECT
-            c.id,
-            c.name,
-            c.type,
-            c.has_alt_art,
-            ci.image_id,
-            ci.local_path,
-            c.frameType,
-            cs.set_rarity,
-            cs.collection_amount,
-            c.level,
-            cs.set_code,
-            COALESCE(c.genesys_points, 0) as genesys_points,
-            (ci.image_id - c.id) as artwork_index
-        FROM cards c
-        -- One row per distinct artwork: cross-join card_images so each image
-        -- gets its own stub, then join card_sets filtered to that artwork index.
-        LEFT JOIN card_images ci ON ci.card_id = c.id
-        LEFT JOIN card_sets cs
-            ON cs.card_id = c.id
-            AND COALESCE(cs.artwork, 0) = (ci.image_id - c.id)
-        WHERE (:name IS NULL OR c.name LIKE :name)
-          AND (:set IS NULL OR cs.set_name = :set)
-          {frame_clause}
-          {pool_clause}
-          AND (:attribute  IS NULL OR c.attribute = :attribute)
-          AND (:race IS NULL OR c.race = :race)
-          AND (:level IS NULL OR c.level = :level)
-          AND (:scale IS NULL OR c.scale = :scale)
-          AND (:atk IS NULL OR c.atk = :atk)
-          AND (:def IS NULL OR c.def = :def)
-          AND (:ban_status IS NULL OR (
-                LOWER(json_extract(c.banlist_info, '{ban_key_path}')) = LOWER(:ban_status)
-              ))
-          AND (:archetype IS NULL OR (
-                c.archetype IS NOT NULL AND
-                EXISTS (
-                    SELECT 1 FROM json_each(c.archetype)
-                    WHERE LOWER(value) = LOWER(:archetype)
-                )
-              ))
-          AND (:genesys_points_min IS NULL OR COALESCE(c.genesys_points, 0) >= :genesys_points_min)
-          AND (:genesys_points_max IS NULL OR COALESCE(c.genesys_points, 0) <= :genesys_points_max)
-        {order_clause}
-    ");
+// Per-stub sort values. They read all of the artwork's printings, not just
+// the filtered ones, so every row of a stub sorts the same. A NULL price
+// currency is USD; a currency without a stored rate sorts by its raw price.
+const ORDER_PRICE: &str = "(
+            SELECT MIN(CAST(o.set_price AS REAL) / COALESCE((
+                SELECT r.rate FROM exchange_rates r
+                WHERE r.currency = COALESCE(o.price_currency, 'USD')
+                ORDER BY r.rate_date DESC LIMIT 1
+            ), 1))
+            FROM card_sets o
+            WHERE o.card_id = c.id
+              AND COALESCE(o.artwork, 0) = COALESCE(ci.image_id - c.id, 0)
+              AND CAST(o.set_price AS REAL) > 0
+        )";
 
-    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+const ORDER_OWNED: &str = "(
+            SELECT COALESCE(SUM(o.collection_amount), 0)
+            FROM card_sets o
+            WHERE o.card_id = c.id
+              AND COALESCE(o.artwork, 0) = COALESCE(ci.image_id - c.id, 0)
+        )";
 
-    let params = named_params! {
-        ":name": name.as_ref().map(|v| format!("%{}%", v)),
-        ":set": set.as_ref(),
-        ":attribute": attribute.as_ref(),
-        ":race": race.as_ref(),
-        ":level": level,
-        ":scale": scale,
-        ":atk": atk,
-        ":def": def,
-        ":ban_status": ban_status.as_ref(),
-        ":archetype": archetype.as_ref(),
-        ":genesys_points_min": genesys_points_min,
-        ":genesys_points_max": genesys_points_max,
-    };
+const ORDER_ADDED: &str = "(
+            SELECT MAX(h.changed_at)
+            FROM collection_history h
+            WHERE h.card_id = c.id
+              AND h.artwork = COALESCE(ci.image_id - c.id, 0)
+              AND h.new_amount > h.old_amount
+        )";
 
-    let rows = stmt
-        .query_map(params, |row| {
-            Ok(RawStubRow {
-                id: row.get("id")?,
-                name: row.get("name")?,
-                card_type: row.get("type")?,
-                has_alt_art: row.get("has_alt_art")?,
-                img_path: row.get("local_path")?,
-                image_id: row.get("image_id")?,
-                frame_type: row.get("frameType").ok(),
-                set_rarity: row.get("set_rarity").ok(),
-                collection_amount: row.get("collection_amount").ok(),
-                level: row.get("level").ok(),
-                set_code: row.get("set_code").ok(),
-                genesys_points: row.get("genesys_points").unwrap_or(0),
-            })
-        })
-        .map_err(|e| e.to_string())?;
+fn stub_row(row: &rusqlite::Row) -> rusqlite::Result<RawStubRow> {
+    Ok(RawStubRow {
+        id: row.get("id")?,
+        name: row.get("name")?,
+        card_type: row.get("type")?,
+        has_alt_art: row.get("has_alt_art")?,
+        img_path: row.get("local_path")?,
+        image_id: row.get("image_id")?,
+        frame_type: row.get("frameType").ok(),
+        set_rarity: row.get("set_rarity").ok(),
+        collection_amount: row.get("collection_amount").ok(),
+        level: row.get("level").ok(),
+        set_code: row.get("set_code").ok(),
+        genesys_points: row.get("genesys_points").unwrap_or(0),
+        tags: row
+            .get::<_, Option<String>>("tags")?
+            .and_then(|s| serde_json::from_str::<Vec<String>>(&s).ok())
+            .unwrap_or_default(),
+    })
+}
 
-    // Collapse rows into one stub per (card_id, image_id).
-    // Alt-art cards produce one stub per artwork — each with its own image and
-    // collection total counting only sets assigned to that artwork index.
+/// Collapses rows into one stub per (card_id, image_id), in first-seen order.
+/// Alt-art cards produce one stub per artwork — each with its own image and
+/// collection total counting only sets assigned to that artwork index.
+fn collapse_stubs(
+    rows: impl Iterator<Item = rusqlite::Result<RawStubRow>>,
+) -> Result<Vec<CardStub>, String> {
     let mut order: Vec<(i64, Option<i64>)> = Vec::new();
     let mut map: HashMap<(i64, Option<i64>), CardStub> = HashMap::new();
     for r in rows {
@@ -222,6 +389,7 @@ pub fn load_card_stubs(
                 level: r.level,
                 set_code: r.set_code.clone(),
                 genesys_points: r.genesys_points,
+                tags: r.tags.clone(),
             });
         }
         let stub = map.get_mut(&key).unwrap();
@@ -236,8 +404,16 @@ pub fn load_card_stubs(
 // Detail
 // ---------------------------------------------------------------------------
 #[tauri::command]
-pub fn load_card_detail(card_id: i64, set_name: Option<String>, artwork: Option<i64>) -> Result<CardDetail, String> {
+pub fn load_card_detail(
+    card_id: i64,
+    set_name: Option<String>,
+    artwork: Option<i64>,
+    currency: Option<String>,
+) -> Result<CardDetail, String> {
     let conn = open_db()?;
+    // Prices are converted from their source currency to this one
+    let currency = currency.unwrap_or_else(|| BASE_CURRENCY.to_string());
+    let rates = ExchangeRates::load(&conn)?;
     // artwork_index: which artwork variant to show (0 = base, 1 = first alt, etc.)
     // Defaults to 0 if not provided (non-alt-art cards never send this param).
     let artwork_index = artwork.unwrap_or(0);
@@ -263,8 +439,12 @@ pub fn load_card_detail(card_id: i64, set_name: Option<String>, artwork: Option<
             c.scale,
             c.linkval,
             c.typeline,
+            c.linkmarkers,
+            c.has_effect,
+            c.treated_as,
             cs.collection_amount,
             cs.set_price,
+            COALESCE(cs.price_currency, 'USD') as price_currency,
             COALESCE(cs.artwork, 0) as artwork
         FROM cards c
         LEFT JOIN card_sets cs ON c.id = cs.card_id
@@ -303,10 +483,20 @@ pub fn load_card_detail(card_id: i64, set_name: Option<String>, artwork: Option<
                 typeline: row
                     .get::<_, Option<String>>("typeline")?
                     .and_then(|s| serde_json::from_str::<Vec<String>>(&s).ok()),
+                link_markers: row
+                    .get::<_, Option<String>>("linkmarkers")?
+                    .and_then(|s| serde_json::from_str::<Vec<String>>(&s).ok())
+                    .unwrap_or_default()
+                    .iter()
+                    .filter_map(|m| LinkMarker::parse(m))
+                    .collect(),
+                has_effect: row.get::<_, Option<i64>>("has_effect")?.map(|v| v != 0),
+                treated_as: row.get::<_, Option<String>>("treated_as")?.filter(|s| !s.is_empty()),
                 collection_amount: row.get("collection_amount").ok(),
                 set_price: row.get::<_, Option<String>>("set_price").ok().flatten()
                     .and_then(|s| s.parse::<f64>().ok())
                     .filter(|&v| v > 0.0),
+                price_currency: row.get("price_currency")?,
                 artwork: row.get::<_, i64>("artwork").unwrap_or(0),
             })
         })
@@ -334,7 +524,18 @@ pub fn load_card_detail(card_id: i64, set_name: Option<String>, artwork: Option<
             scale: r.scale,
             linkval: r.linkval,
             typeline: r.typeline.clone(),
+            link_markers: r.link_markers.clone(),
+            has_effect: r.has_effect,
+            treated_as: r.treated_as.clone(),
             sets: Vec::new(),
+            graded: Vec::new(),
+            loans: Vec::new(),
+            tags: Vec::new(),
+            notes: Vec::new(),
+            locations: Vec::new(),
+            currency: currency.clone(),
+            rate_date: None,
+            missing_rates: Vec::new(),
         });
 
         if let Some(set_code) = &r.set_code {
@@ -349,16 +550,57 @@ pub fn load_card_detail(card_id: i64, set_name: Option<String>, artwork: Option<
                 });
                 d.sets.last_mut().unwrap()
             };
+            let set_price = r.set_price.and_then(|p| {
+                let (converted, date) = rates.convert(p, &r.price_currency, &currency, &mut d.missing_rates)?;
+                d.rate_date = latest_date(d.rate_date.take(), date);
+                Some(round2(converted))
+            });
             set_ref.rarities.push(CardSetRarity {
                 rarity: r.set_rarity.clone(),
                 collection_amount: r.collection_amount,
-                set_price: r.set_price,
+                set_price,
                 artwork: r.artwork,
             });
         }
     }
 
-    detail.ok_or_else(|| format!("No card found with id {}", card_id))
+    let mut detail = detail.ok_or_else(|| format!("No card found with id {}", card_id))?;
+
+    // Slabs of the printings shown above, priced like them
+    for mut slab in load_graded(&conn, None, Some(card_id))? {
+        let shown = slab.artwork == artwork_index
+            && detail.sets.iter().any(|s| {
+                s.set_code.as_ref() == Some(&slab.set_code)
+                    && s.rarities.iter().any(|r| r.rarity.as_ref() == Some(&slab.set_rarity))
+            });
+        if !shown {
+            continue;
+        }
+        if let Some(p) = slab.price {
+            slab.price = rates
+                .convert(p, &slab.currency, &currency, &mut detail.missing_rates)
+                .map(|(converted, date)| {
+                    detail.rate_date = latest_date(detail.rate_date.take(), date);
+                    round2(converted)
+                });
+        }
+        if let (Some(cost), Some(cost_currency)) = (slab.cost, &slab.cost_currency) {
+            slab.cost = rates
+                .convert(cost, cost_currency, &currency, &mut detail.missing_rates)
+                .map(|(converted, date)| {
+                    detail.rate_date = latest_date(detail.rate_date.take(), date);
+                    round2(converted)
+                });
+            slab.cost_currency = Some(currency.clone());
+        }
+        slab.currency = currency.clone();
+        detail.graded.push(slab);
+    }
+    detail.loans = load_loans(&conn, None, Some(card_id), true, None)?;
+    detail.tags = card_tags(&conn, card_id)?;
+    detail.notes = card_notes(&conn, card_id)?;
+    detail.locations = load_stored(&conn, None, false, Some(card_id))?;
+    Ok(detail)
 }
 
 // ---------------------------------------------------------------------------
@@ -422,15 +664,42 @@ pub fn get_all_archetypes() -> Result<Vec<String>, String> {
 // ---------------------------------------------------------------------------
 // Collection
 // ---------------------------------------------------------------------------
-#[tauri::command]
-pub fn update_collection_amount(
-    card_id: i64,
-    set_code: String,
-    rarity: String,
-    artwork: i64,
+/// Sets `collection_amount` for one printing and appends the change to
+/// `collection_history`. Returns the previous amount.
+///
+/// `source` tags where the change came from ("manual", "undo", ...).
+/// Unchanged amounts are not recorded, except for undos: an undo row must
+/// always be written so the reverted entry counts as undone.
+/// Callers wanting several changes to be atomic pass a Transaction.
+pub fn apply_collection_amount(
+    conn: &Connection,
+    key: &PrintingKey,
     amount: i64,
-) -> Result<(), String> {
-    let conn = open_db()?;
+    source: &str,
+    undo_of: Option<i64>,
+) -> Result<i64, String> {
+    let old_amount: i64 = conn
+        .query_row(
+            "SELECT COALESCE(collection_amount, 0)
+             FROM card_sets
+             WHERE card_id = ?1
+               AND set_code = ?2
+               AND set_rarity = ?3
+               AND COALESCE(artwork, 0) = ?4",
+            (key.card_id, &key.set_code, &key.set_rarity, key.artwork),
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!(
+            "No printing {} / {} (artwork {}) for card {}",
+            key.set_code, key.set_rarity, key.artwork, key.card_id
+        ))?;
+
+    if old_amount == amount && undo_of.is_none() {
+        return Ok(old_amount);
+    }
+
     conn.execute(
         "UPDATE card_sets
          SET collection_amount = ?1
@@ -438,7 +707,160 @@ pub fn update_collection_amount(
            AND set_code = ?3
            AND set_rarity = ?4
            AND COALESCE(artwork, 0) = ?5",
-        (amount, card_id, set_code, rarity, artwork),
+        (amount, key.card_id, &key.set_code, &key.set_rarity, key.artwork),
+    ).map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "INSERT INTO collection_history
+            (card_id, set_code, set_rarity, artwork, old_amount, new_amount, source, undo_of)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        (key.card_id, &key.set_code, &key.set_rarity, key.artwork, old_amount, amount, source, undo_of),
     ).map_err(|e| e.to_string())?;
-    Ok(())
-}
\ No newline at end of file
+    let history_id = conn.last_insert_rowid();
+
+    // New copies fulfil the wishlist; undos restore an earlier state instead,
+    // including the wishlist entries and the ledger rows of the undone change.
+    // The ledger goes first, so clamp_lots below sees an undone purchase gone.
+    match undo_of {
+        Some(undone) => {
+            unfulfil_wishlist(conn, undone)?;
+            undo_ledger(conn, undone)?;
+        }
+        None if amount > old_amount => fulfil_wishlist(conn, key, amount - old_amount, history_id)?,
+        None => {}
+    }
+    if amount < old_amount {
+        clamp_trade_amount(conn, key, amount)?;
+        clamp_lots(conn, key, amount)?;
+        clamp_stored(conn, key, amount)?;
+    }
+
+    Ok(old_amount)
+}
+
+/// The id of the newest collection_history row of `key`: right after
+/// apply_collection_amount changed the amount, the row it wrote.
+pub fn last_change_id(conn: &Connection, key: &PrintingKey) -> Result<i64, String> {
+    conn.query_row(
+        "SELECT MAX(id) FROM collection_history
+         WHERE card_id = ?1 AND set_code = ?2 AND set_rarity = ?3 AND artwork = ?4",
+        (key.card_id, &key.set_code, &key.set_rarity, key.artwork),
+        |row| row.get::<_, Option<i64>>(0),
+    )
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| format!("No collection change recorded for card {}", key.card_id))
+}
+
+#[tauri::command]
+pub fn update_collection_amount(
+    card_id: i64,
+    set_code: String,
+    rarity: String,
+    artwork: i64,
+    amount: i64,
+) -> Result<(), String> {
+    let mut conn = open_db()?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let key = PrintingKey { card_id, set_code, set_rarity: rarity, artwork };
+    apply_collection_amount(&tx, &key, amount, "manual", None)?;
+    tx.commit().map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_db;
+
+    #[test]
+    fn stub_order_defaults_to_type() {
+        let default = stub_order(None, false).unwrap();
+        assert!(default.starts_with(&format!("{TYPE_ORDER} ASC")));
+        assert_eq!(stub_order(Some(" , "), false).unwrap(), default);
+        // Ties always end on card id and artwork so paging is stable
+        assert!(default.ends_with("c.id ASC, ci.image_id ASC"));
+    }
+
+    #[test]
+    fn stub_order_chains_keys_with_directions() {
+        assert_eq!(
+            stub_order(Some("atk:DESC, name"), false).unwrap(),
+            "c.atk DESC NULLS LAST, c.name ASC NULLS LAST, c.id ASC, ci.image_id ASC"
+        );
+    }
+
+    #[test]
+    fn stub_order_aggregates_set_codes_when_grouped() {
+        assert!(stub_order(Some("set"), false).unwrap().starts_with("cs.set_code ASC"));
+        assert!(stub_order(Some("set"), true).unwrap().starts_with("MIN(cs.set_code) ASC"));
+        assert!(stub_order(Some("set:desc"), true).unwrap().starts_with("MAX(cs.set_code) DESC"));
+    }
+
+    #[test]
+    fn stub_order_rejects_unknown_keys_and_directions() {
+        assert!(stub_order(Some("colour"), false).is_err());
+        assert!(stub_order(Some("name:up"), false).is_err());
+    }
+
+    #[test]
+    fn price_sort_converts_each_printing_to_eur() {
+        let conn = test_db();
+        conn.execute_batch(
+            "INSERT INTO cards (id, name, type)
+             VALUES (1, 'A', 'Spell Card'), (2, 'B', 'Spell Card'), (3, 'C', 'Spell Card');
+             INSERT INTO card_images (card_id, image_id) VALUES (1, 1), (2, 2), (3, 3);
+             INSERT INTO exchange_rates VALUES ('USD', 1.1, '2026-10-01');
+             INSERT INTO card_sets (card_id, set_code, set_rarity, set_price, price_currency)
+             VALUES (1, 'S-001', 'Rare', '10.00', 'EUR'),
+                    (2, 'S-002', 'Rare', '10.50', NULL),
+                    (3, 'S-003', 'Rare', '9.80', 'GBP');",
+        )
+        .unwrap();
+        // B is 10.50 USD (9.55 EUR); C has no GBP rate and counts as 9.80
+        let page = stub_page(&conn, &CardFilters::default().to_sql(), Some("price"), None, None).unwrap();
+        let ids: Vec<i64> = page.stubs.iter().map(|s| s.id).collect();
+        assert_eq!(ids, vec![2, 3, 1]);
+    }
+
+    fn paging_db() -> Connection {
+        let conn = test_db();
+        conn.execute_batch(
+            "INSERT INTO cards (id, name, type, frameType, attribute, race) VALUES
+                (10, 'A', 'Effect Monster', 'effect', 'DARK', 'Spellcaster'),
+                (20, 'B', 'Effect Monster', 'effect', 'LIGHT', 'Dragon'),
+                (30, 'C', 'Normal Monster', 'normal', 'DARK', 'Dragon'),
+                (40, 'D', 'Spell Card', 'spell', NULL, 'Normal');
+             INSERT INTO card_images (card_id, image_id) VALUES (10, 10), (20, 20), (30, 30), (30, 31), (40, 40);",
+        )
+        .unwrap();
+        conn
+    }
+
+    #[test]
+    fn stub_page_pages_by_artwork() {
+        let conn = paging_db();
+        let filter = CardFilters { category: Some("monster".into()), ..Default::default() }.to_sql();
+        let first = stub_page(&conn, &filter, Some("name"), None, Some(2)).unwrap();
+        let second = stub_page(&conn, &filter, Some("name"), Some(2), Some(2)).unwrap();
+        let stubs = |p: &CardPage| p.stubs.iter().map(|s| (s.id, s.image_id)).collect::<Vec<_>>();
+        assert_eq!(stubs(&first), vec![(10, Some(10)), (20, Some(20))]);
+        assert_eq!(stubs(&second), vec![(30, Some(30)), (30, Some(31))]);
+        assert_eq!((second.offset, second.limit), (2, 2));
+        // Past the end is an empty page, not an error
+        assert!(stub_page(&conn, &filter, Some("name"), Some(4), Some(2)).unwrap().stubs.is_empty());
+    }
+
+    #[test]
+    fn stub_page_counts_every_match() {
+        let conn = paging_db();
+        let filter = CardFilters { category: Some("monster".into()), ..Default::default() }.to_sql();
+        let page = stub_page(&conn, &filter, None, None, Some(1)).unwrap();
+        assert_eq!(page.stubs.len(), 1);
+        assert_eq!(page.total, 4);
+
+        let counts = |f: &[FacetCount]| f.iter().map(|c| (c.value.clone().unwrap(), c.count)).collect::<Vec<_>>();
+        let facet = |v: &[(&str, i64)]| v.iter().map(|(s, n)| (s.to_string(), *n)).collect::<Vec<_>>();
+        assert_eq!(counts(&page.facets.frame_types), facet(&[("effect", 2), ("normal", 2)]));
+        assert_eq!(counts(&page.facets.attributes), facet(&[("DARK", 3), ("LIGHT", 1)]));
+        assert_eq!(counts(&page.facets.races), facet(&[("Dragon", 3), ("Spellcaster", 1)]));
+    }
+}
@@ -0,0 +1,200 @@
+use rusqlite::named_params;
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::db::open_db;
+
+// ---------------------------------------------------------------------------
+// Types
+// ---------------------------------------------------------------------------
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RarityCompletion {
+    pub rarity: String,
+    pub total_cards: i64,
+    pub owned_cards: i64,
+    pub completion: f64, // percentage, 0–100
+}
+
+/// Completion of one set. A "card" here is a distinct set code, so the
+/// numbers line up with the set's checklist (alt artworks share a code).
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetCompletion {
+    pub set_name: String,
+    pub total_cards: i64,
+    pub owned_cards: i64,
+    pub completion: f64,
+    pub rarities: Vec<RarityCompletion>,
+}
+
+/// A set code of the checklist we don't own yet.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MissingCard {
+    pub set_code: String,
+    pub card_id: i64,
+    pub card_name: String,
+    pub rarities: Vec<String>,
+    /// Cheapest known price across the missing rarities
+    pub lowest_price: Option<f64>,
+}
+
+fn percent(owned: i64, total: i64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        (owned as f64 * 10000.0 / total as f64).round() / 100.0
+    }
+}
+
+// One row per (set, code, rarity) with an owned flag; shared by both queries.
+const SET_CODES_CTE: &str = "
+    WITH codes AS (
+        SELECT set_name, set_code, set_rarity,
+               MAX(COALESCE(collection_amount, 0) > 0) AS owned
+        FROM card_sets
+        WHERE set_name IS NOT NULL
+          AND set_code IS NOT NULL
+          AND set_rarity IS NOT NULL
+          AND (:set_name IS NULL OR set_name = :set_name)
+        GROUP BY set_name, set_code, set_rarity
+    )
+";
+
+// ---------------------------------------------------------------------------
+// Commands
+// ---------------------------------------------------------------------------
+
+/// Returns completion per set, and per rarity within each set, sorted by
+/// set name. Pass `set_name` to compute a single set.
+#[tauri::command]
+pub fn get_set_completion(set_name: Option<String>) -> Result<Vec<SetCompletion>, String> {
+    let conn = open_db()?;
+
+    let mut stmt = conn
+        .prepare(&format!("{SET_CODES_CTE}
+            SELECT set_name,
+                   COUNT(DISTINCT set_code) AS total,
+                   COUNT(DISTINCT CASE WHEN owned THEN set_code END) AS owned
+            FROM codes
+            GROUP BY set_name
+            ORDER BY set_name"))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(named_params! { ":set_name": set_name }, |row| {
+            let total: i64 = row.get("total")?;
+            let owned: i64 = row.get("owned")?;
+            Ok(SetCompletion {
+                set_name: row.get("set_name")?,
+                total_cards: total,
+                owned_cards: owned,
+                completion: percent(owned, total),
+                rarities: Vec::new(),
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    let mut sets: Vec<SetCompletion> = rows.collect::<Result<_, _>>().map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare(&format!("{SET_CODES_CTE}
+            SELECT set_name, set_rarity,
+                   COUNT(*) AS total,
+                   SUM(owned) AS owned
+            FROM codes
+            GROUP BY set_name, set_rarity
+            ORDER BY set_name, set_rarity"))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(named_params! { ":set_name": set_name }, |row| {
+            let total: i64 = row.get("total")?;
+            let owned: i64 = row.get("owned")?;
+            Ok((row.get::<_, String>("set_name")?, RarityCompletion {
+                rarity: row.get("set_rarity")?,
+                total_cards: total,
+                owned_cards: owned,
+                completion: percent(owned, total),
+            }))
+        })
+        .map_err(|e| e.to_string())?;
+
+    let index: HashMap<String, usize> = sets.iter()
+        .enumerate()
+        .map(|(i, s)| (s.set_name.clone(), i))
+        .collect();
+    for r in rows {
+        let (name, rarity) = r.map_err(|e| e.to_string())?;
+        if let Some(&i) = index.get(&name) {
+            sets[i].rarities.push(rarity);
+        }
+    }
+
+    Ok(sets)
+}
+
+/// Lists the set codes of `set_name` with no owned copy, in set-code order —
+/// a shopping list for completing the set. With `rarity` only that rarity
+/// counts, so e.g. a missing Secret Rare shows up even if the Ultra is owned.
+#[tauri::command]
+pub fn get_missing_set_cards(set_name: String, rarity: Option<String>) -> Result<Vec<MissingCard>, String> {
+    let conn = open_db()?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT cs.set_code, cs.card_id, c.name, cs.set_rarity,
+                    MIN(NULLIF(CAST(cs.set_price AS REAL), 0)) AS price
+             FROM card_sets cs
+             JOIN cards c ON c.id = cs.card_id
+             WHERE cs.set_name = :set_name
+               AND cs.set_code IS NOT NULL
+               AND (:rarity IS NULL OR cs.set_rarity = :rarity)
+               AND NOT EXISTS (
+                   SELECT 1 FROM card_sets o
+                   WHERE o.set_name = cs.set_name
+                     AND o.set_code = cs.set_code
+                     AND (:rarity IS NULL OR o.set_rarity = :rarity)
+                     AND COALESCE(o.collection_amount, 0) > 0
+               )
+             GROUP BY cs.set_code, cs.card_id, cs.set_rarity
+             ORDER BY cs.set_code, cs.set_rarity",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let rows = stmt
+        .query_map(named_params! { ":set_name": set_name, ":rarity": rarity }, |row| {
+            Ok((
+                row.get::<_, String>("set_code")?,
+                row.get::<_, i64>("card_id")?,
+                row.get::<_, String>("name")?,
+                row.get::<_, Option<String>>("set_rarity")?,
+                row.get::<_, Option<f64>>("price")?,
+            ))
+        })
+        .map_err(|e| e.to_string())?;
+
+    // Rows arrive sorted by set code, so each code's rarities are adjacent
+    let mut missing: Vec<MissingCard> = Vec::new();
+    for r in rows {
+        let (set_code, card_id, card_name, set_rarity, price) = r.map_err(|e| e.to_string())?;
+        let same = missing.last().is_some_and(|m| m.set_code == set_code && m.card_id == card_id);
+        if !same {
+            missing.push(MissingCard {
+                set_code,
+                card_id,
+                card_name,
+                rarities: Vec::new(),
+                lowest_price: None,
+            });
+        }
+        let m = missing.last_mut().unwrap();
+        if let Some(r) = set_rarity {
+            m.rarities.push(r);
+        }
+        m.lowest_price = match (m.lowest_price, price) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+    }
+
+    Ok(missing)
+}
@@ -1,7 +1,11 @@
+use rusqlite::{named_params, Connection, OptionalExtension};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
 
+use crate::commands::tags::tag_id_for;
+use crate::commands::validation::{check_deck, DeckViolation};
+use crate::currency::{latest_date, round2, ExchangeRates, MoneyValue, BASE_CURRENCY};
 use crate::db::{get_db_path, normalize_img_path, normalize_thumb_path, open_db};
 
 // Raw shape of the banlist_info JSON column
@@ -26,15 +30,72 @@ pub struct BanList {
     pub semi_limited: Vec<i64>,
 }
 
+/// The editable metadata of a saved deck, as sent by the frontend.
+/// `tags` replaces the deck's tags; unknown ones are created.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeckInfo {
+    pub format: Option<String>,
+    pub notes: Option<String>,
+    pub cover_card_id: Option<i64>,
+    pub folder: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// A saved deck without its cards, for deck lists.
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
-pub struct LoadedDeck {
+pub struct DeckSummary {
+    pub id: i64,
     pub name: String,
+    pub format: Option<String>,
+    pub notes: Option<String>,
+    pub cover_card_id: Option<i64>,
+    pub cover_thumb_path: Option<String>,
+    pub folder: Option<String>,
+    pub tags: Vec<String>,
+    pub main_count: i64,
+    pub extra_count: i64,
+    pub side_count: i64,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoadedDeck {
+    #[serde(flatten)]
+    pub deck: DeckSummary,
     pub main: Vec<DeckStub>,
     pub extra: Vec<DeckStub>,
     pub side: Vec<DeckStub>,
 }
 
+/// save_deck's reply: the deck as saved (None when it was refused for
+/// breaking the rules of its format) and why it is not legal there (empty
+/// when it is, or when it was saved without a format).
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SavedDeck {
+    pub deck: Option<DeckSummary>,
+    pub violations: Vec<DeckViolation>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeckValue {
+    pub amount: f64,
+    pub currency: String,
+    pub rate_date: Option<String>,
+    /// Currencies without a stored rate; their amounts are left out
+    pub missing_rates: Vec<String>,
+    pub unpriced_card_ids: Vec<i64>,
+}
+
+/// Card ids of a deck's main, extra and side sections, in order.
+type DeckIds = (Vec<i64>, Vec<i64>, Vec<i64>);
+
 /// Minimal card data needed to reconstruct a DeckEntry on the frontend.
 #[derive(Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
@@ -50,6 +111,7 @@ pub struct DeckStub {
 // ---------------------------------------------------------------------------
 // Helpers
 // ---------------------------------------------------------------------------
+/// Where decks were kept as .ydk files before they moved into the DB.
 fn decks_dir() -> std::path::PathBuf {
     let mut path = get_db_path();
     path.pop(); // remove cards.db → ressources/
@@ -57,14 +119,288 @@ fn decks_dir() -> std::path::PathBuf {
     path
 }
 
-fn deck_path(name: &str) -> std::path::PathBuf {
-    let mut p = decks_dir();
-    p.push(format!("{}.ydk", name));
-    p
+/// Splits .ydk content into its main / extra / side card ids.
+fn parse_ydk(content: &str) -> DeckIds {
+    let mut main_ids: Vec<i64> = Vec::new();
+    let mut extra_ids: Vec<i64> = Vec::new();
+    let mut side_ids: Vec<i64> = Vec::new();
+
+    #[derive(PartialEq)]
+    enum Section { None, Main, Extra, Side }
+    let mut section = Section::None;
+
+    for line in content.lines() {
+        let line = line.trim();
+        match line {
+            "#main" => { section = Section::Main;  continue; }
+            "#extra" => { section = Section::Extra; continue; }
+            "!side" => { section = Section::Side;  continue; }
+            _ if line.starts_with('#') => continue, // e.g. #created by Player
+            _ if line.is_empty() => continue,
+            _ => {}
+        }
+        if let Ok(id) = line.parse::<i64>() {
+            match section {
+                Section::Main => main_ids.push(id),
+                Section::Extra => extra_ids.push(id),
+                Section::Side => side_ids.push(id),
+                Section::None => {}
+            }
+        }
+    }
+    (main_ids, extra_ids, side_ids)
+}
+
+fn ydk_content(main_ids: &[i64], extra_ids: &[i64], side_ids: &[i64]) -> String {
+    let mut content = String::new();
+    content.push_str("#created by Player\n");
+    content.push_str("#main\n");
+    for id in main_ids  { content.push_str(&format!("{}\n", id)); }
+    content.push_str("#extra\n");
+    for id in extra_ids { content.push_str(&format!("{}\n", id)); }
+    content.push_str("!side\n");
+    for id in side_ids { content.push_str(&format!("{}\n", id)); }
+    content
+}
+
+fn check_deck_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("A deck needs a name".to_string());
+    }
+    Ok(name.to_string())
+}
+
+/// Formats a deck can be saved for: the ban list formats and Genesys.
+fn check_format(format: &str) -> Result<(), String> {
+    if format == "Genesys" || format_to_ban_key(format).is_some() {
+        Ok(())
+    } else {
+        Err(format!("Unknown format '{}'", format))
+    }
+}
+
+fn find_deck(conn: &Connection, name: &str) -> Result<Option<i64>, String> {
+    conn.query_row("SELECT id FROM decks WHERE name = ?1 COLLATE NOCASE", [name.trim()], |row| row.get(0))
+        .optional()
+        .map_err(|e| e.to_string())
+}
+
+fn deck_id(conn: &Connection, name: &str) -> Result<i64, String> {
+    find_deck(conn, name)?.ok_or_else(|| format!("No deck named '{}'", name.trim()))
+}
+
+/// `base`, or `base (2)`, `base (3)`, ... when a deck already has that name.
+fn free_deck_name(conn: &Connection, base: &str) -> Result<String, String> {
+    let mut name = base.to_string();
+    let mut n = 2;
+    while find_deck(conn, &name)?.is_some() {
+        name = format!("{} ({})", base, n);
+        n += 1;
+    }
+    Ok(name)
+}
+
+/// Replaces the cards of a deck, keeping the order within each section.
+fn write_deck_cards(conn: &Connection, deck_id: i64, main_ids: &[i64], extra_ids: &[i64], side_ids: &[i64]) -> Result<(), String> {
+    conn.execute("DELETE FROM deck_cards WHERE deck_id = ?1", [deck_id])
+        .map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare("INSERT INTO deck_cards (deck_id, section, position, card_id) VALUES (?1, ?2, ?3, ?4)")
+        .map_err(|e| e.to_string())?;
+    for (section, ids) in [("main", main_ids), ("extra", extra_ids), ("side", side_ids)] {
+        for (position, id) in ids.iter().enumerate() {
+            stmt.execute((deck_id, section, position as i64, id))
+                .map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(())
+}
+
+/// The main / extra / side card ids of a saved deck, in order.
+fn deck_card_ids(conn: &Connection, deck_id: i64) -> Result<DeckIds, String> {
+    let mut stmt = conn
+        .prepare("SELECT section, card_id FROM deck_cards WHERE deck_id = ?1 ORDER BY position")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([deck_id], |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?)))
+        .map_err(|e| e.to_string())?;
+    let (mut main_ids, mut extra_ids, mut side_ids) = (Vec::new(), Vec::new(), Vec::new());
+    for r in rows {
+        let (section, id) = r.map_err(|e| e.to_string())?;
+        match section.as_str() {
+            "main" => main_ids.push(id),
+            "extra" => extra_ids.push(id),
+            _ => side_ids.push(id),
+        }
+    }
+    Ok((main_ids, extra_ids, side_ids))
+}
+
+fn insert_deck(conn: &Connection, name: &str, format: Option<&str>) -> Result<i64, String> {
+    conn.execute("INSERT INTO decks (name, format) VALUES (?1, ?2)", (name, format))
+        .map_err(|e| e.to_string())?;
+    Ok(conn.last_insert_rowid())
+}
+
+fn set_deck_tags(conn: &Connection, deck_id: i64, tags: &[String]) -> Result<(), String> {
+    conn.execute("DELETE FROM deck_tags WHERE deck_id = ?1", [deck_id])
+        .map_err(|e| e.to_string())?;
+    for tag in tags {
+        let tag_id = tag_id_for(conn, tag)?;
+        conn.execute("INSERT OR IGNORE INTO deck_tags (deck_id, tag_id) VALUES (?1, ?2)", (deck_id, tag_id))
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Saved decks by folder then name; `id` picks one. `folder` keeps a folder
+/// and its subfolders, `tag` the decks carrying that tag (ignoring case).
+fn load_deck_summaries(
+    conn: &Connection,
+    id: Option<i64>,
+    folder: Option<&str>,
+    tag: Option<&str>,
+) -> Result<Vec<DeckSummary>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT d.id, d.name, d.format, d.notes, d.cover_card_id, d.folder,
+                    d.created_at, d.updated_at,
+                    (SELECT local_path FROM card_images ci
+                     WHERE ci.card_id = d.cover_card_id LIMIT 1) AS cover_path,
+                    (SELECT json_group_array(name) FROM (
+                        SELECT t.name FROM deck_tags dt JOIN tags t ON t.id = dt.tag_id
+                        WHERE dt.deck_id = d.id ORDER BY t.name COLLATE NOCASE
+                    )) AS tags,
+                    (SELECT COUNT(*) FROM deck_cards dc
+                     WHERE dc.deck_id = d.id AND dc.section = 'main') AS main_count,
+                    (SELECT COUNT(*) FROM deck_cards dc
+                     WHERE dc.deck_id = d.id AND dc.section = 'extra') AS extra_count,
+                    (SELECT COUNT(*) FROM deck_cards dc
+                     WHERE dc.deck_id = d.id AND dc.section = 'side') AS side_count
+             FROM decks d
+             WHERE (:id IS NULL OR d.id = :id)
+               AND (:folder IS NULL OR d.folder = :folder COLLATE NOCASE
+                    OR d.folder LIKE :folder || '/%')
+               AND (:tag IS NULL OR EXISTS (
+                    SELECT 1 FROM deck_tags dt JOIN tags t ON t.id = dt.tag_id
+                    WHERE dt.deck_id = d.id AND t.name = :tag COLLATE NOCASE))
+             ORDER BY d.folder IS NOT NULL, d.folder COLLATE NOCASE, d.name COLLATE NOCASE",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(named_params! { ":id": id, ":folder": folder, ":tag": tag }, |row| {
+            let cover_path: Option<String> = row.get("cover_path")?;
+            let tags: String = row.get("tags")?;
+            Ok(DeckSummary {
+                id: row.get("id")?,
+                name: row.get("name")?,
+                format: row.get("format")?,
+                notes: row.get("notes")?,
+                cover_card_id: row.get("cover_card_id")?,
+                cover_thumb_path: normalize_thumb_path(cover_path.as_ref()),
+                folder: row.get("folder")?,
+                tags: serde_json::from_str(&tags).unwrap_or_default(),
+                main_count: row.get("main_count")?,
+                extra_count: row.get("extra_count")?,
+                side_count: row.get("side_count")?,
+                created_at: row.get("created_at")?,
+                updated_at: row.get("updated_at")?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<_, _>>().map_err(|e| e.to_string())
+}
+
+fn load_deck_summary(conn: &Connection, id: i64) -> Result<DeckSummary, String> {
+    load_deck_summaries(conn, Some(id), None, None)?
+        .pop()
+        .ok_or_else(|| format!("No deck with id {}", id))
+}
+
+/// Outcome of migrate_ydk_decks, kept for the frontend (get_ydk_migration).
+/// Skipped files stay where they were, with the reason, so the next launch
+/// tries them again. `error` is set when the decks folder itself failed.
+#[derive(Serialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct YdkMigration {
+    pub migrated: usize,
+    pub skipped: Vec<SkippedYdk>,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SkippedYdk {
+    pub file: String,
+    pub reason: String,
+}
+
+// Imports one .ydk file, then moves it to `migrated`. The file is only moved
+// once the deck is committed, so a failed import leaves it to be retried.
+// A failed move after the commit keeps the deck and reports the file, which
+// the next launch would import again.
+fn migrate_ydk_file(conn: &mut Connection, path: &std::path::Path, migrated: &std::path::Path) -> Result<(), String> {
+    let bytes = fs::read(path).map_err(|e| e.to_string())?;
+    let content = String::from_utf8(bytes).map_err(|_| "not a UTF-8 text file".to_string())?;
+    let file_name = path.file_name().ok_or("not a file")?;
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("Deck");
+    let (main_ids, extra_ids, side_ids) = parse_ydk(&content);
+
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let name = free_deck_name(&tx, &check_deck_name(stem).unwrap_or_else(|_| "Deck".to_string()))?;
+    let id = insert_deck(&tx, &name, None)?;
+    write_deck_cards(&tx, id, &main_ids, &extra_ids, &side_ids)?;
+    tx.commit().map_err(|e| e.to_string())?;
+    fs::rename(path, migrated.join(file_name))
+        .map_err(|e| format!("imported as \"{}\", but the file could not be moved to migrated/: {}", name, e))
+}
+
+fn migrate_ydk_dir(conn: &mut Connection, dir: &std::path::Path) -> Result<YdkMigration, String> {
+    let mut report = YdkMigration::default();
+    if !dir.exists() {
+        return Ok(report);
+    }
+    let mut files: Vec<std::path::PathBuf> = fs::read_dir(dir)
+        .map_err(|e| e.to_string())?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|p| p.is_file() && p.extension().and_then(|e| e.to_str()) == Some("ydk"))
+        .collect();
+    if files.is_empty() {
+        return Ok(report);
+    }
+    files.sort();
+    let migrated = dir.join("migrated");
+    fs::create_dir_all(&migrated).map_err(|e| e.to_string())?;
+
+    for path in files {
+        match migrate_ydk_file(conn, &path, &migrated) {
+            Ok(()) => report.migrated += 1,
+            Err(reason) => report.skipped.push(SkippedYdk { file: path.display().to_string(), reason }),
+        }
+    }
+    Ok(report)
+}
+
+/// Moves the .ydk files of `ressources/decks/` into the DB, once: each
+/// imported file goes to `ressources/decks/migrated/`, so later launches
+/// skip it. A name already taken gets a " (2)" suffix. Each file is
+/// imported in its own transaction; one that can't be read, parsed or
+/// moved is skipped and reported rather than stopping the rest.
+pub fn migrate_ydk_decks(conn: &mut Connection) -> YdkMigration {
+    migrate_ydk_dir(conn, &decks_dir())
+        .unwrap_or_else(|e| YdkMigration { error: Some(e), ..Default::default() })
+}
+
+/// The report of the .ydk migration run at startup, so the UI can name
+/// deck files that were not imported.
+#[tauri::command]
+pub fn get_ydk_migration(report: tauri::State<'_, YdkMigration>) -> YdkMigration {
+    report.inner().clone()
 }
 
 /// Fetch minimal card data for a list of IDs in a single query.
-fn fetch_stubs_by_ids(ids: &[i64]) -> Result<HashMap<i64, DeckStub>, String> {
+pub fn fetch_stubs_by_ids(ids: &[i64]) -> Result<HashMap<i64, DeckStub>, String> {
     if ids.is_empty() {
         return Ok(HashMap::new());
     }
@@ -137,6 +473,7 @@ pub fn get_ban_list() -> Result<BanList, String> {
 
 /// Returns a map of card_id → total collection amount for every card that has
 /// at least 1 copy owned. Cards with 0 owned are omitted (treat missing as 0).
+/// Only raw copies count; graded slabs are never played from.
 #[tauri::command]
 pub fn get_collection_amounts() -> Result<std::collections::HashMap<i64, i64>, String> {
     let conn = open_db()?;
@@ -161,80 +498,322 @@ pub fn get_collection_amounts() -> Result<std::collections::HashMap<i64, i64>, S
     Ok(map)
 }
 
-/// Returns the total monetary value of the collection.
-/// Computed as SUM(CAST(set_price AS REAL) * collection_amount) across all
-/// owned rows. set_price is stored as TEXT in the DB, so we cast it here.
+/// Returns the total monetary value of the collection in `currency`
+/// (default EUR), with the date of the exchange rate used.
+/// Computed as SUM(CAST(set_price AS REAL) * collection_amount) per source
+/// currency across all owned rows, then converted with the stored rates.
+/// set_price is stored as TEXT in the DB, so we cast it here.
+/// Unopened sealed products count at quantity * market_price, graded
+/// slabs at their own price.
 /// Returns 0.0 if nothing is owned or no prices are set.
 #[tauri::command]
-pub fn get_collection_value() -> Result<f64, String> {
+pub fn get_collection_value(currency: Option<String>) -> Result<MoneyValue, String> {
     let conn = open_db()?;
-    let value: f64 = conn
-        .query_row(
-            "SELECT COALESCE(SUM(CAST(set_price AS REAL) * collection_amount), 0.0)
+    let currency = currency.unwrap_or_else(|| BASE_CURRENCY.to_string());
+    let rates = ExchangeRates::load(&conn)?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT COALESCE(price_currency, 'USD'),
+                    SUM(CAST(set_price AS REAL) * collection_amount)
              FROM card_sets
              WHERE collection_amount > 0
                AND set_price IS NOT NULL
-               AND CAST(set_price AS REAL) > 0",
-            [],
-            |row| row.get::<_, f64>(0),
+               AND CAST(set_price AS REAL) > 0
+             GROUP BY 1
+             UNION ALL
+             SELECT currency, SUM(market_price * quantity)
+             FROM sealed_products
+             WHERE quantity > 0 AND market_price > 0
+             GROUP BY 1
+             UNION ALL
+             SELECT currency, SUM(price)
+             FROM graded_cards
+             WHERE price > 0
+             GROUP BY 1",
         )
         .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, f64>(1)?)))
+        .map_err(|e| e.to_string())?;
+
+    let mut value = MoneyValue { amount: 0.0, currency: currency.clone(), rate_date: None, missing_rates: Vec::new() };
+    for r in rows {
+        let (source, sum) = r.map_err(|e| e.to_string())?;
+        if let Some((converted, date)) = rates.convert(sum, &source, &currency, &mut value.missing_rates) {
+            value.amount += converted;
+            value.rate_date = latest_date(value.rate_date, date);
+        }
+    }
+    value.amount = round2(value.amount);
     Ok(value)
 }
 
-
+/// Market value of a deck in `currency` (default EUR): every copy is priced
+/// at the cheapest known printing of its card. Cards with no price at all
+/// are listed in `unpriced_card_ids` and count as 0.
 #[tauri::command]
-pub fn list_decks() -> Result<Vec<String>, String> {
-    let dir = decks_dir();
-    if !dir.exists() {
-        return Ok(Vec::new());
+pub fn get_deck_value(
+    main_ids: Vec<i64>,
+    extra_ids: Vec<i64>,
+    side_ids: Vec<i64>,
+    currency: Option<String>,
+) -> Result<DeckValue, String> {
+    let conn = open_db()?;
+    let currency = currency.unwrap_or_else(|| BASE_CURRENCY.to_string());
+    let rates = ExchangeRates::load(&conn)?;
+
+    let mut counts: HashMap<i64, i64> = HashMap::new();
+    for id in main_ids.iter().chain(&extra_ids).chain(&side_ids) {
+        *counts.entry(*id).or_insert(0) += 1;
     }
 
-    let mut names = Vec::new();
-    for entry in fs::read_dir(&dir).map_err(|e| e.to_string())? {
-        let entry = entry.map_err(|e| e.to_string())?;
-        let path = entry.path();
-        if path.extension().and_then(|e| e.to_str()) == Some("ydk") {
-            if let Some(stem) = path.file_stem().and_then(|s| s.to_str()) {
-                names.push(stem.to_string());
+    let mut stmt = conn
+        .prepare(
+            "SELECT CAST(set_price AS REAL), COALESCE(price_currency, 'USD')
+             FROM card_sets
+             WHERE card_id = ?1 AND CAST(set_price AS REAL) > 0",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let mut value = DeckValue {
+        amount: 0.0,
+        currency: currency.clone(),
+        rate_date: None,
+        missing_rates: Vec::new(),
+        unpriced_card_ids: Vec::new(),
+    };
+    let mut ids: Vec<&i64> = counts.keys().collect();
+    ids.sort();
+    for id in ids {
+        let rows = stmt
+            .query_map([id], |row| Ok((row.get::<_, f64>(0)?, row.get::<_, String>(1)?)))
+            .map_err(|e| e.to_string())?;
+        // Prices of one card may come in different currencies: convert first
+        let mut cheapest: Option<(f64, Option<String>)> = None;
+        for r in rows {
+            let (price, source) = r.map_err(|e| e.to_string())?;
+            let Some(converted) = rates.convert(price, &source, &currency, &mut value.missing_rates) else {
+                continue;
+            };
+            if cheapest.as_ref().is_none_or(|(best, _)| converted.0 < *best) {
+                cheapest = Some(converted);
+            }
+        }
+        match cheapest {
+            Some((price, date)) => {
+                value.amount += price * counts[id] as f64;
+                value.rate_date = latest_date(value.rate_date, date);
             }
+            None => value.unpriced_card_ids.push(*id),
         }
     }
-    names.sort();
-    Ok(names)
+    value.amount = round2(value.amount);
+    Ok(value)
 }
 
-/// Saves a deck as `ressources/decks/{name}.ydk`. Overwrites if it already exists.
+/// Saved decks by folder then name. `folder` narrows to a folder and its
+/// subfolders, `tag` to the decks carrying that tag.
+#[tauri::command]
+pub fn list_decks(folder: Option<String>, tag: Option<String>) -> Result<Vec<DeckSummary>, String> {
+    let conn = open_db()?;
+    let folder = folder.map(|f| f.trim().trim_matches('/').to_string()).filter(|f| !f.is_empty());
+    let tag = tag.map(|t| t.trim().to_string()).filter(|t| !t.is_empty());
+    load_deck_summaries(&conn, None, folder.as_deref(), tag.as_deref())
+}
+
+/// Saves the cards of deck `name`, creating the deck or replacing the cards
+/// of the one with that name (ignoring case); its other metadata stays.
+/// With a `format`, the format is stored with the deck and the deck is
+/// checked against it (see validation::check_deck). An illegal deck is not
+/// saved: the violations come back with no deck. With `draft` it is saved
+/// anyway, and the violations still come back with it.
 #[tauri::command]
 pub fn save_deck(
     name: String,
     main_ids: Vec<i64>,
     extra_ids: Vec<i64>,
     side_ids: Vec<i64>,
-) -> Result<(), String> {
-    let dir = decks_dir();
-    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
-
-    let mut content = String::new();
-    content.push_str("#created by Player\n");
-    content.push_str("#main\n");
-    for id in &main_ids  { content.push_str(&format!("{}\n", id)); }
-    content.push_str("#extra\n");
-    for id in &extra_ids { content.push_str(&format!("{}\n", id)); }
-    content.push_str("!side\n");
-    for id in &side_ids { content.push_str(&format!("{}\n", id)); }
+    format: Option<String>,
+    draft: Option<bool>,
+) -> Result<SavedDeck, String> {
+    let name = check_deck_name(&name)?;
+    let violations = match &format {
+        Some(format) => check_deck(format, &main_ids, &extra_ids, &side_ids)?,
+        None => Vec::new(),
+    };
+    if !violations.is_empty() && !draft.unwrap_or(false) {
+        return Ok(SavedDeck { deck: None, violations });
+    }
 
-    fs::write(deck_path(&name), content).map_err(|e| e.to_string())
+    let mut conn = open_db()?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let id = match find_deck(&tx, &name)? {
+        Some(id) => {
+            tx.execute(
+                "UPDATE decks SET format = COALESCE(?1, format), updated_at = datetime('now') WHERE id = ?2",
+                (&format, id),
+            )
+            .map_err(|e| e.to_string())?;
+            id
+        }
+        None => insert_deck(&tx, &name, format.as_deref())?,
+    };
+    write_deck_cards(&tx, id, &main_ids, &extra_ids, &side_ids)?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(SavedDeck { deck: Some(load_deck_summary(&conn, id)?), violations })
 }
 
-/// Deletes `ressources/decks/{name}.ydk`.
+/// Deletes a saved deck with its cards and tags.
 #[tauri::command]
 pub fn delete_deck(name: String) -> Result<(), String> {
-    let path = deck_path(&name);
-    if path.exists() {
-        fs::remove_file(&path).map_err(|e| e.to_string())?;
+    let mut conn = open_db()?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let id = deck_id(&tx, &name)?;
+    tx.execute("DELETE FROM deck_cards WHERE deck_id = ?1", [id])
+        .map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM deck_tags WHERE deck_id = ?1", [id])
+        .map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM decks WHERE id = ?1", [id])
+        .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())
+}
+
+/// Renames a deck. Changing only the case of the name is allowed.
+#[tauri::command]
+pub fn rename_deck(name: String, new_name: String) -> Result<DeckSummary, String> {
+    let conn = open_db()?;
+    let id = deck_id(&conn, &name)?;
+    let new_name = check_deck_name(&new_name)?;
+    if find_deck(&conn, &new_name)?.is_some_and(|other| other != id) {
+        return Err(format!("A deck named '{}' already exists", new_name));
     }
-    Ok(())
+    conn.execute(
+        "UPDATE decks SET name = ?1, updated_at = datetime('now') WHERE id = ?2",
+        (&new_name, id),
+    )
+    .map_err(|e| e.to_string())?;
+    load_deck_summary(&conn, id)
+}
+
+/// Copies a deck with its cards, metadata and tags under `new_name`
+/// (default "<name> copy", numbered when taken).
+#[tauri::command]
+pub fn duplicate_deck(name: String, new_name: Option<String>) -> Result<DeckSummary, String> {
+    let mut conn = open_db()?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let id = deck_id(&tx, &name)?;
+    let new_name = match new_name {
+        Some(n) => {
+            let n = check_deck_name(&n)?;
+            if find_deck(&tx, &n)?.is_some() {
+                return Err(format!("A deck named '{}' already exists", n));
+            }
+            n
+        }
+        None => {
+            let original: String = tx
+                .query_row("SELECT name FROM decks WHERE id = ?1", [id], |row| row.get(0))
+                .map_err(|e| e.to_string())?;
+            free_deck_name(&tx, &format!("{} copy", original))?
+        }
+    };
+    tx.execute(
+        "INSERT INTO decks (name, format, notes, cover_card_id, folder)
+         SELECT ?1, format, notes, cover_card_id, folder FROM decks WHERE id = ?2",
+        (&new_name, id),
+    )
+    .map_err(|e| e.to_string())?;
+    let copy = tx.last_insert_rowid();
+    tx.execute(
+        "INSERT INTO deck_cards (deck_id, section, position, card_id)
+         SELECT ?1, section, position, card_id FROM deck_cards WHERE deck_id = ?2",
+        (copy, id),
+    )
+    .map_err(|e| e.to_string())?;
+    tx.execute(
+        "INSERT INTO deck_tags (deck_id, tag_id) SELECT ?1, tag_id FROM deck_tags WHERE deck_id = ?2",
+        (copy, id),
+    )
+    .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    load_deck_summary(&conn, copy)
+}
+
+/// Replaces the metadata of a deck: format, notes, cover card, folder and
+/// tags. The cards are untouched.
+#[tauri::command]
+pub fn update_deck_info(name: String, info: DeckInfo) -> Result<DeckSummary, String> {
+    let format = info.format.map(|f| f.trim().to_string()).filter(|f| !f.is_empty());
+    if let Some(format) = &format {
+        check_format(format)?;
+    }
+    let notes = info.notes.map(|n| n.trim().to_string()).filter(|n| !n.is_empty());
+    // Folders are paths like 'Locals/2024'; stray slashes are dropped
+    let folder = info.folder.and_then(|f| {
+        let parts: Vec<&str> = f.split('/').map(str::trim).filter(|p| !p.is_empty()).collect();
+        (!parts.is_empty()).then(|| parts.join("/"))
+    });
+
+    let mut conn = open_db()?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let id = deck_id(&tx, &name)?;
+    if let Some(card_id) = info.cover_card_id {
+        let exists = tx
+            .prepare("SELECT 1 FROM cards WHERE id = ?1")
+            .and_then(|mut stmt| stmt.exists([card_id]))
+            .map_err(|e| e.to_string())?;
+        if !exists {
+            return Err(format!("No card found with id {}", card_id));
+        }
+    }
+    tx.execute(
+        "UPDATE decks
+         SET format = ?1, notes = ?2, cover_card_id = ?3, folder = ?4, updated_at = datetime('now')
+         WHERE id = ?5",
+        (&format, &notes, info.cover_card_id, &folder, id),
+    )
+    .map_err(|e| e.to_string())?;
+    set_deck_tags(&tx, id, &info.tags)?;
+    tx.commit().map_err(|e| e.to_string())?;
+    load_deck_summary(&conn, id)
+}
+
+/// Imports a .ydk file as a new deck named `name` (default: the file name).
+#[tauri::command]
+pub fn import_deck_ydk(path: String, name: Option<String>) -> Result<DeckSummary, String> {
+    let content = fs::read_to_string(&path)
+        .map_err(|e| format!("Could not read '{}': {}", path, e))?;
+    let name = match name {
+        Some(n) => n,
+        None => std::path::Path::new(&path)
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or_default()
+            .to_string(),
+    };
+    let name = check_deck_name(&name)?;
+    let (main_ids, extra_ids, side_ids) = parse_ydk(&content);
+
+    let mut conn = open_db()?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    if find_deck(&tx, &name)?.is_some() {
+        return Err(format!("A deck named '{}' already exists", name));
+    }
+    let id = insert_deck(&tx, &name, None)?;
+    write_deck_cards(&tx, id, &main_ids, &extra_ids, &side_ids)?;
+    tx.commit().map_err(|e| e.to_string())?;
+    load_deck_summary(&conn, id)
+}
+
+/// Writes a saved deck to `path` as a .ydk file.
+#[tauri::command]
+pub fn export_deck_ydk(name: String, path: String) -> Result<(), String> {
+    let conn = open_db()?;
+    let id = deck_id(&conn, &name)?;
+    let (main_ids, extra_ids, side_ids) = deck_card_ids(&conn, id)?;
+    fs::write(&path, ydk_content(&main_ids, &extra_ids, &side_ids))
+        .map_err(|e| format!("Could not write '{}': {}", path, e))
 }
 
 /// Maps a format display name to the banlist_info JSON key that governs it.
@@ -252,17 +831,13 @@ fn format_to_ban_key(format: &str) -> Option<&'static str> {
     }
 }
 
-/// Reads `banlist_info` from every card in the DB that is legal in the given
-/// format, extracts the restriction status, and overwrites `banlist.json`.
-/// Cards not in the format's card pool are simply absent from the file
+/// Builds the ban list of a format from the `banlist_info` of every card in
+/// the format's card pool. Cards outside the pool are simply absent
 /// (treated as unrestricted within the format — the formats column handles pool).
-#[tauri::command]
-pub fn sync_banlist_from_db(format: String) -> Result<(), String> {
-    let ban_key = format_to_ban_key(&format)
+pub fn format_ban_list(conn: &Connection, format: &str) -> Result<BanList, String> {
+    let ban_key = format_to_ban_key(format)
         .ok_or_else(|| format!("Unknown format '{}'. Supported: TCG, OCG, Master Duel, GOAT, OCG GOAT, Edison, Common Charity, Duel Links", format))?;
 
-    let conn = open_db()?;
-
     // Only consider cards that exist in this format's card pool
     let sql = format!(
         "SELECT id, banlist_info FROM cards
@@ -304,6 +879,15 @@ pub fn sync_banlist_from_db(format: String) -> Result<(), String> {
     ban.forbidden.sort();
     ban.limited.sort();
     ban.semi_limited.sort();
+    Ok(ban)
+}
+
+/// Overwrites `banlist.json` with the ban list of the given format, built
+/// from the DB by format_ban_list.
+#[tauri::command]
+pub fn sync_banlist_from_db(format: String) -> Result<(), String> {
+    let conn = open_db()?;
+    let ban = format_ban_list(&conn, &format)?;
 
     let mut path = get_db_path();
     path.pop();
@@ -340,39 +924,14 @@ pub fn get_genesys_points() -> Result<std::collections::HashMap<i64, i64>, Strin
     Ok(map)
 }
 
-/// Loads a saved .ydk file and resolves card IDs to full DeckStub objects.
+/// Loads a saved deck with its metadata and resolves card IDs to full
+/// DeckStub objects.
 #[tauri::command]
 pub fn load_deck(name: String) -> Result<LoadedDeck, String> {
-    let content = fs::read_to_string(deck_path(&name))
-        .map_err(|e| format!("Could not read deck '{}': {}", name, e))?;
-
-    let mut main_ids: Vec<i64> = Vec::new();
-    let mut extra_ids: Vec<i64> = Vec::new();
-    let mut side_ids: Vec<i64> = Vec::new();
-
-    #[derive(PartialEq)]
-    enum Section { None, Main, Extra, Side }
-    let mut section = Section::None;
-
-    for line in content.lines() {
-        let line = line.trim();
-        match line {
-            "#main" => { section = Section::Main;  continue; }
-            "#extra" => { section = Section::Extra; continue; }
-            "!side" => { section = Section::Side;  continue; }
-            _ if line.starts_with('#') => continue, // e.g. #created by Player
-            _ if line.is_empty() => continue,
-            _ => {}
-        }
-        if let Ok(id) = line.parse::<i64>() {
-            match section {
-                Section::Main => main_ids.push(id),
-                Section::Extra => extra_ids.push(id),
-                Section::Side => side_ids.push(id),
-                Section::None => {}
-            }
-        }
-    }
+    let conn = open_db()?;
+    let id = deck_id(&conn, &name)?;
+    let deck = load_deck_summary(&conn, id)?;
+    let (main_ids, extra_ids, side_ids) = deck_card_ids(&conn, id)?;
 
     // Single DB round-trip for all unique IDs across all sections
     let all_ids: Vec<i64> = {
@@ -387,9 +946,66 @@ pub fn load_deck(name: String) -> Result<LoadedDeck, String> {
     let map = fetch_stubs_by_ids(&all_ids)?;
 
     Ok(LoadedDeck {
-        name,
+        deck,
         main: resolve_ids(&main_ids, &map),
         extra: resolve_ids(&extra_ids, &map),
         side: resolve_ids(&side_ids, &map),
     })
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_db;
+
+    #[test]
+    fn parse_ydk_reads_each_section() {
+        let content = "#created by Player\n#main\n89631139\n89631139\n\n#extra\n1861629\n!side\n14558127\nnot an id\n";
+        let (main, extra, side) = parse_ydk(content);
+        assert_eq!(main, vec![89631139, 89631139]);
+        assert_eq!(extra, vec![1861629]);
+        assert_eq!(side, vec![14558127]);
+    }
+
+    #[test]
+    fn parse_ydk_ignores_ids_before_a_section() {
+        let (main, extra, side) = parse_ydk("89631139\n#main\n83764718\n");
+        assert_eq!(main, vec![83764718]);
+        assert!(extra.is_empty() && side.is_empty());
+    }
+
+    #[test]
+    fn ydk_content_round_trips() {
+        let deck = (vec![1, 2, 2], vec![3], vec![4, 5]);
+        let content = ydk_content(&deck.0, &deck.1, &deck.2);
+        assert!(content.starts_with("#created by Player\n#main\n"));
+        assert_eq!(parse_ydk(&content), deck);
+    }
+
+    #[test]
+    fn migration_skips_bad_files_and_keeps_the_rest() {
+        let dir = std::env::temp_dir().join(format!("ydk-migration-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("Good.ydk"), "#main\n1\n1\n#extra\n!side\n").unwrap();
+        fs::write(dir.join("Bad.ydk"), [0xff, 0xfe, 0x00]).unwrap();
+
+        let mut conn = test_db();
+        let report = migrate_ydk_dir(&mut conn, &dir).unwrap();
+        assert_eq!(report.migrated, 1);
+        assert_eq!(report.skipped.len(), 1);
+        assert_eq!(report.skipped[0].file, dir.join("Bad.ydk").display().to_string());
+
+        let names: Vec<String> = conn
+            .prepare("SELECT name FROM decks")
+            .unwrap()
+            .query_map([], |r| r.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(names, vec!["Good".to_string()]);
+        assert!(dir.join("migrated/Good.ydk").exists());
+        assert!(dir.join("Bad.ydk").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,109 @@
+use serde::Serialize;
+use std::fs;
+
+use crate::db::open_db;
+use crate::filters::CardFilters;
+
+// ---------------------------------------------------------------------------
+// Types
+// ---------------------------------------------------------------------------
+
+/// One owned card_sets row as written to the export file.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportRow {
+    pub card_name: String,
+    pub set_code: Option<String>,
+    pub set_name: Option<String>,
+    pub rarity: Option<String>,
+    pub artwork: i64,
+    pub quantity: i64,
+    pub unit_price: Option<f64>,
+    pub line_value: Option<f64>,
+    pub currency: String, // source currency of the price, as stored
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Writes `rows` to `path` as "csv" or "json". Also used by the trade list export.
+pub fn write_rows(path: &str, format: &str, rows: &[ExportRow]) -> Result<(), String> {
+    match format {
+        "csv" => {
+            let mut writer = csv::Writer::from_path(path)
+                .map_err(|e| format!("Could not write '{}': {}", path, e))?;
+            for row in rows {
+                writer.serialize(row).map_err(|e| e.to_string())?;
+            }
+            writer.flush().map_err(|e| e.to_string())?;
+        }
+        "json" => {
+            let json = serde_json::to_string_pretty(rows).map_err(|e| e.to_string())?;
+            fs::write(path, json).map_err(|e| format!("Could not write '{}': {}", path, e))?;
+        }
+        _ => return Err(format!("Unknown export format '{}'. Supported: csv, json", format)),
+    }
+
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Commands
+// ---------------------------------------------------------------------------
+
+/// Writes every owned printing matching `filters` (the same filters
+/// load_card_stubs takes, so the current grid view can be exported) to `path`.
+/// `format` is "csv" or "json". Returns the number of rows written.
+#[tauri::command]
+pub fn export_collection(
+    path: String,
+    format: String,
+    filters: Option<CardFilters>,
+) -> Result<usize, String> {
+    let conn = open_db()?;
+    let filter = filters.unwrap_or_default().to_sql();
+
+    let sql = format!("
+        SELECT
+            c.name,
+            cs.set_code,
+            cs.set_name,
+            cs.set_rarity,
+            COALESCE(cs.artwork, 0) as artwork,
+            cs.collection_amount,
+            cs.set_price,
+            COALESCE(cs.price_currency, 'USD') as price_currency
+        FROM card_sets cs
+        JOIN cards c ON c.id = cs.card_id
+        WHERE cs.collection_amount > 0
+          AND {}
+        ORDER BY c.name, cs.set_code, cs.set_rarity, artwork
+    ", filter.clause);
+
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(filter.params_ref().as_slice(), |row| {
+            let quantity: i64 = row.get("collection_amount")?;
+            // set_price is TEXT in the DB; "0" means "no price known"
+            let unit_price = row.get::<_, Option<String>>("set_price")?
+                .and_then(|s| s.parse::<f64>().ok())
+                .filter(|&v| v > 0.0);
+            Ok(ExportRow {
+                card_name: row.get("name")?,
+                set_code: row.get("set_code")?,
+                set_name: row.get("set_name")?,
+                rarity: row.get("set_rarity")?,
+                artwork: row.get("artwork")?,
+                quantity,
+                unit_price,
+                line_value: unit_price.map(|p| (p * quantity as f64 * 100.0).round() / 100.0),
+                currency: row.get("price_currency")?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    let rows: Vec<ExportRow> = rows.collect::<Result<_, _>>().map_err(|e| e.to_string())?;
+
+    write_rows(&path, &format, &rows)?;
+    Ok(rows.len())
+}
@@ -0,0 +1,217 @@
+use rusqlite::{named_params, Connection, OptionalExtension};
+use serde::Deserialize;
+
+use crate::commands::collection::apply_collection_amount;
+use crate::commands::profit::{owned_amount, return_lot_copy, take_lot_copy};
+use crate::currency::BASE_CURRENCY;
+use crate::db::open_db;
+use crate::models::{GradedCard, PrintingKey};
+
+// ---------------------------------------------------------------------------
+// Types
+// ---------------------------------------------------------------------------
+
+/// The editable fields of a slab, as sent by the frontend. `grade` is kept
+/// as text since companies grade differently ("10", "9.5", "Pristine 10").
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GradedCardFields {
+    #[serde(flatten)]
+    pub printing: PrintingKey,
+    pub company: String,
+    pub grade: String,
+    pub cert_number: Option<String>,
+    pub price: Option<f64>,
+    pub currency: Option<String>,
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Slabs ordered by printing and grade; `id` picks one, `card_id` one card.
+pub fn load_graded(conn: &Connection, id: Option<i64>, card_id: Option<i64>) -> Result<Vec<GradedCard>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT g.id, g.card_id, g.set_code, g.set_rarity, g.artwork, g.company, g.grade,
+                    g.cert_number, g.price, g.currency, g.added_at,
+                    a.unit_cost AS cost, a.currency AS cost_currency
+             FROM graded_cards g
+             LEFT JOIN acquisitions a ON a.id = g.lot_id
+             WHERE (:id IS NULL OR g.id = :id)
+               AND (:card_id IS NULL OR g.card_id = :card_id)
+             ORDER BY g.card_id, g.set_code, g.set_rarity, g.artwork, g.company, g.grade DESC, g.id",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(named_params! { ":id": id, ":card_id": card_id }, |row| {
+            Ok(GradedCard {
+                id: row.get("id")?,
+                card_id: row.get("card_id")?,
+                set_code: row.get("set_code")?,
+                set_rarity: row.get("set_rarity")?,
+                artwork: row.get("artwork")?,
+                company: row.get("company")?,
+                grade: row.get("grade")?,
+                cert_number: row.get("cert_number")?,
+                price: row.get("price")?,
+                currency: row.get("currency")?,
+                cost: row.get("cost")?,
+                cost_currency: row.get("cost_currency")?,
+                added_at: row.get("added_at")?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+
+    let mut slabs = Vec::new();
+    for r in rows {
+        slabs.push(r.map_err(|e| e.to_string())?);
+    }
+    Ok(slabs)
+}
+
+fn load_slab(conn: &Connection, id: i64) -> Result<GradedCard, String> {
+    load_graded(conn, Some(id), None)?
+        .pop()
+        .ok_or_else(|| format!("No graded card with id {}", id))
+}
+
+/// Trims the text fields and checks the printing exists and the
+/// certificate number is not already on another slab of the same company.
+fn validate(conn: &Connection, slab: GradedCardFields, id: Option<i64>) -> Result<GradedCardFields, String> {
+    let company = slab.company.trim().to_string();
+    let grade = slab.grade.trim().to_string();
+    if company.is_empty() || grade.is_empty() {
+        return Err("A graded card needs a grading company and a grade".to_string());
+    }
+    if slab.price.is_some_and(|p| p < 0.0) {
+        return Err("Price cannot be negative".to_string());
+    }
+    owned_amount(conn, &slab.printing)?;
+
+    let cert_number = slab.cert_number.map(|c| c.trim().to_string()).filter(|c| !c.is_empty());
+    if let Some(cert) = &cert_number {
+        let taken = conn
+            .query_row(
+                "SELECT id FROM graded_cards WHERE company = ?1 AND cert_number = ?2 AND id IS NOT ?3",
+                (&company, cert, id),
+                |row| row.get::<_, i64>(0),
+            )
+            .optional()
+            .map_err(|e| e.to_string())?;
+        if taken.is_some() {
+            return Err(format!("{} certificate {} is already recorded", company, cert));
+        }
+    }
+    Ok(GradedCardFields {
+        company,
+        grade,
+        cert_number,
+        currency: slab.currency.or_else(|| Some(BASE_CURRENCY.to_string())),
+        ..slab
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Commands
+// ---------------------------------------------------------------------------
+
+/// Records a slab. With `from_raw`, one raw copy of the printing goes into
+/// the slab (the card came back from grading), so collection_amount drops
+/// by one; that fails when no raw copy is owned. The copy's purchase cost,
+/// if any, moves from its lot to the slab.
+#[tauri::command]
+pub fn add_graded_card(slab: GradedCardFields, from_raw: Option<bool>) -> Result<GradedCard, String> {
+    let mut conn = open_db()?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let s = validate(&tx, slab, None)?;
+    let key = &s.printing;
+
+    let mut lot_id = None;
+    if from_raw.unwrap_or(false) {
+        let owned = owned_amount(&tx, key)?;
+        if owned == 0 {
+            return Err("No raw copy of this printing to grade".to_string());
+        }
+        // Before the amount drops, so clamp_lots does not write the cost off
+        lot_id = take_lot_copy(&tx, key)?;
+        apply_collection_amount(&tx, key, owned - 1, "graded", None)?;
+    }
+    tx.execute(
+        "INSERT INTO graded_cards
+            (card_id, set_code, set_rarity, artwork, company, grade, cert_number, price, currency, lot_id)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        (
+            key.card_id, &key.set_code, &key.set_rarity, key.artwork,
+            &s.company, &s.grade, &s.cert_number, s.price, &s.currency, lot_id,
+        ),
+    )
+    .map_err(|e| e.to_string())?;
+    let id = tx.last_insert_rowid();
+
+    tx.commit().map_err(|e| e.to_string())?;
+    load_slab(&conn, id)
+}
+
+/// Replaces everything about a slab except its id. Raw copies are untouched.
+/// Moving the slab to another printing drops its link to a purchase lot.
+#[tauri::command]
+pub fn update_graded_card(id: i64, slab: GradedCardFields) -> Result<GradedCard, String> {
+    let conn = open_db()?;
+    let s = validate(&conn, slab, Some(id))?;
+    let key = &s.printing;
+    let updated = conn
+        .execute(
+            "UPDATE graded_cards
+             SET lot_id = CASE WHEN card_id = ?1 AND set_code = ?2 AND set_rarity = ?3
+                                    AND artwork = ?4 THEN lot_id END,
+                 card_id = ?1, set_code = ?2, set_rarity = ?3, artwork = ?4, company = ?5,
+                 grade = ?6, cert_number = ?7, price = ?8, currency = ?9
+             WHERE id = ?10",
+            (
+                key.card_id, &key.set_code, &key.set_rarity, key.artwork,
+                &s.company, &s.grade, &s.cert_number, s.price, &s.currency, id,
+            ),
+        )
+        .map_err(|e| e.to_string())?;
+    if updated == 0 {
+        return Err(format!("No graded card with id {}", id));
+    }
+    load_slab(&conn, id)
+}
+
+/// Deletes a slab. With `to_raw`, the card was cracked out of its slab and
+/// comes back as one raw copy of the printing, with its purchase cost.
+#[tauri::command]
+pub fn remove_graded_card(id: i64, to_raw: Option<bool>) -> Result<(), String> {
+    let mut conn = open_db()?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let slab = load_slab(&tx, id)?;
+    let lot_id: Option<i64> = tx
+        .query_row("SELECT lot_id FROM graded_cards WHERE id = ?1", [id], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM graded_cards WHERE id = ?1", [id])
+        .map_err(|e| e.to_string())?;
+
+    if to_raw.unwrap_or(false) {
+        let key = PrintingKey {
+            card_id: slab.card_id,
+            set_code: slab.set_code,
+            set_rarity: slab.set_rarity,
+            artwork: slab.artwork,
+        };
+        let owned = owned_amount(&tx, &key)?;
+        apply_collection_amount(&tx, &key, owned + 1, "ungraded", None)?;
+        if let Some(lot_id) = lot_id {
+            return_lot_copy(&tx, lot_id)?;
+        }
+    }
+    tx.commit().map_err(|e| e.to_string())
+}
+
+/// All slabs, or those of one card. Prices stay in their own currency.
+#[tauri::command]
+pub fn list_graded_cards(card_id: Option<i64>) -> Result<Vec<GradedCard>, String> {
+    let conn = open_db()?;
+    load_graded(&conn, None, card_id)
+}
@@ -0,0 +1,138 @@
+use rusqlite::named_params;
+use serde::Serialize;
+
+use crate::commands::collection::apply_collection_amount;
+use crate::db::open_db;
+use crate::models::PrintingKey;
+
+// ---------------------------------------------------------------------------
+// Types
+// ---------------------------------------------------------------------------
+
+/// One entry of the collection ledger.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CollectionChange {
+    pub id: i64,
+    pub card_id: i64,
+    pub card_name: Option<String>,
+    pub set_code: String,
+    pub set_rarity: String,
+    pub artwork: i64,
+    pub old_amount: i64,
+    pub new_amount: i64,
+    pub source: String,
+    pub undo_of: Option<i64>,
+    pub changed_at: String,
+}
+
+fn change_from_row(row: &rusqlite::Row) -> rusqlite::Result<CollectionChange> {
+    Ok(CollectionChange {
+        id: row.get("id")?,
+        card_id: row.get("card_id")?,
+        card_name: row.get("name")?,
+        set_code: row.get("set_code")?,
+        set_rarity: row.get("set_rarity")?,
+        artwork: row.get("artwork")?,
+        old_amount: row.get("old_amount")?,
+        new_amount: row.get("new_amount")?,
+        source: row.get("source")?,
+        undo_of: row.get("undo_of")?,
+        changed_at: row.get("changed_at")?,
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Commands
+// ---------------------------------------------------------------------------
+
+/// Returns the ledger for a card, newest first. Passing set_code / rarity /
+/// artwork narrows it down to a single printing.
+#[tauri::command]
+pub fn get_collection_history(
+    card_id: i64,
+    set_code: Option<String>,
+    rarity: Option<String>,
+    artwork: Option<i64>,
+    limit: Option<i64>,
+) -> Result<Vec<CollectionChange>, String> {
+    let conn = open_db()?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT h.id, h.card_id, c.name, h.set_code, h.set_rarity, h.artwork,
+                    h.old_amount, h.new_amount, h.source, h.undo_of, h.changed_at
+             FROM collection_history h
+             LEFT JOIN cards c ON c.id = h.card_id
+             WHERE h.card_id = :card_id
+               AND (:set_code IS NULL OR h.set_code = :set_code)
+               AND (:rarity IS NULL OR h.set_rarity = :rarity)
+               AND (:artwork IS NULL OR h.artwork = :artwork)
+             ORDER BY h.id DESC
+             LIMIT :limit",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let rows = stmt
+        .query_map(
+            named_params! {
+                ":card_id": card_id,
+                ":set_code": set_code,
+                ":rarity": rarity,
+                ":artwork": artwork,
+                ":limit": limit.unwrap_or(-1), // -1 = no limit in SQLite
+            },
+            change_from_row,
+        )
+        .map_err(|e| e.to_string())?;
+
+    rows.collect::<Result<_, _>>().map_err(|e| e.to_string())
+}
+
+/// Reverts the last `count` changes that have not been undone yet, newest
+/// first, in one transaction. Each revert is appended to the ledger as an
+/// "undo" entry pointing at the change it reverted; undo entries themselves
+/// are skipped, so repeated calls walk further back in time. Copies moved
+/// into or out of a slab are skipped too: removing or re-adding the slab
+/// is what reverts those. Wishlist entries a change fulfilled are restored
+/// with it, and the purchase lot or sale it recorded is removed.
+/// Returns the reverted changes.
+#[tauri::command]
+pub fn undo_collection_changes(count: i64) -> Result<Vec<CollectionChange>, String> {
+    let mut conn = open_db()?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let changes: Vec<CollectionChange> = {
+        let mut stmt = tx
+            .prepare(
+                "SELECT h.id, h.card_id, c.name, h.set_code, h.set_rarity, h.artwork,
+                        h.old_amount, h.new_amount, h.source, h.undo_of, h.changed_at
+                 FROM collection_history h
+                 LEFT JOIN cards c ON c.id = h.card_id
+                 WHERE h.undo_of IS NULL
+                   AND h.source NOT IN ('graded', 'ungraded')
+                   AND NOT EXISTS (
+                       SELECT 1 FROM collection_history u WHERE u.undo_of = h.id
+                   )
+                 ORDER BY h.id DESC
+                 LIMIT ?1",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([count.max(0)], change_from_row)
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<_, _>>().map_err(|e| e.to_string())?
+    };
+
+    for change in &changes {
+        let key = PrintingKey {
+            card_id: change.card_id,
+            set_code: change.set_code.clone(),
+            set_rarity: change.set_rarity.clone(),
+            artwork: change.artwork,
+        };
+        apply_collection_amount(&tx, &key, change.old_amount, "undo", Some(change.id))?;
+    }
+
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(changes)
+}
@@ -0,0 +1,503 @@
+use rusqlite::{named_params, Connection};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+
+use crate::commands::collection::apply_collection_amount;
+use crate::db::open_db;
+use crate::models::PrintingKey;
+
+// ---------------------------------------------------------------------------
+// Types
+// ---------------------------------------------------------------------------
+
+/// Which CSV header holds which field. Every field is optional: unset fields
+/// fall back to the defaults of the chosen source (see `default_headers`).
+#[derive(Deserialize, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ColumnMapping {
+    pub quantity: Option<String>,
+    pub name: Option<String>,
+    pub set_code: Option<String>,
+    pub set_name: Option<String>,
+    pub rarity: Option<String>,
+}
+
+/// A card_sets row an import row could refer to.
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportCandidate {
+    pub card_id: i64,
+    pub card_name: String,
+    pub set_code: String,
+    pub set_name: Option<String>,
+    pub set_rarity: String,
+    pub artwork: i64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportRow {
+    pub line: usize, // 1-based line in the file, header included
+    pub name: Option<String>,
+    pub set_code: Option<String>,
+    pub set_name: Option<String>,
+    pub rarity: Option<String>,
+    pub quantity: i64,
+    pub candidates: Vec<ImportCandidate>,
+    pub note: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportReport {
+    pub matched: Vec<ImportRow>,
+    pub ambiguous: Vec<ImportRow>,
+    pub unmatched: Vec<ImportRow>,
+    /// false for a dry run
+    pub applied: bool,
+    /// Number of card_sets rows whose amount changed
+    pub changed_printings: usize,
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Header names each export tool uses, most specific first.
+/// Order of the tuple: quantity, name, set_code, set_name, rarity.
+fn default_headers(source: &str) -> Result<[&'static [&'static str]; 5], String> {
+    match source {
+        "cardmarket" => Ok([
+            &["Amount", "Count", "Quantity"],
+            &["English Name", "Name", "Card Name"],
+            &["Number", "Card Number", "Set Code"],
+            &["Exp. Name", "Expansion", "Set"],
+            &["Rarity"],
+        ]),
+        "tcgplayer" => Ok([
+            &["Quantity", "Total Quantity", "Add to Quantity"],
+            &["Name", "Product Name", "Simple Name"],
+            &["Card Number", "Number"],
+            &["Set", "Set Name"],
+            &["Rarity"],
+        ]),
+        "dragonshield" => Ok([
+            &["Quantity"],
+            &["Card Name", "Name"],
+            &["Card Number", "Set Code"],
+            &["Set Name", "Set"],
+            &["Rarity"],
+        ]),
+        // Files written by export_collection / export_trade_list
+        "ygodatabase" => Ok([
+            &["quantity"],
+            &["cardName"],
+            &["setCode"],
+            &["setName"],
+            &["rarity"],
+        ]),
+        "custom" => Ok([&[], &[], &[], &[], &[]]),
+        _ => Err(format!(
+            "Unknown import source '{}'. Supported: cardmarket, tcgplayer, dragonshield, ygodatabase, custom",
+            source
+        )),
+    }
+}
+
+/// Resolves a field to a column index: the explicit mapping wins, otherwise
+/// the first default header present in the file. Comparison ignores case.
+fn find_column(headers: &[String], explicit: Option<&String>, defaults: &[&str]) -> Option<usize> {
+    let find = |wanted: &str| headers.iter().position(|h| h.trim().eq_ignore_ascii_case(wanted.trim()));
+    match explicit {
+        Some(name) => find(name),
+        None => defaults.iter().find_map(|d| find(d)),
+    }
+}
+
+/// Same normalisation update_prices.py uses to compare rarity strings.
+pub fn normalize_rarity(r: &str) -> String {
+    r.to_lowercase().replace('-', " ").replace('\'', "").trim().to_string()
+}
+
+/// Picks the delimiter from the header line. Cardmarket exports use ';',
+/// most other tools ','. A leading Excel "sep=X" line overrides the guess
+/// and is stripped.
+fn sniff_delimiter(content: &str) -> (u8, &str) {
+    let first = content.lines().next().unwrap_or("");
+    if let Some(sep) = first.trim().strip_prefix("sep=") {
+        let rest = content.split_once('\n').map(|(_, r)| r).unwrap_or("");
+        return (sep.bytes().next().unwrap_or(b','), rest);
+    }
+    let delim = [b',', b';', b'\t']
+        .into_iter()
+        .max_by_key(|&d| first.bytes().filter(|&b| b == d).count())
+        .unwrap_or(b',');
+    (delim, content)
+}
+
+pub fn query_candidates(
+    conn: &Connection,
+    set_code: Option<&str>,
+    name: Option<&str>,
+) -> Result<Vec<ImportCandidate>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT cs.card_id, c.name, cs.set_code, cs.set_name, cs.set_rarity,
+                    COALESCE(cs.artwork, 0) AS artwork
+             FROM card_sets cs
+             JOIN cards c ON c.id = cs.card_id
+             WHERE (:set_code IS NULL OR cs.set_code = :set_code COLLATE NOCASE)
+               AND (:name IS NULL OR c.name = :name COLLATE NOCASE)
+             ORDER BY cs.set_code, cs.set_rarity, artwork",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(named_params! { ":set_code": set_code, ":name": name }, |row| {
+            Ok(ImportCandidate {
+                card_id: row.get("card_id")?,
+                card_name: row.get("name")?,
+                set_code: row.get("set_code")?,
+                set_name: row.get("set_name")?,
+                set_rarity: row.get("set_rarity")?,
+                artwork: row.get("artwork")?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<_, _>>().map_err(|e| e.to_string())
+}
+
+/// Narrows candidates by rarity / set name where the row provides them.
+/// A filter that would leave nothing is skipped, so the caller can still
+/// offer the remaining rows; the returned flag is false in that case so a
+/// lone leftover is not mistaken for a match.
+fn narrow(
+    mut candidates: Vec<ImportCandidate>,
+    rarity: Option<&str>,
+    set_name: Option<&str>,
+) -> (Vec<ImportCandidate>, bool) {
+    let mut exact = true;
+    if let Some(r) = rarity {
+        let wanted = normalize_rarity(r);
+        let filtered: Vec<_> = candidates.iter()
+            .filter(|c| normalize_rarity(&c.set_rarity) == wanted)
+            .cloned()
+            .collect();
+        if filtered.is_empty() {
+            exact = false;
+        } else {
+            candidates = filtered;
+        }
+    }
+    if let Some(s) = set_name {
+        let filtered: Vec<_> = candidates.iter()
+            .filter(|c| c.set_name.as_deref().is_some_and(|n| n.eq_ignore_ascii_case(s)))
+            .cloned()
+            .collect();
+        if !filtered.is_empty() {
+            candidates = filtered;
+        }
+    }
+    (candidates, exact)
+}
+
+/// Reads a card list CSV and matches every row against card_sets without
+/// changing anything. Shared by the collection import and trade matching.
+pub fn match_file(
+    conn: &Connection,
+    path: &str,
+    source: &str,
+    mapping: Option<ColumnMapping>,
+) -> Result<ImportReport, String> {
+    let defaults = default_headers(source)?;
+    let mapping = mapping.unwrap_or_default();
+
+    let raw = fs::read_to_string(path)
+        .map_err(|e| format!("Could not read '{}': {}", path, e))?;
+    let content = raw.trim_start_matches('\u{feff}'); // Excel BOM
+    let (delimiter, content) = sniff_delimiter(content);
+
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(content.as_bytes());
+
+    let headers: Vec<String> = reader
+        .headers()
+        .map_err(|e| e.to_string())?
+        .iter()
+        .map(|h| h.to_string())
+        .collect();
+
+    let col_qty = find_column(&headers, mapping.quantity.as_ref(), defaults[0]);
+    let col_name = find_column(&headers, mapping.name.as_ref(), defaults[1]);
+    let col_code = find_column(&headers, mapping.set_code.as_ref(), defaults[2]);
+    let col_set = find_column(&headers, mapping.set_name.as_ref(), defaults[3]);
+    let col_rarity = find_column(&headers, mapping.rarity.as_ref(), defaults[4]);
+    if col_name.is_none() && col_code.is_none() {
+        return Err(format!(
+            "Neither a card name nor a set code column was found. Headers: {}",
+            headers.join(", ")
+        ));
+    }
+
+    let mut report = ImportReport {
+        matched: Vec::new(),
+        ambiguous: Vec::new(),
+        unmatched: Vec::new(),
+        applied: false,
+        changed_printings: 0,
+    };
+
+    for (i, record) in reader.records().enumerate() {
+        let line = i + 2;
+        let record = match record {
+            Ok(r) => r,
+            Err(e) => {
+                report.unmatched.push(ImportRow {
+                    line,
+                    name: None,
+                    set_code: None,
+                    set_name: None,
+                    rarity: None,
+                    quantity: 0,
+                    candidates: Vec::new(),
+                    note: Some(e.to_string()),
+                });
+                continue;
+            }
+        };
+        let field = |col: Option<usize>| {
+            col.and_then(|c| record.get(c))
+                .filter(|v| !v.is_empty())
+                .map(|v| v.to_string())
+        };
+
+        let mut row = ImportRow {
+            line,
+            name: field(col_name),
+            set_code: field(col_code),
+            set_name: field(col_set),
+            rarity: field(col_rarity),
+            quantity: 1,
+            candidates: Vec::new(),
+            note: None,
+        };
+
+        if let Some(q) = field(col_qty) {
+            match q.parse::<i64>() {
+                Ok(n) if n >= 0 => row.quantity = n,
+                _ => {
+                    row.note = Some(format!("Invalid quantity '{}'", q));
+                    report.unmatched.push(row);
+                    continue;
+                }
+            }
+        }
+
+        let (mut candidates, mut exact) = (Vec::new(), false);
+        if let Some(code) = row.set_code.as_deref() {
+            (candidates, exact) = narrow(query_candidates(conn, Some(code), None)?, row.rarity.as_deref(), None);
+        }
+        if candidates.is_empty() {
+            if let Some(name) = row.name.as_deref() {
+                (candidates, exact) = narrow(
+                    query_candidates(conn, None, Some(name))?,
+                    row.rarity.as_deref(),
+                    row.set_name.as_deref(),
+                );
+                if !candidates.is_empty() && row.set_code.is_some() {
+                    row.note = Some("Set code not found, matched by name".to_string());
+                }
+            }
+        }
+        if !candidates.is_empty() && !exact {
+            row.note = Some(format!(
+                "Rarity '{}' not found for this card",
+                row.rarity.as_deref().unwrap_or_default()
+            ));
+        }
+        row.candidates = candidates;
+
+        match row.candidates.len() {
+            0 => report.unmatched.push(row),
+            1 if exact => report.matched.push(row),
+            _ => report.ambiguous.push(row),
+        }
+    }
+
+    Ok(report)
+}
+
+/// Applies the matched rows of `report` in one transaction (see
+/// import_collection_csv for the modes). "replace" is refused while any row
+/// is ambiguous or unmatched: the printings such a row means would be
+/// zeroed like any other printing missing from the file.
+fn apply_import(conn: &mut Connection, report: &mut ImportReport, mode: &str) -> Result<(), String> {
+    if mode == "replace" && (!report.ambiguous.is_empty() || !report.unmatched.is_empty()) {
+        return Err(format!(
+            "Cannot replace the collection: {} ambiguous and {} unmatched rows would be lost. \
+             Fix them in the file, or import with mode \"add\".",
+            report.ambiguous.len(),
+            report.unmatched.len()
+        ));
+    }
+
+    // Several rows may point at the same printing (e.g. different conditions)
+    let mut totals: HashMap<(i64, String, String, i64), i64> = HashMap::new();
+    for row in &report.matched {
+        let c = &row.candidates[0];
+        *totals
+            .entry((c.card_id, c.set_code.clone(), c.set_rarity.clone(), c.artwork))
+            .or_insert(0) += row.quantity;
+    }
+
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    if mode == "replace" {
+        let owned: Vec<PrintingKey> = {
+            let mut stmt = tx
+                .prepare(
+                    "SELECT card_id, set_code, set_rarity, COALESCE(artwork, 0)
+                     FROM card_sets
+                     WHERE collection_amount > 0",
+                )
+                .map_err(|e| e.to_string())?;
+            let rows = stmt
+                .query_map([], |row| {
+                    Ok(PrintingKey {
+                        card_id: row.get(0)?,
+                        set_code: row.get(1)?,
+                        set_rarity: row.get(2)?,
+                        artwork: row.get(3)?,
+                    })
+                })
+                .map_err(|e| e.to_string())?;
+            rows.collect::<Result<_, _>>().map_err(|e| e.to_string())?
+        };
+        for key in owned {
+            let k = (key.card_id, key.set_code.clone(), key.set_rarity.clone(), key.artwork);
+            if !totals.contains_key(&k) {
+                apply_collection_amount(&tx, &key, 0, "import", None)?;
+                report.changed_printings += 1;
+            }
+        }
+    }
+
+    for ((card_id, set_code, set_rarity, artwork), qty) in totals {
+        let key = PrintingKey { card_id, set_code, set_rarity, artwork };
+        let new_amount = if mode == "add" {
+            let current: i64 = tx
+                .query_row(
+                    "SELECT COALESCE(collection_amount, 0) FROM card_sets
+                     WHERE card_id = ?1 AND set_code = ?2 AND set_rarity = ?3
+                       AND COALESCE(artwork, 0) = ?4",
+                    (key.card_id, &key.set_code, &key.set_rarity, key.artwork),
+                    |row| row.get(0),
+                )
+                .map_err(|e| e.to_string())?;
+            current + qty
+        } else {
+            qty
+        };
+        if apply_collection_amount(&tx, &key, new_amount, "import", None)? != new_amount {
+            report.changed_printings += 1;
+        }
+    }
+
+    tx.commit().map_err(|e| e.to_string())?;
+    report.applied = true;
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Commands
+// ---------------------------------------------------------------------------
+
+/// Imports a collection CSV exported from Cardmarket, TCGplayer, Dragon Shield
+/// or this app's own export.
+///
+/// Rows are matched to card_sets by set code + rarity, falling back to the
+/// card name (narrowed by rarity and set name). A row is "matched" when
+/// exactly one printing fits, "ambiguous" when several do (e.g. multiple
+/// rarities or artworks) and "unmatched" otherwise.
+///
+/// With `dry_run` the report is returned without touching the DB. Otherwise
+/// matched rows are applied in one transaction and logged to
+/// collection_history:
+///   mode "add"     → quantities are added to the current amounts
+///   mode "replace" → the file becomes the collection: matched printings are
+///                    set to the file quantity, every other owned printing to 0.
+///                    Refused while any row is ambiguous or unmatched, since
+///                    the copies that row stands for would be zeroed.
+/// In "add" mode ambiguous and unmatched rows are skipped.
+#[tauri::command]
+pub fn import_collection_csv(
+    path: String,
+    source: String,
+    mapping: Option<ColumnMapping>,
+    mode: String,
+    dry_run: bool,
+) -> Result<ImportReport, String> {
+    if mode != "add" && mode != "replace" {
+        return Err(format!("Unknown import mode '{}'. Supported: add, replace", mode));
+    }
+    let mut conn = open_db()?;
+    let mut report = match_file(&conn, &path, &source, mapping)?;
+
+    if dry_run {
+        return Ok(report);
+    }
+
+    apply_import(&mut conn, &mut report, &mode)?;
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_db;
+
+    fn owned(conn: &Connection, card_id: i64, set_rarity: &str) -> i64 {
+        conn.query_row(
+            "SELECT collection_amount FROM card_sets WHERE card_id = ?1 AND set_rarity = ?2",
+            (card_id, set_rarity),
+            |r| r.get(0),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn replace_is_refused_while_a_row_is_ambiguous() {
+        let mut conn = test_db();
+        conn.execute_batch(
+            "INSERT INTO cards (id, name) VALUES (1, 'Dark Magician'), (2, 'Kuriboh');
+             INSERT INTO card_sets (card_id, set_code, set_rarity, collection_amount) VALUES
+                (1, 'LOB-005', 'Ultra Rare', 2),
+                (1, 'LOB-005', 'Secret Rare', 0),
+                (2, 'MRD-071', 'Common', 1);",
+        )
+        .unwrap();
+        let path = std::env::temp_dir().join(format!("import-replace-{}.csv", std::process::id()));
+        // The Dark Magician row has no rarity, so it could be either printing
+        fs::write(
+            &path,
+            "quantity,cardName,setCode,setName,rarity\n3,Kuriboh,MRD-071,,Common\n2,Dark Magician,LOB-005,,\n",
+        )
+        .unwrap();
+        let mut report = match_file(&conn, path.to_str().unwrap(), "ygodatabase", None).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!((report.matched.len(), report.ambiguous.len()), (1, 1));
+
+        assert!(apply_import(&mut conn, &mut report, "replace").is_err());
+        assert!(!report.applied);
+        assert_eq!(owned(&conn, 1, "Ultra Rare"), 2);
+        assert_eq!(owned(&conn, 2, "Common"), 1);
+
+        // Adding only touches the matched row
+        apply_import(&mut conn, &mut report, "add").unwrap();
+        assert_eq!(owned(&conn, 1, "Ultra Rare"), 2);
+        assert_eq!(owned(&conn, 2, "Common"), 4);
+    }
+}
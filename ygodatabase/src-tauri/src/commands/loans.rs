@@ -0,0 +1,267 @@
+use std::collections::HashMap;
+
+use rusqlite::{named_params, Connection};
+use serde::Deserialize;
+
+use crate::commands::profit::checked_date;
+use crate::db::open_db;
+use crate::models::Loan;
+
+// ---------------------------------------------------------------------------
+// Types
+// ---------------------------------------------------------------------------
+
+/// A new loan, as sent by the frontend. Leave set_code / set_rarity /
+/// artwork out to lend a card from a deck without naming the printing.
+/// `lent_on` defaults to today; `due_on` is the expected return date.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoanFields {
+    pub card_id: i64,
+    pub set_code: Option<String>,
+    pub set_rarity: Option<String>,
+    pub artwork: Option<i64>,
+    pub quantity: i64,
+    pub borrower: String,
+    pub lent_on: Option<String>,
+    pub due_on: Option<String>,
+    pub note: Option<String>,
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Loans by lending date, newest first. `id` picks one, `card_id` one card;
+/// `outstanding_only` skips returned loans, `borrower` matches ignoring case.
+pub fn load_loans(
+    conn: &Connection,
+    id: Option<i64>,
+    card_id: Option<i64>,
+    outstanding_only: bool,
+    borrower: Option<&str>,
+) -> Result<Vec<Loan>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT l.id, l.card_id, c.name, l.set_code, l.set_rarity, l.artwork, l.quantity,
+                    l.borrower, l.lent_on, l.due_on, l.returned_on, l.note,
+                    (l.returned_on IS NULL AND l.due_on IS NOT NULL AND l.due_on < date('now')) AS overdue
+             FROM loans l
+             JOIN cards c ON c.id = l.card_id
+             WHERE (:id IS NULL OR l.id = :id)
+               AND (:card_id IS NULL OR l.card_id = :card_id)
+               AND (:outstanding_only = 0 OR l.returned_on IS NULL)
+               AND (:borrower IS NULL OR l.borrower = :borrower COLLATE NOCASE)
+             ORDER BY l.lent_on DESC, l.id DESC",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(
+            named_params! {
+                ":id": id,
+                ":card_id": card_id,
+                ":outstanding_only": outstanding_only,
+                ":borrower": borrower,
+            },
+            |row| {
+                Ok(Loan {
+                    id: row.get("id")?,
+                    card_id: row.get("card_id")?,
+                    card_name: row.get("name")?,
+                    set_code: row.get("set_code")?,
+                    set_rarity: row.get("set_rarity")?,
+                    artwork: row.get("artwork")?,
+                    quantity: row.get("quantity")?,
+                    borrower: row.get("borrower")?,
+                    lent_on: row.get("lent_on")?,
+                    due_on: row.get("due_on")?,
+                    returned_on: row.get("returned_on")?,
+                    overdue: row.get("overdue")?,
+                    note: row.get("note")?,
+                })
+            },
+        )
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<_, _>>().map_err(|e| e.to_string())
+}
+
+fn load_loan(conn: &Connection, id: i64) -> Result<Loan, String> {
+    load_loans(conn, Some(id), None, false, None)?
+        .pop()
+        .ok_or_else(|| format!("No loan with id {}", id))
+}
+
+/// Owned copies matching the loan's printing pattern, and how many of them
+/// are already lent out under the same pattern.
+fn lendable(conn: &Connection, loan: &LoanFields) -> Result<(i64, i64), String> {
+    let params = named_params! {
+        ":card_id": loan.card_id,
+        ":set_code": loan.set_code,
+        ":set_rarity": loan.set_rarity,
+        ":artwork": loan.artwork,
+    };
+    let owned: Option<i64> = conn
+        .query_row(
+            "SELECT SUM(COALESCE(collection_amount, 0)) FROM card_sets
+             WHERE card_id = :card_id
+               AND (:set_code IS NULL OR set_code = :set_code)
+               AND (:set_rarity IS NULL OR set_rarity = :set_rarity)
+               AND (:artwork IS NULL OR COALESCE(artwork, 0) = :artwork)",
+            params,
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    let owned = owned.ok_or_else(|| format!("No printing of card {} matches this loan", loan.card_id))?;
+    let lent: i64 = conn
+        .query_row(
+            "SELECT COALESCE(SUM(quantity), 0) FROM loans
+             WHERE card_id = :card_id AND returned_on IS NULL
+               AND set_code IS :set_code AND set_rarity IS :set_rarity AND artwork IS :artwork",
+            params,
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    Ok((owned, lent))
+}
+
+// ---------------------------------------------------------------------------
+// Commands
+// ---------------------------------------------------------------------------
+
+/// Records copies lent to `borrower`. The copies stay in the collection but
+/// cannot be lent twice: fails when fewer matching copies are owned than
+/// would then be out, counting both this printing's and the card's loans.
+#[tauri::command]
+pub fn lend_cards(loan: LoanFields) -> Result<Loan, String> {
+    if loan.quantity <= 0 {
+        return Err("Quantity must be at least 1".to_string());
+    }
+    let borrower = loan.borrower.trim().to_string();
+    if borrower.is_empty() {
+        return Err("A loan needs the name of the borrower".to_string());
+    }
+    let mut conn = open_db()?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let lent_on = checked_date(&tx, loan.lent_on.clone())?;
+    let due_on = match loan.due_on.clone() {
+        Some(d) => Some(checked_date(&tx, Some(d))?),
+        None => None,
+    };
+    if due_on.as_ref().is_some_and(|d| *d < lent_on) {
+        return Err("The return date cannot be before the lending date".to_string());
+    }
+
+    let (owned, lent) = lendable(&tx, &loan)?;
+    let (card_owned, card_lent): (i64, i64) = tx
+        .query_row(
+            "SELECT (SELECT COALESCE(SUM(collection_amount), 0) FROM card_sets WHERE card_id = ?1),
+                    (SELECT COALESCE(SUM(quantity), 0) FROM loans
+                     WHERE card_id = ?1 AND returned_on IS NULL)",
+            [loan.card_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|e| e.to_string())?;
+    let available = (owned - lent).min(card_owned - card_lent).max(0);
+    if loan.quantity > available {
+        return Err(format!(
+            "Cannot lend {} copies: only {} owned and not already lent out",
+            loan.quantity, available
+        ));
+    }
+
+    let note = loan.note.map(|n| n.trim().to_string()).filter(|n| !n.is_empty());
+    tx.execute(
+        "INSERT INTO loans
+            (card_id, set_code, set_rarity, artwork, quantity, borrower, lent_on, due_on, note)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        (
+            loan.card_id, &loan.set_code, &loan.set_rarity, loan.artwork,
+            loan.quantity, &borrower, &lent_on, &due_on, &note,
+        ),
+    )
+    .map_err(|e| e.to_string())?;
+    let id = tx.last_insert_rowid();
+
+    tx.commit().map_err(|e| e.to_string())?;
+    load_loan(&conn, id)
+}
+
+/// Marks a loan as returned on `returned_on` (default today).
+#[tauri::command]
+pub fn return_loan(id: i64, returned_on: Option<String>) -> Result<Loan, String> {
+    let conn = open_db()?;
+    let loan = load_loan(&conn, id)?;
+    if loan.returned_on.is_some() {
+        return Err(format!("Loan {} was already returned", id));
+    }
+    let returned_on = checked_date(&conn, returned_on)?;
+    if returned_on < loan.lent_on {
+        return Err("The return date cannot be before the lending date".to_string());
+    }
+    conn.execute("UPDATE loans SET returned_on = ?1 WHERE id = ?2", (&returned_on, id))
+        .map_err(|e| e.to_string())?;
+    load_loan(&conn, id)
+}
+
+/// Deletes a loan recorded by mistake; use return_loan when the cards came
+/// back, so the loan stays in the history.
+#[tauri::command]
+pub fn delete_loan(id: i64) -> Result<(), String> {
+    let conn = open_db()?;
+    let removed = conn
+        .execute("DELETE FROM loans WHERE id = ?1", [id])
+        .map_err(|e| e.to_string())?;
+    if removed == 0 {
+        return Err(format!("No loan with id {}", id));
+    }
+    Ok(())
+}
+
+/// Loans, newest first. `outstanding_only` (default true) hides returned
+/// ones; `borrower` narrows to one person.
+#[tauri::command]
+pub fn list_loans(outstanding_only: Option<bool>, borrower: Option<String>) -> Result<Vec<Loan>, String> {
+    let conn = open_db()?;
+    let borrower = borrower.map(|b| b.trim().to_string()).filter(|b| !b.is_empty());
+    load_loans(&conn, None, None, outstanding_only.unwrap_or(true), borrower.as_deref())
+}
+
+/// Returns a map of card_id → copies currently lent out, for every card with
+/// an outstanding loan. Used next to get_collection_amounts by deck tools.
+#[tauri::command]
+pub fn get_lent_amounts() -> Result<HashMap<i64, i64>, String> {
+    let conn = open_db()?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT card_id, SUM(quantity) FROM loans
+             WHERE returned_on IS NULL
+             GROUP BY card_id",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?)))
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<_, _>>().map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_db;
+
+    #[test]
+    fn loan_without_due_date_is_never_overdue() {
+        let conn = test_db();
+        conn.execute_batch(
+            "INSERT INTO cards (id, name) VALUES (1, 'Dark Magician');
+             INSERT INTO loans (card_id, quantity, borrower, lent_on)
+             VALUES (1, 1, 'Sam', '2020-01-01');
+             INSERT INTO loans (card_id, quantity, borrower, lent_on, due_on)
+             VALUES (1, 1, 'Kim', '2020-01-01', '2020-02-01');",
+        )
+        .unwrap();
+        let loans = load_loans(&conn, None, Some(1), true, None).unwrap();
+        let overdue: Vec<(&str, bool)> = loans.iter().map(|l| (l.borrower.as_str(), l.overdue)).collect();
+        assert_eq!(overdue, vec![("Kim", true), ("Sam", false)]);
+    }
+}
@@ -1,4 +1,22 @@
 pub mod altart;
 pub mod collection;
+pub mod completion;
 pub mod deck;
-pub mod sync;
\ No newline at end of file
+pub mod export;
+pub mod graded;
+pub mod history;
+pub mod import;
+pub mod loans;
+pub mod playset;
+pub mod prices;
+pub mod profit;
+pub mod quickadd;
+pub mod searches;
+pub mod sealed;
+pub mod stats;
+pub mod storage;
+pub mod sync;
+pub mod tags;
+pub mod trade;
+pub mod validation;
+pub mod wishlist;
\ No newline at end of file
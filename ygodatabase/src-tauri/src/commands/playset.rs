@@ -0,0 +1,362 @@
+use rusqlite::named_params;
+use serde::Serialize;
+
+use crate::commands::import::normalize_rarity;
+use crate::currency::{latest_date, round2, ExchangeRates, BASE_CURRENCY};
+use crate::db::open_db;
+use crate::filters::{ban_key_for_format, CardFilters};
+
+// ---------------------------------------------------------------------------
+// Types
+// ---------------------------------------------------------------------------
+
+/// One owned printing of an over-threshold card. `keep` copies stay in the
+/// playset, `excess` copies are free to trade.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExcessPrinting {
+    pub set_code: String,
+    pub set_name: Option<String>,
+    pub set_rarity: String,
+    pub artwork: i64,
+    pub owned: i64,
+    pub keep: i64,
+    pub excess: i64,
+    pub price: Option<f64>,
+}
+
+/// A card owned more than `threshold` times. Printings are listed in the
+/// order they are kept (best first), so the excess sits at the end.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExcessCard {
+    pub card_id: i64,
+    pub card_name: String,
+    pub owned: i64,
+    pub excess: i64,
+    /// Price of the excess copies; unpriced copies count as 0
+    pub excess_value: f64,
+    pub printings: Vec<ExcessPrinting>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExcessReport {
+    pub cards: Vec<ExcessCard>,
+    pub threshold: i64,
+    pub keep: String,
+    pub currency: String,
+    pub rate_date: Option<String>,
+    /// Currencies without a stored rate; their amounts are left out
+    pub missing_rates: Vec<String>,
+}
+
+/// A card in scope owned fewer than `target` times. `target` is the playset
+/// size, lowered to the ban list limit when a format is given.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShortCard {
+    pub card_id: i64,
+    pub card_name: String,
+    pub ban_status: Option<String>,
+    pub owned: i64,
+    pub target: i64,
+    pub missing: i64,
+    /// Cheapest priced printing, in the report currency
+    pub lowest_price: Option<f64>,
+    pub lowest_price_set_code: Option<String>,
+    pub lowest_price_rarity: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaysetReport {
+    pub cards: Vec<ShortCard>,
+    pub playset: i64,
+    pub missing_copies: i64,
+    /// Sum of missing × lowest_price; cards without a price are left out
+    pub missing_cost: f64,
+    pub currency: String,
+    pub rate_date: Option<String>,
+    /// Currencies without a stored rate; their amounts are left out
+    pub missing_rates: Vec<String>,
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+// Rarities from plainest to blingiest, normalized. Anything not listed
+// (parallels, promos, unknown) ranks with commons.
+const BLING_ORDER: &[&str] = &[
+    "common",
+    "short print",
+    "rare",
+    "super rare",
+    "super short print",
+    "ultra rare",
+    "secret rare",
+    "prismatic secret rare",
+    "ultimate rare",
+    "gold rare",
+    "premium gold rare",
+    "gold secret rare",
+    "platinum rare",
+    "platinum secret rare",
+    "collectors rare",
+    "ghost rare",
+    "starlight rare",
+    "quarter century secret rare",
+    "10000 secret rare",
+];
+
+fn bling_rank(rarity: &str) -> usize {
+    let r = normalize_rarity(rarity);
+    BLING_ORDER.iter().position(|b| *b == r).unwrap_or(0)
+}
+
+/// Copies a format allows of a card with this ban list status.
+fn ban_limit(status: Option<&str>) -> i64 {
+    match status {
+        Some("Banned") | Some("Forbidden") => 0,
+        Some("Limited") => 1,
+        Some("Semi-Limited") => 2,
+        _ => i64::MAX,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Commands
+// ---------------------------------------------------------------------------
+
+/// Cards owned more than `threshold` times (default 3) across all printings,
+/// and which copies are surplus. The copies kept are chosen by `keep`:
+///   "value" (default) → most valuable printings first, rarity breaks ties
+///   "bling"           → rarest-looking printings first, price breaks ties
+/// Prices are converted to `currency` (default EUR).
+#[tauri::command]
+pub fn get_excess_copies(
+    threshold: Option<i64>,
+    keep: Option<String>,
+    currency: Option<String>,
+) -> Result<ExcessReport, String> {
+    let threshold = threshold.unwrap_or(3).max(0);
+    let keep = keep.unwrap_or_else(|| "value".to_string());
+    if keep != "value" && keep != "bling" {
+        return Err(format!("Unknown keep order '{}' (use value or bling)", keep));
+    }
+    let currency = currency.unwrap_or_else(|| BASE_CURRENCY.to_string());
+    let conn = open_db()?;
+    let rates = ExchangeRates::load(&conn)?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT cs.card_id, c.name, cs.set_code, cs.set_name, cs.set_rarity,
+                    COALESCE(cs.artwork, 0) AS artwork, cs.collection_amount,
+                    CAST(cs.set_price AS REAL) AS price,
+                    COALESCE(cs.price_currency, 'USD') AS price_currency
+             FROM card_sets cs
+             JOIN cards c ON c.id = cs.card_id
+             WHERE cs.collection_amount > 0
+               AND cs.card_id IN (
+                   SELECT card_id FROM card_sets
+                   GROUP BY card_id
+                   HAVING SUM(collection_amount) > :threshold
+               )
+             ORDER BY c.name, cs.card_id",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(named_params! { ":threshold": threshold }, |row| {
+            Ok((
+                row.get::<_, i64>("card_id")?,
+                row.get::<_, String>("name")?,
+                ExcessPrinting {
+                    set_code: row.get("set_code")?,
+                    set_name: row.get("set_name")?,
+                    set_rarity: row.get("set_rarity")?,
+                    artwork: row.get("artwork")?,
+                    owned: row.get("collection_amount")?,
+                    keep: 0,
+                    excess: 0,
+                    price: row.get::<_, Option<f64>>("price")?.filter(|&p| p > 0.0),
+                },
+                row.get::<_, String>("price_currency")?,
+            ))
+        })
+        .map_err(|e| e.to_string())?;
+
+    let mut report = ExcessReport {
+        cards: Vec::new(),
+        threshold,
+        keep: keep.clone(),
+        currency: currency.clone(),
+        rate_date: None,
+        missing_rates: Vec::new(),
+    };
+    for r in rows {
+        let (card_id, card_name, mut printing, price_currency) = r.map_err(|e| e.to_string())?;
+        if let Some(p) = printing.price {
+            printing.price = rates
+                .convert(p, &price_currency, &currency, &mut report.missing_rates)
+                .map(|(converted, date)| {
+                    report.rate_date = latest_date(report.rate_date.take(), date);
+                    round2(converted)
+                });
+        }
+        if report.cards.last().is_none_or(|c| c.card_id != card_id) {
+            report.cards.push(ExcessCard {
+                card_id,
+                card_name,
+                owned: 0,
+                excess: 0,
+                excess_value: 0.0,
+                printings: Vec::new(),
+            });
+        }
+        let card = report.cards.last_mut().unwrap();
+        card.owned += printing.owned;
+        card.printings.push(printing);
+    }
+
+    for card in &mut report.cards {
+        let price = |p: &ExcessPrinting| p.price.unwrap_or(0.0);
+        card.printings.sort_by(|a, b| {
+            let by_price = price(b).total_cmp(&price(a));
+            let by_bling = bling_rank(&b.set_rarity).cmp(&bling_rank(&a.set_rarity));
+            match keep.as_str() {
+                "bling" => by_bling.then(by_price),
+                _ => by_price.then(by_bling),
+            }
+        });
+        let mut to_keep = threshold;
+        for p in &mut card.printings {
+            p.keep = p.owned.min(to_keep);
+            p.excess = p.owned - p.keep;
+            to_keep -= p.keep;
+            card.excess += p.excess;
+            card.excess_value += p.excess as f64 * price(p);
+        }
+        card.excess_value = round2(card.excess_value);
+    }
+
+    Ok(report)
+}
+
+/// Cards in scope owned fewer than `playset` times (default 3). The scope is
+/// the usual card filters, e.g. an archetype, a set or a format's card pool.
+/// With a format set, Limited / Semi-Limited cards only need 1 / 2 copies
+/// and Forbidden cards are left out. Graded slabs do not count as owned.
+#[tauri::command]
+pub fn get_playset_shortfall(
+    filters: Option<CardFilters>,
+    playset: Option<i64>,
+    currency: Option<String>,
+) -> Result<PlaysetReport, String> {
+    let filters = filters.unwrap_or_default();
+    let playset = playset.unwrap_or(3).max(1);
+    let currency = currency.unwrap_or_else(|| BASE_CURRENCY.to_string());
+    let conn = open_db()?;
+    let rates = ExchangeRates::load(&conn)?;
+
+    // Genesys has no ban list (see ban_key_for_format)
+    let ban_key = match filters.format.as_deref() {
+        Some(f) if f != "Genesys" => Some(ban_key_for_format(Some(f))),
+        _ => None,
+    };
+    let filter = filters.to_sql();
+    let sql = format!("
+        SELECT c.id, c.name,
+               json_extract(c.banlist_info, '$.{}') AS ban_status,
+               (SELECT COALESCE(SUM(o.collection_amount), 0)
+                FROM card_sets o WHERE o.card_id = c.id) AS owned
+        FROM cards c
+        LEFT JOIN card_sets cs ON cs.card_id = c.id
+        WHERE {}
+        GROUP BY c.id
+        HAVING owned < :playset
+        ORDER BY c.name, c.id
+    ", ban_key.unwrap_or("ban_tcg"), filter.clause);
+
+    let mut params = filter.params_ref();
+    params.push((":playset", &playset));
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params.as_slice(), |row| {
+            Ok((
+                row.get::<_, i64>("id")?,
+                row.get::<_, String>("name")?,
+                row.get::<_, Option<String>>("ban_status")?,
+                row.get::<_, i64>("owned")?,
+            ))
+        })
+        .map_err(|e| e.to_string())?;
+
+    let mut report = PlaysetReport {
+        cards: Vec::new(),
+        playset,
+        missing_copies: 0,
+        missing_cost: 0.0,
+        currency: currency.clone(),
+        rate_date: None,
+        missing_rates: Vec::new(),
+    };
+    for r in rows {
+        let (card_id, card_name, ban_status, owned) = r.map_err(|e| e.to_string())?;
+        let ban_status = ban_key.and(ban_status);
+        let target = playset.min(ban_limit(ban_status.as_deref()));
+        if owned >= target {
+            continue;
+        }
+        report.cards.push(ShortCard {
+            card_id,
+            card_name,
+            ban_status,
+            owned,
+            target,
+            missing: target - owned,
+            lowest_price: None,
+            lowest_price_set_code: None,
+            lowest_price_rarity: None,
+        });
+    }
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT set_code, set_rarity, CAST(set_price AS REAL) AS price,
+                    COALESCE(price_currency, 'USD') AS price_currency
+             FROM card_sets
+             WHERE card_id = ?1 AND CAST(set_price AS REAL) > 0",
+        )
+        .map_err(|e| e.to_string())?;
+    for card in &mut report.cards {
+        let rows = stmt
+            .query_map([card.card_id], |row| {
+                Ok((
+                    row.get::<_, String>("set_code")?,
+                    row.get::<_, String>("set_rarity")?,
+                    row.get::<_, f64>("price")?,
+                    row.get::<_, String>("price_currency")?,
+                ))
+            })
+            .map_err(|e| e.to_string())?;
+        for r in rows {
+            let (set_code, rarity, price, price_currency) = r.map_err(|e| e.to_string())?;
+            let Some((price, date)) = rates.convert(price, &price_currency, &currency, &mut report.missing_rates) else {
+                continue;
+            };
+            let price = round2(price);
+            if card.lowest_price.is_none_or(|p| price < p) {
+                report.rate_date = latest_date(report.rate_date.take(), date);
+                card.lowest_price = Some(price);
+                card.lowest_price_set_code = Some(set_code);
+                card.lowest_price_rarity = Some(rarity);
+            }
+        }
+        report.missing_copies += card.missing;
+        report.missing_cost += card.missing as f64 * card.lowest_price.unwrap_or(0.0);
+    }
+    report.missing_cost = round2(report.missing_cost);
+
+    Ok(report)
+}
@@ -0,0 +1,235 @@
+use rusqlite::{named_params, Connection};
+use serde::Serialize;
+
+use crate::db::open_db;
+
+// ---------------------------------------------------------------------------
+// Types
+// ---------------------------------------------------------------------------
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PricePoint {
+    pub date: String, // YYYY-MM-DD
+    pub price: f64,
+    pub currency: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValuePoint {
+    pub date: String,
+    pub value: f64,
+    pub copies: i64,
+}
+
+/// Price change of one printing between the start of a window and now.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PriceMove {
+    pub card_id: i64,
+    pub card_name: String,
+    pub set_code: String,
+    pub set_name: Option<String>,
+    pub set_rarity: String,
+    pub artwork: i64,
+    pub old_price: f64,
+    pub new_price: f64,
+    pub change: f64,
+    pub change_percent: f64,
+    pub currency: String,
+    pub collection_amount: i64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PriceMovers {
+    pub gainers: Vec<PriceMove>,
+    pub losers: Vec<PriceMove>,
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Writes (or refreshes) today's row of collection_value_history from the
+/// current card_sets prices and amounts, converted to EUR with the latest
+/// stored rates, plus unopened sealed products at market price and graded
+/// slabs at their own price. Amounts in a currency without a stored rate
+/// are left out, as in get_collection_value.
+pub fn record_value_snapshot(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "WITH rates AS (
+             SELECT currency, rate FROM exchange_rates r
+             WHERE currency <> 'EUR'
+               AND rate_date = (SELECT MAX(rate_date) FROM exchange_rates WHERE currency = r.currency)
+             UNION ALL
+             SELECT 'EUR', 1.0
+         )
+         INSERT OR REPLACE INTO collection_value_history (snapshot_on, total_value, total_copies)
+         SELECT date('now'),
+                COALESCE(SUM(CASE WHEN CAST(cs.set_price AS REAL) > 0
+                                  THEN CAST(cs.set_price AS REAL) * cs.collection_amount
+                                       / rt.rate END), 0.0)
+                  + (SELECT COALESCE(SUM(sp.market_price * sp.quantity / sr.rate), 0.0)
+                     FROM sealed_products sp
+                     LEFT JOIN rates sr ON sr.currency = sp.currency
+                     WHERE sp.quantity > 0 AND sp.market_price > 0)
+                  + (SELECT COALESCE(SUM(g.price / gr.rate), 0.0)
+                     FROM graded_cards g
+                     LEFT JOIN rates gr ON gr.currency = g.currency
+                     WHERE g.price > 0),
+                COALESCE(SUM(cs.collection_amount), 0)
+         FROM card_sets cs
+         LEFT JOIN rates rt ON rt.currency = COALESCE(cs.price_currency, 'USD')
+         WHERE cs.collection_amount > 0",
+        [],
+    ).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Commands
+// ---------------------------------------------------------------------------
+
+/// Returns the recorded prices of one printing, oldest first. Entries are
+/// only written when the price changed, so each price holds until the next.
+#[tauri::command]
+pub fn get_price_history(
+    card_id: i64,
+    set_code: String,
+    rarity: String,
+    artwork: Option<i64>,
+) -> Result<Vec<PricePoint>, String> {
+    let conn = open_db()?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT recorded_on, price, currency
+             FROM price_history
+             WHERE card_id = ?1 AND set_code = ?2 AND set_rarity = ?3 AND artwork = ?4
+             ORDER BY recorded_on",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map((card_id, set_code, rarity, artwork.unwrap_or(0)), |row| {
+            Ok(PricePoint { date: row.get(0)?, price: row.get(1)?, currency: row.get(2)? })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<_, _>>().map_err(|e| e.to_string())
+}
+
+/// Returns the daily collection value snapshots (in EUR), oldest first.
+/// `days` limits the series to the most recent N days.
+#[tauri::command]
+pub fn get_collection_value_history(days: Option<i64>) -> Result<Vec<ValuePoint>, String> {
+    if days.is_some_and(|d| d < 0) {
+        return Err("Days cannot be negative".to_string());
+    }
+    let conn = open_db()?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT snapshot_on, total_value, total_copies
+             FROM collection_value_history
+             WHERE :days IS NULL OR snapshot_on >= date('now', '-' || :days || ' days')
+             ORDER BY snapshot_on",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(named_params! { ":days": days }, |row| {
+            Ok(ValuePoint { date: row.get(0)?, value: row.get(1)?, copies: row.get(2)? })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<_, _>>().map_err(|e| e.to_string())
+}
+
+/// Compares each printing's price as of `days` ago (its latest history entry
+/// on or before that date) with its current set_price, and returns the
+/// `limit` biggest absolute gainers and losers (default 20 each).
+/// Prices are compared in their source currency; a printing whose currency
+/// changed since the start of the window is skipped.
+/// With `owned_only` only printings in the collection are considered.
+#[tauri::command]
+pub fn get_price_movers(days: i64, limit: Option<i64>, owned_only: Option<bool>) -> Result<PriceMovers, String> {
+    if days < 0 {
+        return Err("Days cannot be negative".to_string());
+    }
+    let conn = open_db()?;
+    let limit = limit.unwrap_or(20);
+
+    let query = |direction: &str| -> Result<Vec<PriceMove>, String> {
+        let (condition, order) = if direction == "up" {
+            ("change > 0", "change DESC")
+        } else {
+            ("change < 0", "change ASC")
+        };
+        let sql = format!("
+            WITH start AS (
+                SELECT card_id, set_code, set_rarity, artwork, price, currency,
+                       ROW_NUMBER() OVER (
+                           PARTITION BY card_id, set_code, set_rarity, artwork
+                           ORDER BY recorded_on DESC
+                       ) AS rn
+                FROM price_history
+                WHERE recorded_on <= date('now', '-' || :days || ' days')
+            ),
+            moves AS (
+                SELECT cs.card_id, c.name, cs.set_code, cs.set_name, cs.set_rarity,
+                       COALESCE(cs.artwork, 0) AS artwork,
+                       s.price AS old_price,
+                       CAST(cs.set_price AS REAL) AS new_price,
+                       CAST(cs.set_price AS REAL) - s.price AS change,
+                       s.currency,
+                       COALESCE(cs.collection_amount, 0) AS collection_amount
+                FROM start s
+                JOIN card_sets cs
+                  ON cs.card_id = s.card_id
+                 AND cs.set_code = s.set_code
+                 AND cs.set_rarity = s.set_rarity
+                 AND COALESCE(cs.artwork, 0) = s.artwork
+                JOIN cards c ON c.id = cs.card_id
+                WHERE s.rn = 1
+                  AND COALESCE(cs.price_currency, 'USD') = s.currency
+                  AND CAST(cs.set_price AS REAL) > 0
+                  AND (:owned_only = 0 OR cs.collection_amount > 0)
+            )
+            SELECT * FROM moves
+            WHERE {condition}
+            ORDER BY {order}, name
+            LIMIT :limit
+        ");
+        let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(
+                named_params! {
+                    ":days": days,
+                    ":owned_only": owned_only.unwrap_or(false),
+                    ":limit": limit,
+                },
+                |row| {
+                    let old_price: f64 = row.get("old_price")?;
+                    let change: f64 = row.get("change")?;
+                    Ok(PriceMove {
+                        card_id: row.get("card_id")?,
+                        card_name: row.get("name")?,
+                        set_code: row.get("set_code")?,
+                        set_name: row.get("set_name")?,
+                        set_rarity: row.get("set_rarity")?,
+                        artwork: row.get("artwork")?,
+                        old_price,
+                        new_price: row.get("new_price")?,
+                        change: (change * 100.0).round() / 100.0,
+                        change_percent: (change * 10000.0 / old_price).round() / 100.0,
+                        currency: row.get("currency")?,
+                        collection_amount: row.get("collection_amount")?,
+                    })
+                },
+            )
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<_, _>>().map_err(|e| e.to_string())
+    };
+
+    Ok(PriceMovers {
+        gainers: query("up")?,
+        losers: query("down")?,
+    })
+}
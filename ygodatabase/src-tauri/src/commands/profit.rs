@@ -0,0 +1,566 @@
+use rusqlite::{named_params, Connection, OptionalExtension};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::commands::collection::{apply_collection_amount, last_change_id};
+use crate::currency::{latest_date, round2, ExchangeRates, BASE_CURRENCY};
+use crate::db::open_db;
+use crate::models::PrintingKey;
+
+// ---------------------------------------------------------------------------
+// Types
+// ---------------------------------------------------------------------------
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Sale {
+    pub id: i64,
+    pub card_id: i64,
+    pub card_name: Option<String>,
+    pub set_code: String,
+    pub set_rarity: String,
+    pub artwork: i64,
+    pub quantity: i64,
+    pub unit_price: f64,
+    pub currency: String,
+    pub sold_on: String,
+    /// Copies whose purchase cost was known
+    pub costed_quantity: i64,
+    pub cost_basis: f64,
+    /// Proceeds of the costed copies minus their cost; None if none were costed
+    pub realised_gain: Option<f64>,
+}
+
+/// Cost basis and gains of one card, one set or the whole collection.
+/// Only copies with a recorded purchase (`costed_copies`) count towards
+/// cost_basis / current_value / unrealised_gain; copies without a current
+/// price are valued at 0.
+#[derive(Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfitLossRow {
+    pub label: String,
+    pub card_id: Option<i64>,
+    pub set_name: Option<String>,
+    pub owned_copies: i64,
+    pub costed_copies: i64,
+    pub cost_basis: f64,
+    pub current_value: f64,
+    pub unrealised_gain: f64,
+    pub realised_gain: f64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfitLossReport {
+    pub rows: Vec<ProfitLossRow>,
+    pub currency: String,
+    pub rate_date: Option<String>,
+    /// Currencies without a stored rate; their amounts are left out
+    pub missing_rates: Vec<String>,
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Raw copies owned of the printing; errors when card_sets has no such row.
+pub fn owned_amount(conn: &Connection, key: &PrintingKey) -> Result<i64, String> {
+    conn.query_row(
+        "SELECT COALESCE(collection_amount, 0) FROM card_sets
+         WHERE card_id = ?1 AND set_code = ?2 AND set_rarity = ?3
+           AND COALESCE(artwork, 0) = ?4",
+        (key.card_id, &key.set_code, &key.set_rarity, key.artwork),
+        |row| row.get(0),
+    )
+    .optional()
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| format!(
+        "No printing {} / {} (artwork {}) for card {}",
+        key.set_code, key.set_rarity, key.artwork, key.card_id
+    ))
+}
+
+/// Returns `date` as YYYY-MM-DD (today when None), rejecting anything
+/// SQLite's date() doesn't round-trip.
+pub fn checked_date(conn: &Connection, date: Option<String>) -> Result<String, String> {
+    let parsed: Option<String> = conn
+        .query_row("SELECT date(COALESCE(?1, 'now'))", [&date], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    match (parsed, date) {
+        (Some(p), None) => Ok(p),
+        (Some(p), Some(d)) if p == d => Ok(p),
+        (_, d) => Err(format!("Invalid date '{}', expected YYYY-MM-DD", d.unwrap_or_default())),
+    }
+}
+
+/// Takes `quantity` copies out of the printing's lots, oldest first.
+/// Returns (lot id, copies, unit_cost, currency) per lot touched; fewer
+/// copies than asked for come back when the lots run out.
+fn consume_lots(conn: &Connection, key: &PrintingKey, quantity: i64) -> Result<Vec<(i64, i64, f64, String)>, String> {
+    let lots: Vec<(i64, i64, f64, String)> = {
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, remaining, unit_cost, currency FROM acquisitions
+                 WHERE card_id = ?1 AND set_code = ?2 AND set_rarity = ?3 AND artwork = ?4
+                   AND remaining > 0
+                 ORDER BY acquired_on, id",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map((key.card_id, &key.set_code, &key.set_rarity, key.artwork), |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+            })
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<_, _>>().map_err(|e| e.to_string())?
+    };
+
+    let mut left = quantity;
+    let mut used = Vec::new();
+    for (id, remaining, unit_cost, currency) in lots {
+        if left <= 0 {
+            break;
+        }
+        let n = remaining.min(left);
+        conn.execute("UPDATE acquisitions SET remaining = remaining - ?1 WHERE id = ?2", (n, id))
+            .map_err(|e| e.to_string())?;
+        used.push((id, n, unit_cost, currency));
+        left -= n;
+    }
+    Ok(used)
+}
+
+/// Takes one copy out of the printing's oldest open lot, for a copy that
+/// leaves the raw collection but stays owned (graded). Returns the lot,
+/// None when no lot is open.
+pub fn take_lot_copy(conn: &Connection, key: &PrintingKey) -> Result<Option<i64>, String> {
+    let lot_id: Option<i64> = conn
+        .query_row(
+            "SELECT id FROM acquisitions
+             WHERE card_id = ?1 AND set_code = ?2 AND set_rarity = ?3 AND artwork = ?4
+               AND remaining > 0
+             ORDER BY acquired_on, id
+             LIMIT 1",
+            (key.card_id, &key.set_code, &key.set_rarity, key.artwork),
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    if let Some(id) = lot_id {
+        conn.execute("UPDATE acquisitions SET remaining = remaining - 1 WHERE id = ?1", [id])
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(lot_id)
+}
+
+/// Puts a copy taken by take_lot_copy back into its lot, if the lot is
+/// still there.
+pub fn return_lot_copy(conn: &Connection, lot_id: i64) -> Result<(), String> {
+    conn.execute(
+        "UPDATE acquisitions SET remaining = remaining + 1 WHERE id = ?1 AND remaining < quantity",
+        [lot_id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Reverses what the collection change `history_id` recorded in the
+/// ledger: its purchase lot is removed, its sale is removed and the sold
+/// copies go back to the lots they came from.
+/// Called by apply_collection_amount when that change is undone.
+pub fn undo_ledger(conn: &Connection, history_id: i64) -> Result<(), String> {
+    conn.execute(
+        "UPDATE acquisitions
+         SET remaining = remaining + (
+             SELECT SUM(sl.quantity) FROM sale_lots sl
+             JOIN sales s ON s.id = sl.sale_id
+             WHERE s.history_id = ?1 AND sl.lot_id = acquisitions.id
+         )
+         WHERE id IN (
+             SELECT sl.lot_id FROM sale_lots sl
+             JOIN sales s ON s.id = sl.sale_id
+             WHERE s.history_id = ?1
+         )",
+        [history_id],
+    )
+    .map_err(|e| e.to_string())?;
+    conn.execute(
+        "DELETE FROM sale_lots WHERE sale_id IN (SELECT id FROM sales WHERE history_id = ?1)",
+        [history_id],
+    )
+    .map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM sales WHERE history_id = ?1", [history_id])
+        .map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM acquisitions WHERE history_id = ?1", [history_id])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Keeps the open lots within the owned amount after copies were removed
+/// other than by a sale, so their cost leaves the cost basis without a gain.
+/// Called by apply_collection_amount whenever owned copies go down.
+pub fn clamp_lots(conn: &Connection, key: &PrintingKey, owned: i64) -> Result<(), String> {
+    let open: i64 = conn
+        .query_row(
+            "SELECT COALESCE(SUM(remaining), 0) FROM acquisitions
+             WHERE card_id = ?1 AND set_code = ?2 AND set_rarity = ?3 AND artwork = ?4",
+            (key.card_id, &key.set_code, &key.set_rarity, key.artwork),
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    if open > owned {
+        consume_lots(conn, key, open - owned.max(0))?;
+    }
+    Ok(())
+}
+
+fn group_row<'a>(
+    groups: &'a mut BTreeMap<String, ProfitLossRow>,
+    group_by: &str,
+    label: String,
+    card_id: i64,
+    set_name: Option<String>,
+) -> &'a mut ProfitLossRow {
+    groups.entry(label.clone()).or_insert_with(|| ProfitLossRow {
+        label,
+        card_id: (group_by == "card").then_some(card_id),
+        set_name: if group_by == "set" { set_name } else { None },
+        ..Default::default()
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Commands
+// ---------------------------------------------------------------------------
+
+/// Adds `quantity` bought copies of `printing` to the collection and records
+/// what they cost. `unit_cost` is per copy in `currency` (default EUR),
+/// `acquired_on` defaults to today, `source` is free text (shop, seller, ...).
+/// Returns the id of the new lot.
+#[tauri::command]
+pub fn add_purchase(
+    printing: PrintingKey,
+    quantity: i64,
+    unit_cost: f64,
+    currency: Option<String>,
+    acquired_on: Option<String>,
+    source: Option<String>,
+) -> Result<i64, String> {
+    if quantity <= 0 {
+        return Err("Quantity must be at least 1".to_string());
+    }
+    if unit_cost < 0.0 {
+        return Err("Cost cannot be negative".to_string());
+    }
+    let mut conn = open_db()?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let acquired_on = checked_date(&tx, acquired_on)?;
+
+    let owned = owned_amount(&tx, &printing)?;
+    apply_collection_amount(&tx, &printing, owned + quantity, "purchase", None)?;
+    let history_id = last_change_id(&tx, &printing)?;
+    tx.execute(
+        "INSERT INTO acquisitions
+            (card_id, set_code, set_rarity, artwork, quantity, remaining, unit_cost, currency, acquired_on,
+             source, history_id)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?5, ?6, ?7, ?8, ?9, ?10)",
+        (
+            printing.card_id, &printing.set_code, &printing.set_rarity, printing.artwork,
+            quantity, unit_cost, currency.unwrap_or_else(|| BASE_CURRENCY.to_string()), acquired_on,
+            source, history_id,
+        ),
+    ).map_err(|e| e.to_string())?;
+    let id = tx.last_insert_rowid();
+
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(id)
+}
+
+/// Removes `quantity` sold copies of `printing` from the collection and
+/// records the sale. The cost basis comes from the oldest open purchase lots
+/// (FIFO), converted to the sale currency with the latest stored rates.
+#[tauri::command]
+pub fn record_sale(
+    printing: PrintingKey,
+    quantity: i64,
+    unit_price: f64,
+    currency: Option<String>,
+    sold_on: Option<String>,
+) -> Result<Sale, String> {
+    if quantity <= 0 {
+        return Err("Quantity must be at least 1".to_string());
+    }
+    if unit_price < 0.0 {
+        return Err("Price cannot be negative".to_string());
+    }
+    let currency = currency.unwrap_or_else(|| BASE_CURRENCY.to_string());
+    let mut conn = open_db()?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let sold_on = checked_date(&tx, sold_on)?;
+
+    let owned = owned_amount(&tx, &printing)?;
+    if quantity > owned {
+        return Err(format!("Only {} copies owned, cannot sell {}", owned, quantity));
+    }
+
+    let rates = ExchangeRates::load(&tx)?;
+    let mut costed_quantity = 0;
+    let mut cost_basis = 0.0;
+    let lots = consume_lots(&tx, &printing, quantity)?;
+    for (_, n, unit_cost, lot_currency) in &lots {
+        let (cost, _) = rates.try_convert(*unit_cost, lot_currency, &currency)?;
+        costed_quantity += n;
+        cost_basis += cost * *n as f64;
+    }
+    let cost_basis = round2(cost_basis);
+
+    tx.execute(
+        "INSERT INTO sales
+            (card_id, set_code, set_rarity, artwork, quantity, unit_price, currency, sold_on,
+             costed_quantity, cost_basis)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        (
+            printing.card_id, &printing.set_code, &printing.set_rarity, printing.artwork,
+            quantity, unit_price, &currency, &sold_on, costed_quantity, cost_basis,
+        ),
+    ).map_err(|e| e.to_string())?;
+    let id = tx.last_insert_rowid();
+    // Which lots the sold copies came from, for undo_ledger
+    for (lot_id, n, _, _) in &lots {
+        tx.execute("INSERT INTO sale_lots (sale_id, lot_id, quantity) VALUES (?1, ?2, ?3)", (id, lot_id, n))
+            .map_err(|e| e.to_string())?;
+    }
+    apply_collection_amount(&tx, &printing, owned - quantity, "sale", None)?;
+    tx.execute("UPDATE sales SET history_id = ?1 WHERE id = ?2", (last_change_id(&tx, &printing)?, id))
+        .map_err(|e| e.to_string())?;
+
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(Sale {
+        id,
+        card_id: printing.card_id,
+        card_name: None,
+        set_code: printing.set_code,
+        set_rarity: printing.set_rarity,
+        artwork: printing.artwork,
+        quantity,
+        unit_price,
+        currency,
+        sold_on,
+        costed_quantity,
+        cost_basis,
+        realised_gain: (costed_quantity > 0)
+            .then(|| round2(unit_price * costed_quantity as f64 - cost_basis)),
+    })
+}
+
+/// Lists recorded sales, newest first, optionally for one card.
+#[tauri::command]
+pub fn get_sales(card_id: Option<i64>) -> Result<Vec<Sale>, String> {
+    let conn = open_db()?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT s.*, c.name
+             FROM sales s
+             LEFT JOIN cards c ON c.id = s.card_id
+             WHERE :card_id IS NULL OR s.card_id = :card_id
+             ORDER BY s.sold_on DESC, s.id DESC",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(named_params! { ":card_id": card_id }, |row| {
+            let quantity: i64 = row.get("costed_quantity")?;
+            let unit_price: f64 = row.get("unit_price")?;
+            let cost_basis: f64 = row.get("cost_basis")?;
+            Ok(Sale {
+                id: row.get("id")?,
+                card_id: row.get("card_id")?,
+                card_name: row.get("name")?,
+                set_code: row.get("set_code")?,
+                set_rarity: row.get("set_rarity")?,
+                artwork: row.get("artwork")?,
+                quantity: row.get("quantity")?,
+                unit_price,
+                currency: row.get("currency")?,
+                sold_on: row.get("sold_on")?,
+                costed_quantity: quantity,
+                cost_basis,
+                realised_gain: (quantity > 0).then(|| round2(unit_price * quantity as f64 - cost_basis)),
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<_, _>>().map_err(|e| e.to_string())
+}
+
+/// Cost basis, current value and unrealised / realised gain, grouped by
+/// "card", "set" or "collection" (a single row), in `currency` (default EUR).
+/// Costs and sale proceeds are converted with the latest stored rates.
+#[tauri::command]
+pub fn get_profit_loss(group_by: String, currency: Option<String>) -> Result<ProfitLossReport, String> {
+    let label_sql = match group_by.as_str() {
+        "card" => "c.name",
+        "set" => "COALESCE(cs.set_name, '(no set)')",
+        "collection" => "'Collection'",
+        _ => return Err(format!("Unknown grouping '{}'. Supported: card, set, collection", group_by)),
+    };
+    let currency = currency.unwrap_or_else(|| BASE_CURRENCY.to_string());
+    let conn = open_db()?;
+    let rates = ExchangeRates::load(&conn)?;
+    let mut rate_date: Option<String> = None;
+    let mut missing_rates = Vec::new();
+    let mut groups: BTreeMap<String, ProfitLossRow> = BTreeMap::new();
+
+    // Owned copies
+    let mut stmt = conn
+        .prepare(&format!("
+            SELECT {label_sql} AS label, MIN(cs.card_id) AS card_id, MIN(cs.set_name) AS set_name,
+                   SUM(cs.collection_amount) AS owned
+            FROM card_sets cs
+            JOIN cards c ON c.id = cs.card_id
+            WHERE cs.collection_amount > 0
+            GROUP BY label
+        "))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?, row.get::<_, Option<String>>(2)?, row.get::<_, i64>(3)?))
+        })
+        .map_err(|e| e.to_string())?;
+    for r in rows {
+        let (label, card_id, set_name, owned) = r.map_err(|e| e.to_string())?;
+        group_row(&mut groups, &group_by, label, card_id, set_name).owned_copies += owned;
+    }
+
+    // Cost and current value of the copies still in open lots
+    let mut stmt = conn
+        .prepare(&format!("
+            SELECT {label_sql} AS label, a.card_id, cs.set_name,
+                   a.remaining, a.unit_cost, a.currency,
+                   NULLIF(CAST(cs.set_price AS REAL), 0) AS price,
+                   COALESCE(cs.price_currency, 'USD') AS price_currency
+            FROM acquisitions a
+            JOIN cards c ON c.id = a.card_id
+            LEFT JOIN card_sets cs
+              ON cs.card_id = a.card_id
+             AND cs.set_code = a.set_code
+             AND cs.set_rarity = a.set_rarity
+             AND COALESCE(cs.artwork, 0) = a.artwork
+            WHERE a.remaining > 0
+        "))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>("label")?,
+                row.get::<_, i64>("card_id")?,
+                row.get::<_, Option<String>>("set_name")?,
+                row.get::<_, i64>("remaining")?,
+                row.get::<_, f64>("unit_cost")?,
+                row.get::<_, String>("currency")?,
+                row.get::<_, Option<f64>>("price")?,
+                row.get::<_, String>("price_currency")?,
+            ))
+        })
+        .map_err(|e| e.to_string())?;
+    for r in rows {
+        let (label, card_id, set_name, remaining, unit_cost, cost_currency, price, price_currency) =
+            r.map_err(|e| e.to_string())?;
+        // A lot whose cost can't be converted is left out whole, so its
+        // value doesn't count as gain
+        let Some((cost, d)) = rates.convert(unit_cost, &cost_currency, &currency, &mut missing_rates) else {
+            continue;
+        };
+        let g = group_row(&mut groups, &group_by, label, card_id, set_name);
+        rate_date = latest_date(rate_date, d);
+        g.costed_copies += remaining;
+        g.cost_basis += cost * remaining as f64;
+        if let Some((p, d)) = price.and_then(|p| rates.convert(p, &price_currency, &currency, &mut missing_rates)) {
+            rate_date = latest_date(rate_date, d);
+            g.current_value += p * remaining as f64;
+        }
+    }
+
+    // Realised gains of past sales
+    let mut stmt = conn
+        .prepare(&format!("
+            SELECT {label_sql} AS label, s.card_id, cs.set_name,
+                   s.costed_quantity * s.unit_price - s.cost_basis AS gain,
+                   s.currency
+            FROM sales s
+            JOIN cards c ON c.id = s.card_id
+            LEFT JOIN card_sets cs
+              ON cs.card_id = s.card_id
+             AND cs.set_code = s.set_code
+             AND cs.set_rarity = s.set_rarity
+             AND COALESCE(cs.artwork, 0) = s.artwork
+            WHERE s.costed_quantity > 0
+        "))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>("label")?,
+                row.get::<_, i64>("card_id")?,
+                row.get::<_, Option<String>>("set_name")?,
+                row.get::<_, f64>("gain")?,
+                row.get::<_, String>("currency")?,
+            ))
+        })
+        .map_err(|e| e.to_string())?;
+    for r in rows {
+        let (label, card_id, set_name, gain, sale_currency) = r.map_err(|e| e.to_string())?;
+        let Some((gain, d)) = rates.convert(gain, &sale_currency, &currency, &mut missing_rates) else {
+            continue;
+        };
+        rate_date = latest_date(rate_date, d);
+        group_row(&mut groups, &group_by, label, card_id, set_name).realised_gain += gain;
+    }
+
+    let rows = groups
+        .into_values()
+        .map(|mut g| {
+            g.cost_basis = round2(g.cost_basis);
+            g.current_value = round2(g.current_value);
+            g.unrealised_gain = round2(g.current_value - g.cost_basis);
+            g.realised_gain = round2(g.realised_gain);
+            g
+        })
+        .collect();
+
+    Ok(ProfitLossReport { rows, currency, rate_date, missing_rates })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_db;
+
+    #[test]
+    fn undone_sale_gives_its_copies_back_to_their_lots() {
+        let conn = test_db();
+        conn.execute_batch(
+            "INSERT INTO acquisitions (id, card_id, set_code, set_rarity, quantity, remaining, unit_cost, acquired_on, history_id)
+             VALUES (1, 1, 'SDY-006', 'Ultra Rare', 2, 0, 3.0, '2024-01-01', 10),
+                    (2, 1, 'SDY-006', 'Ultra Rare', 2, 1, 5.0, '2024-02-01', 11);
+             INSERT INTO sales (id, card_id, set_code, set_rarity, quantity, unit_price, sold_on, costed_quantity, cost_basis, history_id)
+             VALUES (1, 1, 'SDY-006', 'Ultra Rare', 3, 9.0, '2024-03-01', 3, 11.0, 12);
+             INSERT INTO sale_lots (sale_id, lot_id, quantity) VALUES (1, 1, 2), (1, 2, 1);",
+        )
+        .unwrap();
+
+        undo_ledger(&conn, 12).unwrap();
+        let remaining: Vec<i64> = conn
+            .prepare("SELECT remaining FROM acquisitions ORDER BY id")
+            .unwrap()
+            .query_map([], |r| r.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(remaining, vec![2, 2]);
+        let sales: i64 = conn.query_row("SELECT COUNT(*) FROM sales", [], |r| r.get(0)).unwrap();
+        assert_eq!(sales, 0);
+
+        // Undoing the second purchase removes its lot
+        undo_ledger(&conn, 11).unwrap();
+        let lots: i64 = conn.query_row("SELECT COUNT(*) FROM acquisitions", [], |r| r.get(0)).unwrap();
+        assert_eq!(lots, 1);
+    }
+}
@@ -0,0 +1,243 @@
+use rusqlite::Connection;
+use serde::Serialize;
+
+use crate::commands::collection::apply_collection_amount;
+use crate::commands::import::{normalize_rarity, query_candidates, ImportCandidate};
+use crate::db::open_db;
+use crate::models::PrintingKey;
+
+// ---------------------------------------------------------------------------
+// Types
+// ---------------------------------------------------------------------------
+
+/// Outcome of one quick-add line.
+///   status "added"     → `candidates` holds the printing, `new_amount` its amount
+///   status "ambiguous" → several rarities / artworks fit; nothing was added
+///   status "not_found" → no printing has this set code (and rarity)
+///   status "invalid"   → the line could not be read
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuickAddResult {
+    pub line: usize,
+    pub input: String,
+    pub set_code: Option<String>,
+    pub quantity: i64,
+    pub status: String,
+    pub candidates: Vec<ImportCandidate>,
+    pub new_amount: Option<i64>,
+    pub note: Option<String>,
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// "3", "3x", "x3" → 3
+fn parse_quantity(token: &str) -> Option<i64> {
+    let digits = token
+        .strip_suffix(['x', 'X'])
+        .or_else(|| token.strip_prefix(['x', 'X']))
+        .unwrap_or(token);
+    digits.parse::<i64>().ok().filter(|&n| n > 0)
+}
+
+/// Splits a pasted line into (set code, quantity, rarity). Accepted shapes:
+///   DUNE-EN056
+///   3 DUNE-EN056 / 3x DUNE-EN056
+///   DUNE-EN056 3 / DUNE-EN056 x3
+/// optionally followed by a rarity ("DUNE-EN056 2 Secret Rare").
+/// Commas, semicolons and tabs count as spaces.
+fn parse_line(line: &str) -> Result<(String, i64, Option<String>), String> {
+    let cleaned = line.replace([',', ';', '\t'], " ");
+    let mut tokens: Vec<&str> = cleaned.split_whitespace().collect();
+
+    let mut quantity = None;
+    if let Some(q) = tokens.first().and_then(|t| parse_quantity(t)) {
+        quantity = Some(q);
+        tokens.remove(0);
+    }
+    if tokens.is_empty() {
+        return Err("Missing set code".to_string());
+    }
+    let code = tokens.remove(0);
+    if !code.contains('-') {
+        return Err(format!("'{}' does not look like a set code (e.g. DUNE-EN056)", code));
+    }
+    if quantity.is_none() {
+        if let Some(q) = tokens.first().and_then(|t| parse_quantity(t)) {
+            quantity = Some(q);
+            tokens.remove(0);
+        }
+    }
+    let rarity = (!tokens.is_empty()).then(|| tokens.join(" "));
+    Ok((code.to_uppercase(), quantity.unwrap_or(1), rarity))
+}
+
+/// Resolves one set code and, when exactly one printing fits, adds
+/// `quantity` copies to it (logged with source "quick_add").
+fn add_by_code(
+    conn: &Connection,
+    line: usize,
+    input: &str,
+    set_code: &str,
+    quantity: i64,
+    rarity: Option<&str>,
+    artwork: Option<i64>,
+) -> Result<QuickAddResult, String> {
+    let mut result = QuickAddResult {
+        line,
+        input: input.to_string(),
+        set_code: Some(set_code.to_string()),
+        quantity,
+        status: "not_found".to_string(),
+        candidates: Vec::new(),
+        new_amount: None,
+        note: None,
+    };
+
+    let mut candidates = query_candidates(conn, Some(set_code), None)?;
+    if candidates.is_empty() {
+        return Ok(result);
+    }
+    if let Some(r) = rarity {
+        let wanted = normalize_rarity(r);
+        candidates.retain(|c| normalize_rarity(&c.set_rarity) == wanted);
+        if candidates.is_empty() {
+            result.note = Some(format!("No '{}' printing of {}", r, set_code));
+            return Ok(result);
+        }
+    }
+    if let Some(a) = artwork {
+        candidates.retain(|c| c.artwork == a);
+        if candidates.is_empty() {
+            result.note = Some(format!("No artwork {} for {}", a, set_code));
+            return Ok(result);
+        }
+    }
+
+    if candidates.len() > 1 {
+        result.status = "ambiguous".to_string();
+        result.candidates = candidates;
+        return Ok(result);
+    }
+
+    let c = &candidates[0];
+    let key = PrintingKey {
+        card_id: c.card_id,
+        set_code: c.set_code.clone(),
+        set_rarity: c.set_rarity.clone(),
+        artwork: c.artwork,
+    };
+    let current: i64 = conn
+        .query_row(
+            "SELECT COALESCE(collection_amount, 0) FROM card_sets
+             WHERE card_id = ?1 AND set_code = ?2 AND set_rarity = ?3
+               AND COALESCE(artwork, 0) = ?4",
+            (key.card_id, &key.set_code, &key.set_rarity, key.artwork),
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    apply_collection_amount(conn, &key, current + quantity, "quick_add", None)?;
+
+    result.status = "added".to_string();
+    result.new_amount = Some(current + quantity);
+    result.candidates = candidates;
+    Ok(result)
+}
+
+// ---------------------------------------------------------------------------
+// Commands
+// ---------------------------------------------------------------------------
+
+/// Adds `quantity` copies (default 1) of the printing with `set_code`.
+/// When the code has several rarities or artworks the result is "ambiguous"
+/// and lists them; call again with `rarity` / `artwork` to pick one.
+#[tauri::command]
+pub fn quick_add(
+    set_code: String,
+    quantity: Option<i64>,
+    rarity: Option<String>,
+    artwork: Option<i64>,
+) -> Result<QuickAddResult, String> {
+    let quantity = quantity.unwrap_or(1);
+    if quantity <= 0 {
+        return Err("Quantity must be at least 1".to_string());
+    }
+    let code = set_code.trim().to_uppercase();
+    let mut conn = open_db()?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let result = add_by_code(&tx, 1, &set_code, &code, quantity, rarity.as_deref(), artwork)?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(result)
+}
+
+/// Quick-adds a pasted list, one set code per line (see `parse_line` for the
+/// accepted shapes). Unambiguous lines are added in one transaction; the
+/// others are returned untouched so they can be resolved one by one with
+/// `quick_add`. Blank lines and lines starting with '#' are skipped.
+#[tauri::command]
+pub fn quick_add_batch(text: String) -> Result<Vec<QuickAddResult>, String> {
+    let mut conn = open_db()?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let mut results = Vec::new();
+
+    for (i, raw) in text.lines().enumerate() {
+        let input = raw.trim();
+        if input.is_empty() || input.starts_with('#') {
+            continue;
+        }
+        match parse_line(input) {
+            Ok((code, quantity, rarity)) => {
+                results.push(add_by_code(&tx, i + 1, input, &code, quantity, rarity.as_deref(), None)?);
+            }
+            Err(e) => results.push(QuickAddResult {
+                line: i + 1,
+                input: input.to_string(),
+                set_code: None,
+                quantity: 0,
+                status: "invalid".to_string(),
+                candidates: Vec::new(),
+                new_amount: None,
+                note: Some(e),
+            }),
+        }
+    }
+
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quantity_before_or_after_the_code() {
+        assert_eq!(parse_line("DUNE-EN056").unwrap(), ("DUNE-EN056".to_string(), 1, None));
+        assert_eq!(parse_line("3 dune-en056").unwrap(), ("DUNE-EN056".to_string(), 3, None));
+        assert_eq!(parse_line("3x DUNE-EN056").unwrap(), ("DUNE-EN056".to_string(), 3, None));
+        assert_eq!(parse_line("DUNE-EN056 x2").unwrap(), ("DUNE-EN056".to_string(), 2, None));
+        assert_eq!(parse_line("DUNE-EN056,\t4").unwrap(), ("DUNE-EN056".to_string(), 4, None));
+    }
+
+    #[test]
+    fn trailing_words_are_the_rarity() {
+        assert_eq!(
+            parse_line("DUNE-EN056 2 Secret Rare").unwrap(),
+            ("DUNE-EN056".to_string(), 2, Some("Secret Rare".to_string()))
+        );
+        assert_eq!(
+            parse_line("2 DUNE-EN056 Ultra Rare").unwrap(),
+            ("DUNE-EN056".to_string(), 2, Some("Ultra Rare".to_string()))
+        );
+    }
+
+    #[test]
+    fn rejects_lines_without_a_set_code() {
+        assert!(parse_line("").is_err());
+        assert!(parse_line("3x").is_err());
+        assert!(parse_line("Dark Magician").is_err());
+        // A zero quantity is not a quantity, so "0" is taken as the code
+        assert!(parse_line("0 DUNE-EN056").is_err());
+    }
+}
@@ -0,0 +1,344 @@
+use rusqlite::{named_params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::commands::collection::{apply_collection_amount, last_change_id};
+use crate::commands::profit::{checked_date, owned_amount};
+use crate::currency::BASE_CURRENCY;
+use crate::db::open_db;
+use crate::models::PrintingKey;
+
+// ---------------------------------------------------------------------------
+// Types
+// ---------------------------------------------------------------------------
+
+/// The editable fields of a sealed product, as sent by the frontend.
+/// Prices are per unit in `currency` (default EUR).
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SealedProductFields {
+    pub name: String,
+    pub product_type: Option<String>,
+    pub set_name: Option<String>,
+    pub quantity: i64,
+    pub purchase_price: Option<f64>,
+    pub market_price: Option<f64>,
+    pub currency: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SealedProduct {
+    pub id: i64,
+    pub name: String,
+    pub product_type: Option<String>,
+    pub set_name: Option<String>,
+    pub quantity: i64,
+    pub purchase_price: Option<f64>,
+    pub market_price: Option<f64>,
+    pub currency: String,
+    pub opened: i64,
+    pub added_at: String,
+}
+
+/// Copies of one printing pulled from an opened product.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SealedPull {
+    #[serde(flatten)]
+    pub printing: PrintingKey,
+    pub quantity: i64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SealedOpening {
+    pub id: i64,
+    pub product_id: i64,
+    pub opened_at: String,
+    pub note: Option<String>,
+    pub pulls: Vec<SealedPull>,
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Trims the name and set and rejects values the table would not make sense
+/// with. A set has to be one the card data knows.
+fn validate(conn: &Connection, product: SealedProductFields) -> Result<SealedProductFields, String> {
+    let name = product.name.trim().to_string();
+    if name.is_empty() {
+        return Err("A sealed product needs a name".to_string());
+    }
+    if product.quantity < 0 {
+        return Err("Quantity cannot be negative".to_string());
+    }
+    if product.purchase_price.is_some_and(|p| p < 0.0) || product.market_price.is_some_and(|p| p < 0.0) {
+        return Err("Prices cannot be negative".to_string());
+    }
+    let set_name = product.set_name.map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+    if let Some(set) = &set_name {
+        let known = conn
+            .prepare("SELECT 1 FROM card_sets WHERE set_name = ?1")
+            .and_then(|mut stmt| stmt.exists([set]))
+            .map_err(|e| e.to_string())?;
+        if !known {
+            return Err(format!("Unknown set '{}'", set));
+        }
+    }
+    Ok(SealedProductFields {
+        name,
+        product_type: product.product_type.map(|t| t.trim().to_string()).filter(|t| !t.is_empty()),
+        set_name,
+        currency: product.currency.or_else(|| Some(BASE_CURRENCY.to_string())),
+        ..product
+    })
+}
+
+/// Sealed products by name; `id` picks one.
+fn load_products(conn: &Connection, id: Option<i64>) -> Result<Vec<SealedProduct>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT sp.id, sp.name, sp.product_type, sp.set_name, sp.quantity,
+                    sp.purchase_price, sp.market_price, sp.currency, sp.added_at,
+                    (SELECT COUNT(*) FROM sealed_openings so WHERE so.product_id = sp.id) AS opened
+             FROM sealed_products sp
+             WHERE (:id IS NULL OR sp.id = :id)
+             ORDER BY sp.name COLLATE NOCASE, sp.id",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(named_params! { ":id": id }, |row| {
+            Ok(SealedProduct {
+                id: row.get("id")?,
+                name: row.get("name")?,
+                product_type: row.get("product_type")?,
+                set_name: row.get("set_name")?,
+                quantity: row.get("quantity")?,
+                purchase_price: row.get("purchase_price")?,
+                market_price: row.get("market_price")?,
+                currency: row.get("currency")?,
+                opened: row.get("opened")?,
+                added_at: row.get("added_at")?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+
+    let mut products = Vec::new();
+    for r in rows {
+        products.push(r.map_err(|e| e.to_string())?);
+    }
+    Ok(products)
+}
+
+fn load_product(conn: &Connection, id: i64) -> Result<SealedProduct, String> {
+    load_products(conn, Some(id))?
+        .pop()
+        .ok_or_else(|| format!("No sealed product with id {}", id))
+}
+
+// ---------------------------------------------------------------------------
+// Commands
+// ---------------------------------------------------------------------------
+
+#[tauri::command]
+pub fn add_sealed_product(product: SealedProductFields) -> Result<SealedProduct, String> {
+    let conn = open_db()?;
+    let p = validate(&conn, product)?;
+    conn.execute(
+        "INSERT INTO sealed_products
+            (name, product_type, set_name, quantity, purchase_price, market_price, currency)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        (&p.name, &p.product_type, &p.set_name, p.quantity, p.purchase_price, p.market_price, &p.currency),
+    )
+    .map_err(|e| e.to_string())?;
+    load_product(&conn, conn.last_insert_rowid())
+}
+
+/// Replaces everything about a sealed product except its id and openings.
+#[tauri::command]
+pub fn update_sealed_product(id: i64, product: SealedProductFields) -> Result<SealedProduct, String> {
+    let conn = open_db()?;
+    let p = validate(&conn, product)?;
+    let updated = conn
+        .execute(
+            "UPDATE sealed_products
+             SET name = ?1, product_type = ?2, set_name = ?3, quantity = ?4,
+                 purchase_price = ?5, market_price = ?6, currency = ?7
+             WHERE id = ?8",
+            (&p.name, &p.product_type, &p.set_name, p.quantity, p.purchase_price, p.market_price, &p.currency, id),
+        )
+        .map_err(|e| e.to_string())?;
+    if updated == 0 {
+        return Err(format!("No sealed product with id {}", id));
+    }
+    load_product(&conn, id)
+}
+
+/// Deletes a product with its opening records. Cards already pulled stay in
+/// the collection.
+#[tauri::command]
+pub fn remove_sealed_product(id: i64) -> Result<(), String> {
+    let mut conn = open_db()?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    tx.execute(
+        "DELETE FROM sealed_pulls
+         WHERE opening_id IN (SELECT id FROM sealed_openings WHERE product_id = ?1)",
+        [id],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM sealed_openings WHERE product_id = ?1", [id])
+        .map_err(|e| e.to_string())?;
+    let removed = tx
+        .execute("DELETE FROM sealed_products WHERE id = ?1", [id])
+        .map_err(|e| e.to_string())?;
+    if removed == 0 {
+        return Err(format!("No sealed product with id {}", id));
+    }
+    tx.commit().map_err(|e| e.to_string())
+}
+
+/// All sealed products by name, including ones with no units left.
+#[tauri::command]
+pub fn list_sealed_products() -> Result<Vec<SealedProduct>, String> {
+    let conn = open_db()?;
+    load_products(&conn, None)
+}
+
+/// Opens one unit of a product: takes it out of the sealed inventory,
+/// records the opening with its pulls and adds the pulled copies to the
+/// collection (source "open_product"). When the product has a purchase
+/// price, it is split evenly over the pulled copies and recorded as their
+/// purchase lots. All or nothing — an unknown printing or an empty product
+/// leaves everything untouched.
+#[tauri::command]
+pub fn open_sealed_product(
+    product_id: i64,
+    pulls: Vec<SealedPull>,
+    note: Option<String>,
+) -> Result<SealedOpening, String> {
+    if pulls.iter().any(|p| p.quantity <= 0) {
+        return Err("Pulled quantities must be at least 1".to_string());
+    }
+    let mut conn = open_db()?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let (quantity, name, purchase_price, currency): (i64, String, Option<f64>, String) = tx
+        .query_row(
+            "SELECT quantity, name, purchase_price, currency FROM sealed_products WHERE id = ?1",
+            [product_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("No sealed product with id {}", product_id))?;
+    if quantity == 0 {
+        return Err("No unopened units of this product left".to_string());
+    }
+    tx.execute("UPDATE sealed_products SET quantity = quantity - 1 WHERE id = ?1", [product_id])
+        .map_err(|e| e.to_string())?;
+
+    let note = note.map(|n| n.trim().to_string()).filter(|n| !n.is_empty());
+    tx.execute(
+        "INSERT INTO sealed_openings (product_id, note) VALUES (?1, ?2)",
+        (product_id, &note),
+    )
+    .map_err(|e| e.to_string())?;
+    let opening_id = tx.last_insert_rowid();
+
+    let copies: i64 = pulls.iter().map(|p| p.quantity).sum();
+    let unit_cost = purchase_price.filter(|_| copies > 0).map(|p| p / copies as f64);
+    let acquired_on = checked_date(&tx, None)?;
+
+    for pull in &pulls {
+        let key = &pull.printing;
+        // The same printing may be listed twice; the pull row adds them up
+        tx.execute(
+            "INSERT INTO sealed_pulls (opening_id, card_id, set_code, set_rarity, artwork, quantity)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+             ON CONFLICT (opening_id, card_id, set_code, set_rarity, artwork)
+             DO UPDATE SET quantity = quantity + excluded.quantity",
+            (opening_id, key.card_id, &key.set_code, &key.set_rarity, key.artwork, pull.quantity),
+        )
+        .map_err(|e| e.to_string())?;
+        // Fails on printings card_sets does not know, rolling back the opening
+        let owned = owned_amount(&tx, key)?;
+        apply_collection_amount(&tx, key, owned + pull.quantity, "open_product", None)?;
+        if let Some(unit_cost) = unit_cost {
+            let history_id = last_change_id(&tx, key)?;
+            tx.execute(
+                "INSERT INTO acquisitions
+                    (card_id, set_code, set_rarity, artwork, quantity, remaining, unit_cost, currency,
+                     acquired_on, source, history_id)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?5, ?6, ?7, ?8, ?9, ?10)",
+                (
+                    key.card_id, &key.set_code, &key.set_rarity, key.artwork, pull.quantity, unit_cost,
+                    &currency, &acquired_on, &name, history_id,
+                ),
+            )
+            .map_err(|e| e.to_string())?;
+        }
+    }
+
+    let opened_at: String = tx
+        .query_row("SELECT opened_at FROM sealed_openings WHERE id = ?1", [opening_id], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok(SealedOpening { id: opening_id, product_id, opened_at, note, pulls })
+}
+
+/// The recorded openings of a product with their pulls, newest first.
+#[tauri::command]
+pub fn list_sealed_openings(product_id: i64) -> Result<Vec<SealedOpening>, String> {
+    let conn = open_db()?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, opened_at, note FROM sealed_openings
+             WHERE product_id = ?1
+             ORDER BY opened_at DESC, id DESC",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([product_id], |row| {
+            Ok(SealedOpening {
+                id: row.get("id")?,
+                product_id,
+                opened_at: row.get("opened_at")?,
+                note: row.get("note")?,
+                pulls: Vec::new(),
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    let mut openings = Vec::new();
+    for r in rows {
+        openings.push(r.map_err(|e| e.to_string())?);
+    }
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT card_id, set_code, set_rarity, artwork, quantity FROM sealed_pulls
+             WHERE opening_id = ?1
+             ORDER BY set_code, set_rarity, card_id, artwork",
+        )
+        .map_err(|e| e.to_string())?;
+    for opening in &mut openings {
+        let rows = stmt
+            .query_map([opening.id], |row| {
+                Ok(SealedPull {
+                    printing: PrintingKey {
+                        card_id: row.get("card_id")?,
+                        set_code: row.get("set_code")?,
+                        set_rarity: row.get("set_rarity")?,
+                        artwork: row.get("artwork")?,
+                    },
+                    quantity: row.get("quantity")?,
+                })
+            })
+            .map_err(|e| e.to_string())?;
+        for r in rows {
+            opening.pulls.push(r.map_err(|e| e.to_string())?);
+        }
+    }
+    Ok(openings)
+}
@@ -0,0 +1,232 @@
+use rusqlite::{named_params, Connection, OptionalExtension};
+use serde::Serialize;
+
+use crate::commands::collection::{card_filter, stub_order, stub_page};
+use crate::db::open_db;
+use crate::filters::CardFilters;
+use crate::models::CardPage;
+
+// ---------------------------------------------------------------------------
+// Types
+// ---------------------------------------------------------------------------
+
+/// A named search: the full CardFilters set, an optional search box query
+/// on top, and the sort to run it with. Smart lists show up as collection
+/// views and get `match_count` refreshed after every sync.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SavedSearch {
+    pub id: i64,
+    pub name: String,
+    pub filters: CardFilters,
+    pub query: Option<String>,
+    pub sort: Option<String>,
+    pub smart_list: bool,
+    pub match_count: Option<i64>,
+    pub counted_at: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Saved searches by name; `id` picks one, `smart_only` keeps smart lists.
+fn load_searches(conn: &Connection, id: Option<i64>, smart_only: bool) -> Result<Vec<SavedSearch>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, name, filters, query, sort, smart_list, match_count,
+                    counted_at, created_at, updated_at
+             FROM saved_searches
+             WHERE (:id IS NULL OR id = :id)
+               AND (:smart_only = 0 OR smart_list = 1)
+             ORDER BY name COLLATE NOCASE",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(named_params! { ":id": id, ":smart_only": smart_only }, |row| {
+            Ok((
+                row.get::<_, String>("filters")?,
+                SavedSearch {
+                    id: row.get("id")?,
+                    name: row.get("name")?,
+                    filters: CardFilters::default(),
+                    query: row.get("query")?,
+                    sort: row.get("sort")?,
+                    smart_list: row.get("smart_list")?,
+                    match_count: row.get("match_count")?,
+                    counted_at: row.get("counted_at")?,
+                    created_at: row.get("created_at")?,
+                    updated_at: row.get("updated_at")?,
+                },
+            ))
+        })
+        .map_err(|e| e.to_string())?;
+
+    let mut searches = Vec::new();
+    for r in rows {
+        let (filters, mut search) = r.map_err(|e| e.to_string())?;
+        search.filters = serde_json::from_str(&filters)
+            .map_err(|e| format!("Saved search '{}' has unreadable filters: {}", search.name, e))?;
+        searches.push(search);
+    }
+    Ok(searches)
+}
+
+fn load_search(conn: &Connection, id: i64) -> Result<SavedSearch, String> {
+    load_searches(conn, Some(id), false)?
+        .pop()
+        .ok_or_else(|| format!("No saved search with id {}", id))
+}
+
+/// Trims the name and query and checks the query and sort parse, so a saved
+/// search can always be run.
+fn validate(
+    name: &str,
+    filters: &CardFilters,
+    query: Option<String>,
+    sort: Option<&str>,
+) -> Result<(String, Option<String>), String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("A saved search needs a name".to_string());
+    }
+    let query = query.map(|q| q.trim().to_string()).filter(|q| !q.is_empty());
+    card_filter(filters, query.as_deref())?;
+    stub_order(sort, false)?;
+    Ok((name, query))
+}
+
+fn check_name_free(conn: &Connection, name: &str, id: Option<i64>) -> Result<(), String> {
+    let taken = conn
+        .query_row(
+            "SELECT id FROM saved_searches WHERE name = ?1 COLLATE NOCASE AND id IS NOT ?2",
+            (name, id),
+            |row| row.get::<_, i64>(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    match taken {
+        Some(_) => Err(format!("A saved search named '{}' already exists", name)),
+        None => Ok(()),
+    }
+}
+
+/// Runs one search and records its stub count.
+fn run_search(conn: &Connection, search: &SavedSearch, offset: Option<i64>, limit: Option<i64>) -> Result<CardPage, String> {
+    let filter = card_filter(&search.filters, search.query.as_deref())?;
+    let page = stub_page(conn, &filter, search.sort.as_deref(), offset, limit)?;
+    conn.execute(
+        "UPDATE saved_searches SET match_count = ?1, counted_at = datetime('now') WHERE id = ?2",
+        (page.total, search.id),
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(page)
+}
+
+/// Re-evaluates every smart list against the current card data. Called when
+/// a sync finishes; returns the smart lists with their new counts.
+pub fn refresh_smart_lists(conn: &Connection) -> Result<Vec<SavedSearch>, String> {
+    for search in load_searches(conn, None, true)? {
+        run_search(conn, &search, Some(0), Some(1))?;
+    }
+    load_searches(conn, None, true)
+}
+
+// ---------------------------------------------------------------------------
+// Commands
+// ---------------------------------------------------------------------------
+
+/// Saves a new search. Names are unique, ignoring case.
+#[tauri::command]
+pub fn save_search(
+    name: String,
+    filters: Option<CardFilters>,
+    query: Option<String>,
+    sort: Option<String>,
+    smart_list: Option<bool>,
+) -> Result<SavedSearch, String> {
+    let filters = filters.unwrap_or_default();
+    let (name, query) = validate(&name, &filters, query, sort.as_deref())?;
+    let conn = open_db()?;
+    check_name_free(&conn, &name, None)?;
+    conn.execute(
+        "INSERT INTO saved_searches (name, filters, query, sort, smart_list)
+         VALUES (:name, :filters, :query, :sort, :smart_list)",
+        named_params! {
+            ":name": name,
+            ":filters": serde_json::to_string(&filters).map_err(|e| e.to_string())?,
+            ":query": query,
+            ":sort": sort,
+            ":smart_list": smart_list.unwrap_or(false),
+        },
+    )
+    .map_err(|e| e.to_string())?;
+    load_search(&conn, conn.last_insert_rowid())
+}
+
+/// Replaces everything about a saved search except its id.
+#[tauri::command]
+pub fn update_saved_search(
+    id: i64,
+    name: String,
+    filters: Option<CardFilters>,
+    query: Option<String>,
+    sort: Option<String>,
+    smart_list: Option<bool>,
+) -> Result<SavedSearch, String> {
+    let filters = filters.unwrap_or_default();
+    let (name, query) = validate(&name, &filters, query, sort.as_deref())?;
+    let conn = open_db()?;
+    check_name_free(&conn, &name, Some(id))?;
+    let updated = conn
+        .execute(
+            "UPDATE saved_searches
+             SET name = :name, filters = :filters, query = :query, sort = :sort,
+                 smart_list = :smart_list, match_count = NULL, counted_at = NULL,
+                 updated_at = datetime('now')
+             WHERE id = :id",
+            named_params! {
+                ":id": id,
+                ":name": name,
+                ":filters": serde_json::to_string(&filters).map_err(|e| e.to_string())?,
+                ":query": query,
+                ":sort": sort,
+                ":smart_list": smart_list.unwrap_or(false),
+            },
+        )
+        .map_err(|e| e.to_string())?;
+    if updated == 0 {
+        return Err(format!("No saved search with id {}", id));
+    }
+    load_search(&conn, id)
+}
+
+#[tauri::command]
+pub fn delete_saved_search(id: i64) -> Result<(), String> {
+    let conn = open_db()?;
+    let removed = conn
+        .execute("DELETE FROM saved_searches WHERE id = ?1", [id])
+        .map_err(|e| e.to_string())?;
+    if removed == 0 {
+        return Err(format!("No saved search with id {}", id));
+    }
+    Ok(())
+}
+
+/// All saved searches by name. `smart_only` lists just the smart lists.
+#[tauri::command]
+pub fn list_saved_searches(smart_only: Option<bool>) -> Result<Vec<SavedSearch>, String> {
+    let conn = open_db()?;
+    load_searches(&conn, None, smart_only.unwrap_or(false))
+}
+
+/// Runs a saved search against the current data; paging works as in
+/// search_card_stubs.
+#[tauri::command]
+pub fn run_saved_search(id: i64, offset: Option<i64>, limit: Option<i64>) -> Result<CardPage, String> {
+    let conn = open_db()?;
+    let search = load_search(&conn, id)?;
+    run_search(&conn, &search, offset, limit)
+}
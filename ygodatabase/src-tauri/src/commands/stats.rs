@@ -0,0 +1,307 @@
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+
+use crate::currency::{latest_date, round2, ExchangeRates, BASE_CURRENCY};
+use crate::db::open_db;
+
+// ---------------------------------------------------------------------------
+// Types
+// ---------------------------------------------------------------------------
+
+/// Owned copies sharing one value of a card or printing property. `cards`
+/// counts distinct cards; a None value groups cards without the property.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatBucket {
+    pub value: Option<String>,
+    pub copies: i64,
+    pub cards: i64,
+    pub total_value: f64,
+}
+
+/// Owned printings whose unit price falls in [min, max). Printings without a
+/// price form the band with min and max both None.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValueBand {
+    pub label: String,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub printings: i64,
+    pub copies: i64,
+    pub total_value: f64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TopPrinting {
+    pub card_id: i64,
+    pub card_name: String,
+    pub set_code: String,
+    pub set_name: Option<String>,
+    pub set_rarity: String,
+    pub artwork: i64,
+    pub owned: i64,
+    pub price: f64,
+    pub total_value: f64,
+}
+
+/// Everything the statistics dashboard shows, computed over owned printings.
+/// Values are in `currency`; unpriced copies count as 0.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CollectionStats {
+    pub total_copies: i64,
+    pub distinct_cards: i64,
+    pub distinct_printings: i64,
+    pub total_value: f64,
+    pub unpriced_copies: i64,
+    pub by_frame_type: Vec<StatBucket>,
+    pub by_attribute: Vec<StatBucket>,
+    pub by_race: Vec<StatBucket>,
+    pub by_rarity: Vec<StatBucket>,
+    pub by_set: Vec<StatBucket>,
+    pub by_archetype: Vec<StatBucket>,
+    pub value_distribution: Vec<ValueBand>,
+    pub top_printings: Vec<TopPrinting>,
+    pub currency: String,
+    pub rate_date: Option<String>,
+    /// Currencies without a stored rate; their amounts are left out
+    pub missing_rates: Vec<String>,
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+// Upper bounds of the value bands, in the report currency
+const VALUE_BANDS: &[f64] = &[1.0, 5.0, 10.0, 25.0, 50.0, 100.0];
+
+struct OwnedRow {
+    card_id: i64,
+    card_name: String,
+    frame_type: Option<String>,
+    attribute: Option<String>,
+    race: Option<String>,
+    archetype: Option<String>,
+    set_code: String,
+    set_name: Option<String>,
+    set_rarity: String,
+    artwork: i64,
+    owned: i64,
+    price: Option<f64>,
+    price_currency: String,
+}
+
+#[derive(Default)]
+struct Tally {
+    copies: i64,
+    cards: HashSet<i64>,
+    value: f64,
+}
+
+fn tally(map: &mut BTreeMap<Option<String>, Tally>, key: Option<String>, card_id: i64, copies: i64, value: f64) {
+    let t = map.entry(key).or_default();
+    t.copies += copies;
+    t.cards.insert(card_id);
+    t.value += value;
+}
+
+/// Buckets by copies, most first.
+fn buckets(map: BTreeMap<Option<String>, Tally>) -> Vec<StatBucket> {
+    let mut out: Vec<StatBucket> = map
+        .into_iter()
+        .map(|(value, t)| StatBucket {
+            value,
+            copies: t.copies,
+            cards: t.cards.len() as i64,
+            total_value: round2(t.value),
+        })
+        .collect();
+    out.sort_by_key(|b| std::cmp::Reverse(b.copies));
+    out
+}
+
+fn empty_bands() -> Vec<ValueBand> {
+    let mut bands = Vec::new();
+    let mut min = 0.0;
+    for &max in VALUE_BANDS {
+        bands.push(ValueBand {
+            label: format!("{}–{}", min, max),
+            min: Some(min),
+            max: Some(max),
+            printings: 0,
+            copies: 0,
+            total_value: 0.0,
+        });
+        min = max;
+    }
+    bands.push(ValueBand {
+        label: format!("{}+", min),
+        min: Some(min),
+        max: None,
+        printings: 0,
+        copies: 0,
+        total_value: 0.0,
+    });
+    bands.push(ValueBand {
+        label: "unpriced".to_string(),
+        min: None,
+        max: None,
+        printings: 0,
+        copies: 0,
+        total_value: 0.0,
+    });
+    bands
+}
+
+// ---------------------------------------------------------------------------
+// Commands
+// ---------------------------------------------------------------------------
+
+/// Aggregated statistics of the collection: copies, distinct cards and value
+/// per frame type, attribute, race, rarity, set and archetype, how the owned
+/// printings spread over price bands, and the `top_n` (default 10) most
+/// valuable owned printings by total value. Prices are converted to
+/// `currency` (default EUR).
+#[tauri::command]
+pub fn get_collection_stats(top_n: Option<i64>, currency: Option<String>) -> Result<CollectionStats, String> {
+    let conn = open_db()?;
+    let currency = currency.unwrap_or_else(|| BASE_CURRENCY.to_string());
+    let top_n = top_n.unwrap_or(10).clamp(0, 1000) as usize;
+    let rates = ExchangeRates::load(&conn)?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT cs.card_id, c.name, c.frameType, c.attribute, c.race, c.archetype,
+                    cs.set_code, cs.set_name, cs.set_rarity,
+                    COALESCE(cs.artwork, 0) AS artwork, cs.collection_amount,
+                    CAST(cs.set_price AS REAL) AS price,
+                    COALESCE(cs.price_currency, 'USD') AS price_currency
+             FROM card_sets cs
+             JOIN cards c ON c.id = cs.card_id
+             WHERE cs.collection_amount > 0",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(OwnedRow {
+                card_id: row.get("card_id")?,
+                card_name: row.get("name")?,
+                frame_type: row.get("frameType")?,
+                attribute: row.get::<_, Option<String>>("attribute")?.filter(|a| !a.is_empty()),
+                race: row.get("race")?,
+                archetype: row.get("archetype")?,
+                set_code: row.get("set_code")?,
+                set_name: row.get("set_name")?,
+                set_rarity: row.get("set_rarity")?,
+                artwork: row.get("artwork")?,
+                owned: row.get("collection_amount")?,
+                price: row.get::<_, Option<f64>>("price")?.filter(|&p| p > 0.0),
+                price_currency: row.get("price_currency")?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+
+    let mut stats = CollectionStats {
+        total_copies: 0,
+        distinct_cards: 0,
+        distinct_printings: 0,
+        total_value: 0.0,
+        unpriced_copies: 0,
+        by_frame_type: Vec::new(),
+        by_attribute: Vec::new(),
+        by_race: Vec::new(),
+        by_rarity: Vec::new(),
+        by_set: Vec::new(),
+        by_archetype: Vec::new(),
+        value_distribution: empty_bands(),
+        top_printings: Vec::new(),
+        currency: currency.clone(),
+        rate_date: None,
+        missing_rates: Vec::new(),
+    };
+    let mut cards = HashSet::new();
+    let mut frame_types = BTreeMap::new();
+    let mut attributes = BTreeMap::new();
+    let mut races = BTreeMap::new();
+    let mut rarities = BTreeMap::new();
+    let mut sets = BTreeMap::new();
+    let mut archetypes = BTreeMap::new();
+
+    for r in rows {
+        let row = r.map_err(|e| e.to_string())?;
+        let (card_id, owned) = (row.card_id, row.owned);
+        let price = row.price.and_then(|p| {
+            let (converted, date) = rates.convert(p, &row.price_currency, &currency, &mut stats.missing_rates)?;
+            stats.rate_date = latest_date(stats.rate_date.take(), date);
+            Some(converted)
+        });
+        let value = price.unwrap_or(0.0) * owned as f64;
+
+        stats.total_copies += owned;
+        stats.distinct_printings += 1;
+        stats.total_value += value;
+        cards.insert(card_id);
+
+        tally(&mut frame_types, row.frame_type, card_id, owned, value);
+        tally(&mut attributes, row.attribute, card_id, owned, value);
+        tally(&mut races, row.race, card_id, owned, value);
+        tally(&mut rarities, Some(row.set_rarity.clone()), card_id, owned, value);
+        tally(&mut sets, row.set_name.clone(), card_id, owned, value);
+        let names: Vec<String> = row.archetype
+            .and_then(|a| serde_json::from_str::<Option<Vec<String>>>(&a).ok().flatten())
+            .unwrap_or_default();
+        if names.is_empty() {
+            tally(&mut archetypes, None, card_id, owned, value);
+        }
+        for name in names {
+            tally(&mut archetypes, Some(name), card_id, owned, value);
+        }
+
+        let band = match price {
+            Some(p) => VALUE_BANDS.iter().position(|&max| p < max).unwrap_or(VALUE_BANDS.len()),
+            None => {
+                stats.unpriced_copies += owned;
+                VALUE_BANDS.len() + 1
+            }
+        };
+        let b = &mut stats.value_distribution[band];
+        b.printings += 1;
+        b.copies += owned;
+        b.total_value += value;
+
+        if let Some(p) = price {
+            stats.top_printings.push(TopPrinting {
+                card_id,
+                card_name: row.card_name,
+                set_code: row.set_code,
+                set_name: row.set_name,
+                set_rarity: row.set_rarity,
+                artwork: row.artwork,
+                owned,
+                price: round2(p),
+                total_value: value,
+            });
+        }
+    }
+
+    stats.distinct_cards = cards.len() as i64;
+    stats.total_value = round2(stats.total_value);
+    stats.by_frame_type = buckets(frame_types);
+    stats.by_attribute = buckets(attributes);
+    stats.by_race = buckets(races);
+    stats.by_rarity = buckets(rarities);
+    stats.by_set = buckets(sets);
+    stats.by_archetype = buckets(archetypes);
+    for b in &mut stats.value_distribution {
+        b.total_value = round2(b.total_value);
+    }
+    stats.top_printings.sort_by(|a, b| b.total_value.total_cmp(&a.total_value));
+    stats.top_printings.truncate(top_n);
+    for p in &mut stats.top_printings {
+        p.total_value = round2(p.total_value);
+    }
+
+    Ok(stats)
+}
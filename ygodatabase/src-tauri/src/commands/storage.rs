@@ -0,0 +1,478 @@
+use rusqlite::{named_params, Connection, OptionalExtension};
+use serde::Serialize;
+
+use crate::commands::collection::card_filter;
+use crate::commands::profit::owned_amount;
+use crate::db::open_db;
+use crate::filters::CardFilters;
+use crate::models::{PrintingKey, StoredCopies};
+
+// ---------------------------------------------------------------------------
+// Types
+// ---------------------------------------------------------------------------
+
+/// A storage location. `copies` counts what is stored directly in it, not in
+/// the locations below it.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageLocation {
+    pub id: i64,
+    pub parent_id: Option<i64>,
+    pub name: String,
+    pub kind: String,
+    pub position: Option<i64>,
+    pub path: String,
+    pub copies: i64,
+}
+
+/// One binder pocket: a printing and how many copies of it are owned.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BinderSlot {
+    pub slot: i64,
+    pub card_id: i64,
+    pub card_name: String,
+    pub set_code: String,
+    pub set_name: Option<String>,
+    pub set_rarity: String,
+    pub artwork: i64,
+    pub owned: i64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BinderPage {
+    pub page: i64,
+    pub slots: Vec<BinderSlot>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BinderLayout {
+    pub pages: Vec<BinderPage>,
+    pub pockets_per_page: i64,
+    pub printings: i64,
+    pub owned_printings: i64,
+}
+
+// Nesting order: a location only holds kinds that come after its own
+const KINDS: &[&str] = &["box", "binder", "page", "slot"];
+
+// Every location with its full path and a key that sorts it under its
+// parent, siblings by position then name. `ids` lists the location and its
+// ancestors as "/1/4/9/", to find everything below a location; sort_key
+// can't do that, names may contain '/'.
+const LOCATION_TREE: &str = "
+    WITH RECURSIVE tree (id, path, sort_key, ids) AS (
+        SELECT id, name, printf('%010d', COALESCE(position, 0)) || name, '/' || id || '/'
+        FROM storage_locations WHERE parent_id IS NULL
+        UNION ALL
+        SELECT l.id, tree.path || ' / ' || l.name,
+               tree.sort_key || '/' || printf('%010d', COALESCE(l.position, 0)) || l.name,
+               tree.ids || l.id || '/'
+        FROM storage_locations l
+        JOIN tree ON l.parent_id = tree.id
+    )
+";
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+fn kind_rank(kind: &str) -> Result<usize, String> {
+    KINDS
+        .iter()
+        .position(|k| *k == kind)
+        .ok_or_else(|| format!("Unknown location kind '{}' (use box, binder, page or slot)", kind))
+}
+
+/// Locations in tree order; `id` picks one.
+fn load_locations(conn: &Connection, id: Option<i64>) -> Result<Vec<StorageLocation>, String> {
+    let sql = format!("
+        {LOCATION_TREE}
+        SELECT l.id, l.parent_id, l.name, l.kind, l.position, tree.path,
+               (SELECT COALESCE(SUM(quantity), 0) FROM stored_copies s
+                WHERE s.location_id = l.id) AS copies
+        FROM storage_locations l
+        JOIN tree ON tree.id = l.id
+        WHERE (:id IS NULL OR l.id = :id)
+        ORDER BY tree.sort_key
+    ");
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(named_params! { ":id": id }, |row| {
+            Ok(StorageLocation {
+                id: row.get("id")?,
+                parent_id: row.get("parent_id")?,
+                name: row.get("name")?,
+                kind: row.get("kind")?,
+                position: row.get("position")?,
+                path: row.get("path")?,
+                copies: row.get("copies")?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<_, _>>().map_err(|e| e.to_string())
+}
+
+fn load_location(conn: &Connection, id: i64) -> Result<StorageLocation, String> {
+    load_locations(conn, Some(id))?
+        .pop()
+        .ok_or_else(|| format!("No storage location with id {}", id))
+}
+
+/// Checks a location of `kind` named `name` may sit under `parent_id`: the
+/// parent exists and is an earlier kind (which also keeps a location out of
+/// its own children), and has no other child of that name.
+fn check_placement(
+    conn: &Connection,
+    id: Option<i64>,
+    name: &str,
+    kind: &str,
+    parent_id: Option<i64>,
+) -> Result<(), String> {
+    if name.is_empty() {
+        return Err("A storage location needs a name".to_string());
+    }
+    let rank = kind_rank(kind)?;
+    if let Some(parent_id) = parent_id {
+        let parent = load_location(conn, parent_id)?;
+        if kind_rank(&parent.kind)? >= rank {
+            return Err(format!("A {} cannot go inside a {}", kind, parent.kind));
+        }
+    }
+    let taken = conn
+        .query_row(
+            "SELECT id FROM storage_locations
+             WHERE parent_id IS ?1 AND name = ?2 COLLATE NOCASE AND id IS NOT ?3",
+            (parent_id, name, id),
+            |row| row.get::<_, i64>(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    if taken.is_some() {
+        return Err(format!("There already is a location named '{}' there", name));
+    }
+    Ok(())
+}
+
+/// Stored copies with their location paths, in tree then set-code order.
+/// `location_id` keeps one location (and with `below`, the ones inside it),
+/// `card_id` one card.
+pub fn load_stored(
+    conn: &Connection,
+    location_id: Option<i64>,
+    below: bool,
+    card_id: Option<i64>,
+) -> Result<Vec<StoredCopies>, String> {
+    let sql = format!("
+        {LOCATION_TREE}
+        SELECT s.location_id, tree.path, s.card_id, c.name, s.set_code, s.set_rarity,
+               s.artwork, s.quantity
+        FROM stored_copies s
+        JOIN tree ON tree.id = s.location_id
+        JOIN cards c ON c.id = s.card_id
+        WHERE (:card_id IS NULL OR s.card_id = :card_id)
+          AND (:location_id IS NULL OR s.location_id = :location_id OR (
+                :below = 1 AND instr(tree.ids, '/' || :location_id || '/') > 0
+              ))
+        ORDER BY tree.sort_key, s.set_code, s.set_rarity, s.artwork
+    ");
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(
+            named_params! { ":location_id": location_id, ":below": below, ":card_id": card_id },
+            |row| {
+                Ok(StoredCopies {
+                    location_id: row.get("location_id")?,
+                    path: row.get("path")?,
+                    card_id: row.get("card_id")?,
+                    card_name: row.get("name")?,
+                    set_code: row.get("set_code")?,
+                    set_rarity: row.get("set_rarity")?,
+                    artwork: row.get("artwork")?,
+                    quantity: row.get("quantity")?,
+                })
+            },
+        )
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<_, _>>().map_err(|e| e.to_string())
+}
+
+/// Keeps the stored copies of a printing within the owned amount after a
+/// collection change, taking them out of the most recently filled
+/// locations first. Called by apply_collection_amount whenever owned copies
+/// go down.
+pub fn clamp_stored(conn: &Connection, key: &PrintingKey, owned: i64) -> Result<(), String> {
+    let stored: Vec<(i64, i64)> = {
+        let mut stmt = conn
+            .prepare(
+                "SELECT location_id, quantity FROM stored_copies
+                 WHERE card_id = ?1 AND set_code = ?2 AND set_rarity = ?3 AND artwork = ?4
+                 ORDER BY rowid DESC",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map((key.card_id, &key.set_code, &key.set_rarity, key.artwork), |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<_, _>>().map_err(|e| e.to_string())?
+    };
+    let mut excess = stored.iter().map(|(_, q)| q).sum::<i64>() - owned.max(0);
+    for (location_id, quantity) in stored {
+        if excess <= 0 {
+            break;
+        }
+        let take = quantity.min(excess);
+        set_stored(conn, location_id, key, quantity - take)?;
+        excess -= take;
+    }
+    Ok(())
+}
+
+fn set_stored(conn: &Connection, location_id: i64, key: &PrintingKey, quantity: i64) -> Result<(), String> {
+    if quantity <= 0 {
+        conn.execute(
+            "DELETE FROM stored_copies
+             WHERE location_id = ?1 AND card_id = ?2 AND set_code = ?3 AND set_rarity = ?4 AND artwork = ?5",
+            (location_id, key.card_id, &key.set_code, &key.set_rarity, key.artwork),
+        )
+    } else {
+        conn.execute(
+            "INSERT INTO stored_copies (location_id, card_id, set_code, set_rarity, artwork, quantity)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+             ON CONFLICT (location_id, card_id, set_code, set_rarity, artwork)
+             DO UPDATE SET quantity = excluded.quantity",
+            (location_id, key.card_id, &key.set_code, &key.set_rarity, key.artwork, quantity),
+        )
+    }
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Commands
+// ---------------------------------------------------------------------------
+
+/// Adds a location under `parent_id` (None = top level). `kind` is box,
+/// binder, page or slot; `position` is e.g. the page or slot number.
+#[tauri::command]
+pub fn create_location(
+    name: String,
+    kind: String,
+    parent_id: Option<i64>,
+    position: Option<i64>,
+) -> Result<StorageLocation, String> {
+    let conn = open_db()?;
+    let name = name.trim().to_string();
+    let kind = kind.trim().to_lowercase();
+    check_placement(&conn, None, &name, &kind, parent_id)?;
+    conn.execute(
+        "INSERT INTO storage_locations (parent_id, name, kind, position) VALUES (?1, ?2, ?3, ?4)",
+        (parent_id, &name, &kind, position),
+    )
+    .map_err(|e| e.to_string())?;
+    load_location(&conn, conn.last_insert_rowid())
+}
+
+/// Renames, moves or renumbers a location; what is inside moves with it.
+#[tauri::command]
+pub fn update_location(
+    id: i64,
+    name: String,
+    parent_id: Option<i64>,
+    position: Option<i64>,
+) -> Result<StorageLocation, String> {
+    let conn = open_db()?;
+    let kind = load_location(&conn, id)?.kind;
+    let name = name.trim().to_string();
+    check_placement(&conn, Some(id), &name, &kind, parent_id)?;
+    conn.execute(
+        "UPDATE storage_locations SET name = ?1, parent_id = ?2, position = ?3 WHERE id = ?4",
+        (&name, parent_id, position, id),
+    )
+    .map_err(|e| e.to_string())?;
+    load_location(&conn, id)
+}
+
+/// Deletes a location with everything inside it. The copies stored there
+/// stay in the collection, just without a location.
+#[tauri::command]
+pub fn delete_location(id: i64) -> Result<(), String> {
+    let mut conn = open_db()?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    load_location(&tx, id)?;
+    let ids: Vec<i64> = {
+        let mut stmt = tx
+            .prepare(
+                "WITH RECURSIVE below (id) AS (
+                     SELECT ?1
+                     UNION ALL
+                     SELECT l.id FROM storage_locations l JOIN below ON l.parent_id = below.id
+                 )
+                 SELECT id FROM below",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt.query_map([id], |row| row.get(0)).map_err(|e| e.to_string())?;
+        rows.collect::<Result<_, _>>().map_err(|e| e.to_string())?
+    };
+    // Children come after their parents, so delete from the end
+    for location_id in ids.into_iter().rev() {
+        tx.execute("DELETE FROM stored_copies WHERE location_id = ?1", [location_id])
+            .map_err(|e| e.to_string())?;
+        tx.execute("DELETE FROM storage_locations WHERE id = ?1", [location_id])
+            .map_err(|e| e.to_string())?;
+    }
+    tx.commit().map_err(|e| e.to_string())
+}
+
+/// Every location in tree order (parents before their children), with its
+/// full path, so the frontend can build the tree from parent_id.
+#[tauri::command]
+pub fn list_locations() -> Result<Vec<StorageLocation>, String> {
+    let conn = open_db()?;
+    load_locations(&conn, None)
+}
+
+/// Puts `quantity` copies of a printing at a location (0 takes them out).
+/// Copies are counted once: the printing's stored copies across all
+/// locations cannot exceed the owned amount.
+#[tauri::command]
+pub fn store_copies(
+    location_id: i64,
+    card_id: i64,
+    set_code: String,
+    rarity: String,
+    artwork: i64,
+    quantity: i64,
+) -> Result<Vec<StoredCopies>, String> {
+    if quantity < 0 {
+        return Err("Quantity cannot be negative".to_string());
+    }
+    let conn = open_db()?;
+    load_location(&conn, location_id)?;
+    let key = PrintingKey { card_id, set_code, set_rarity: rarity, artwork };
+    let owned = owned_amount(&conn, &key)?;
+    let elsewhere: i64 = conn
+        .query_row(
+            "SELECT COALESCE(SUM(quantity), 0) FROM stored_copies
+             WHERE card_id = ?1 AND set_code = ?2 AND set_rarity = ?3 AND artwork = ?4
+               AND location_id != ?5",
+            (key.card_id, &key.set_code, &key.set_rarity, key.artwork, location_id),
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    if quantity > owned - elsewhere {
+        return Err(format!(
+            "Only {} copies owned and not stored elsewhere, cannot store {}",
+            (owned - elsewhere).max(0), quantity
+        ));
+    }
+    set_stored(&conn, location_id, &key, quantity)?;
+    load_stored(&conn, None, false, Some(card_id))
+}
+
+/// What is stored at a location; `include_children` (default true) adds
+/// everything in the locations inside it.
+#[tauri::command]
+pub fn get_location_contents(location_id: i64, include_children: Option<bool>) -> Result<Vec<StoredCopies>, String> {
+    let conn = open_db()?;
+    load_location(&conn, location_id)?;
+    load_stored(&conn, Some(location_id), include_children.unwrap_or(true), None)
+}
+
+/// Lays out the printings matching `filters` (and the search box `query`)
+/// as binder pages of `pockets_per_page` (default 9), filled in set-code
+/// order. `owned_only` leaves out printings with no copy owned; otherwise
+/// their pockets show where the gaps are.
+#[tauri::command]
+pub fn get_binder_layout(
+    filters: Option<CardFilters>,
+    query: Option<String>,
+    pockets_per_page: Option<i64>,
+    owned_only: Option<bool>,
+) -> Result<BinderLayout, String> {
+    let filters = filters.unwrap_or_default();
+    let pockets = pockets_per_page.unwrap_or(9).clamp(1, 36);
+    let filter = card_filter(&filters, query.as_deref())?;
+    let conn = open_db()?;
+
+    let sql = format!("
+        SELECT cs.card_id, c.name, cs.set_code, cs.set_name, cs.set_rarity,
+               COALESCE(cs.artwork, 0) AS artwork,
+               MAX(COALESCE(cs.collection_amount, 0)) AS owned
+        FROM cards c
+        JOIN card_sets cs ON cs.card_id = c.id
+        WHERE {}
+          AND cs.set_code IS NOT NULL AND cs.set_rarity IS NOT NULL
+          AND (:owned_only = 0 OR cs.collection_amount > 0)
+        GROUP BY cs.card_id, cs.set_code, cs.set_rarity, COALESCE(cs.artwork, 0)
+        ORDER BY cs.set_code, cs.set_rarity, artwork, cs.card_id
+    ", filter.clause);
+    let owned_only = owned_only.unwrap_or(false);
+    let mut params = filter.params_ref();
+    params.push((":owned_only", &owned_only));
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params.as_slice(), |row| {
+            Ok(BinderSlot {
+                slot: 0,
+                card_id: row.get("card_id")?,
+                card_name: row.get("name")?,
+                set_code: row.get("set_code")?,
+                set_name: row.get("set_name")?,
+                set_rarity: row.get("set_rarity")?,
+                artwork: row.get("artwork")?,
+                owned: row.get("owned")?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+
+    let mut layout = BinderLayout { pages: Vec::new(), pockets_per_page: pockets, printings: 0, owned_printings: 0 };
+    for r in rows {
+        let mut slot = r.map_err(|e| e.to_string())?;
+        let index = layout.printings;
+        layout.printings += 1;
+        if slot.owned > 0 {
+            layout.owned_printings += 1;
+        }
+        if index % pockets == 0 {
+            layout.pages.push(BinderPage { page: index / pockets + 1, slots: Vec::new() });
+        }
+        slot.slot = index % pockets + 1;
+        layout.pages.last_mut().unwrap().slots.push(slot);
+    }
+    Ok(layout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_db;
+
+    #[test]
+    fn contents_below_a_location_stay_in_its_subtree() {
+        let conn = test_db();
+        // "A_" must not take in the contents of "AB" as a LIKE pattern would
+        conn.execute_batch(
+            "INSERT INTO cards (id, name) VALUES (1, 'Dark Magician');
+             INSERT INTO card_sets (card_id, set_code, set_rarity, collection_amount) VALUES (1, 'SDY-006', 'Ultra Rare', 3);
+             INSERT INTO storage_locations (id, parent_id, name, kind) VALUES
+                 (1, NULL, 'A_', 'box'), (2, NULL, 'AB', 'box'),
+                 (3, 1, 'Binder', 'binder'), (4, 2, 'Binder', 'binder');
+             INSERT INTO stored_copies (location_id, card_id, set_code, set_rarity, artwork, quantity) VALUES
+                 (3, 1, 'SDY-006', 'Ultra Rare', 0, 1), (4, 1, 'SDY-006', 'Ultra Rare', 0, 2);",
+        )
+        .unwrap();
+
+        let below: Vec<(i64, String)> = load_stored(&conn, Some(1), true, None)
+            .unwrap()
+            .into_iter()
+            .map(|s| (s.location_id, s.path))
+            .collect();
+        assert_eq!(below, vec![(3, "A_ / Binder".to_string())]);
+        assert!(load_stored(&conn, Some(1), false, None).unwrap().is_empty());
+        assert_eq!(load_stored(&conn, None, false, Some(1)).unwrap().len(), 2);
+    }
+}
@@ -2,7 +2,9 @@ use std::io::{BufRead, BufReader};
 use std::process::{Command, Stdio};
 use tauri::{AppHandle, Emitter};
 
-use crate::db::get_db_path;
+use crate::commands::prices::record_value_snapshot;
+use crate::commands::searches::refresh_smart_lists;
+use crate::db::{get_db_path, open_db};
 
 /// Finds the project root from the exe path.
 /// In dev: exe is at target/debug/app.exe → pop 3 times → project root
@@ -52,6 +54,8 @@ fn find_python() -> Option<String> {
 
 /// Runs ygoprodeckscraper.py in a background thread, streaming each stdout/stderr
 /// line as a "sync-progress" event. Emits "sync-done" ("ok" | "error") when finished.
+/// After a successful sync the smart lists are re-counted and sent as
+/// "smart-lists-updated" (the list of SavedSearch), before "sync-done".
 ///
 /// The command returns immediately — the actual work happens asynchronously.
 /// The frontend should listen to "sync-progress" and "sync-done".
@@ -102,6 +106,18 @@ pub fn run_sync(app: AppHandle) -> Result<(), String> {
     std::thread::spawn(move || {
         match child.wait() {
             Ok(status) if status.success() => {
+                // Prices only change here, so this is where the daily value is recorded
+                if let Err(e) = open_db().and_then(|conn| record_value_snapshot(&conn)) {
+                    let _ = app.emit("sync-progress", format!("[warn] Value snapshot not recorded: {}", e));
+                }
+                match open_db().and_then(|conn| refresh_smart_lists(&conn)) {
+                    Ok(lists) => {
+                        let _ = app.emit("smart-lists-updated", &lists);
+                    }
+                    Err(e) => {
+                        let _ = app.emit("sync-progress", format!("[warn] Smart lists not refreshed: {}", e));
+                    }
+                }
                 let _ = app.emit("sync-done", "ok");
             }
             Ok(status) => {
@@ -0,0 +1,270 @@
+use rusqlite::{named_params, Connection, OptionalExtension};
+
+use crate::db::open_db;
+use crate::models::{CardNote, Tag};
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Tags by name with their card counts; `id` picks one.
+fn load_tags(conn: &Connection, id: Option<i64>) -> Result<Vec<Tag>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT t.id, t.name, t.color,
+                    (SELECT COUNT(*) FROM card_tags ct WHERE ct.tag_id = t.id) AS card_count
+             FROM tags t
+             WHERE (:id IS NULL OR t.id = :id)
+             ORDER BY t.name COLLATE NOCASE",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(named_params! { ":id": id }, |row| {
+            Ok(Tag {
+                id: row.get("id")?,
+                name: row.get("name")?,
+                color: row.get("color")?,
+                card_count: row.get("card_count")?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<_, _>>().map_err(|e| e.to_string())
+}
+
+fn load_tag(conn: &Connection, id: i64) -> Result<Tag, String> {
+    load_tags(conn, Some(id))?
+        .pop()
+        .ok_or_else(|| format!("No tag with id {}", id))
+}
+
+/// Trims the name and color; a tag needs a name that no other tag has.
+fn validate(conn: &Connection, name: &str, color: Option<String>, id: Option<i64>) -> Result<(String, Option<String>), String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("A tag needs a name".to_string());
+    }
+    let taken = conn
+        .query_row(
+            "SELECT id FROM tags WHERE name = ?1 COLLATE NOCASE AND id IS NOT ?2",
+            (&name, id),
+            |row| row.get::<_, i64>(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    if taken.is_some() {
+        return Err(format!("A tag named '{}' already exists", name));
+    }
+    let color = color.map(|c| c.trim().to_string()).filter(|c| !c.is_empty());
+    Ok((name, color))
+}
+
+fn check_card(conn: &Connection, card_id: i64) -> Result<(), String> {
+    let exists = conn
+        .prepare("SELECT 1 FROM cards WHERE id = ?1")
+        .and_then(|mut stmt| stmt.exists([card_id]))
+        .map_err(|e| e.to_string())?;
+    if !exists {
+        return Err(format!("No card found with id {}", card_id));
+    }
+    Ok(())
+}
+
+/// The id of the tag `name` (ignoring case), creating the tag on first use.
+pub fn tag_id_for(conn: &Connection, name: &str) -> Result<i64, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("A tag needs a name".to_string());
+    }
+    let existing = conn
+        .query_row("SELECT id FROM tags WHERE name = ?1 COLLATE NOCASE", [name], |row| row.get::<_, i64>(0))
+        .optional()
+        .map_err(|e| e.to_string())?;
+    match existing {
+        Some(id) => Ok(id),
+        None => {
+            conn.execute("INSERT INTO tags (name) VALUES (?1)", [name])
+                .map_err(|e| e.to_string())?;
+            Ok(conn.last_insert_rowid())
+        }
+    }
+}
+
+/// Tag names of one card, by name.
+pub fn card_tags(conn: &Connection, card_id: i64) -> Result<Vec<String>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT t.name FROM card_tags ct
+             JOIN tags t ON t.id = ct.tag_id
+             WHERE ct.card_id = ?1
+             ORDER BY t.name COLLATE NOCASE",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([card_id], |row| row.get::<_, String>(0))
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<_, _>>().map_err(|e| e.to_string())
+}
+
+/// Notes of one card: the card's own note first, then printing notes.
+pub fn card_notes(conn: &Connection, card_id: i64) -> Result<Vec<CardNote>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT card_id, set_code, set_rarity, artwork, note, updated_at
+             FROM card_notes
+             WHERE card_id = ?1
+             ORDER BY set_code IS NOT NULL, set_code, set_rarity, artwork",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([card_id], |row| {
+            Ok(CardNote {
+                card_id: row.get("card_id")?,
+                set_code: row.get("set_code")?,
+                set_rarity: row.get("set_rarity")?,
+                artwork: row.get("artwork")?,
+                note: row.get("note")?,
+                updated_at: row.get("updated_at")?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<_, _>>().map_err(|e| e.to_string())
+}
+
+// ---------------------------------------------------------------------------
+// Commands
+// ---------------------------------------------------------------------------
+
+#[tauri::command]
+pub fn create_tag(name: String, color: Option<String>) -> Result<Tag, String> {
+    let conn = open_db()?;
+    let (name, color) = validate(&conn, &name, color, None)?;
+    conn.execute("INSERT INTO tags (name, color) VALUES (?1, ?2)", (&name, &color))
+        .map_err(|e| e.to_string())?;
+    load_tag(&conn, conn.last_insert_rowid())
+}
+
+/// Renames / recolors a tag; the cards carrying it keep it.
+#[tauri::command]
+pub fn update_tag(id: i64, name: String, color: Option<String>) -> Result<Tag, String> {
+    let conn = open_db()?;
+    let (name, color) = validate(&conn, &name, color, Some(id))?;
+    let updated = conn
+        .execute("UPDATE tags SET name = ?1, color = ?2 WHERE id = ?3", (&name, &color, id))
+        .map_err(|e| e.to_string())?;
+    if updated == 0 {
+        return Err(format!("No tag with id {}", id));
+    }
+    load_tag(&conn, id)
+}
+
+/// Deletes a tag and takes it off every card and deck.
+#[tauri::command]
+pub fn delete_tag(id: i64) -> Result<(), String> {
+    let mut conn = open_db()?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM card_tags WHERE tag_id = ?1", [id])
+        .map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM deck_tags WHERE tag_id = ?1", [id])
+        .map_err(|e| e.to_string())?;
+    let removed = tx
+        .execute("DELETE FROM tags WHERE id = ?1", [id])
+        .map_err(|e| e.to_string())?;
+    if removed == 0 {
+        return Err(format!("No tag with id {}", id));
+    }
+    tx.commit().map_err(|e| e.to_string())
+}
+
+/// All tags by name, with how many cards carry each.
+#[tauri::command]
+pub fn list_tags() -> Result<Vec<Tag>, String> {
+    let conn = open_db()?;
+    load_tags(&conn, None)
+}
+
+/// Puts the tag `name` on a card, creating the tag on first use. Returns
+/// the card's tags.
+#[tauri::command]
+pub fn add_card_tag(card_id: i64, name: String) -> Result<Vec<String>, String> {
+    let mut conn = open_db()?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    check_card(&tx, card_id)?;
+    let tag_id = tag_id_for(&tx, &name)?;
+    tx.execute("INSERT OR IGNORE INTO card_tags (card_id, tag_id) VALUES (?1, ?2)", (card_id, tag_id))
+        .map_err(|e| e.to_string())?;
+    let tags = card_tags(&tx, card_id)?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(tags)
+}
+
+/// Takes the tag `name` off a card; the tag itself stays. Returns the
+/// card's tags.
+#[tauri::command]
+pub fn remove_card_tag(card_id: i64, name: String) -> Result<Vec<String>, String> {
+    let conn = open_db()?;
+    conn.execute(
+        "DELETE FROM card_tags
+         WHERE card_id = ?1 AND tag_id = (SELECT id FROM tags WHERE name = ?2 COLLATE NOCASE)",
+        (card_id, name.trim()),
+    )
+    .map_err(|e| e.to_string())?;
+    card_tags(&conn, card_id)
+}
+
+/// Sets the note of a card, or of one of its printings when `set_code` and
+/// `rarity` are given (`artwork` defaults to 0). An empty note deletes it.
+/// Returns the stored note.
+#[tauri::command]
+pub fn set_card_note(
+    card_id: i64,
+    set_code: Option<String>,
+    rarity: Option<String>,
+    artwork: Option<i64>,
+    note: String,
+) -> Result<Option<CardNote>, String> {
+    let conn = open_db()?;
+    let artwork = match (&set_code, &rarity, artwork) {
+        (None, None, None) => {
+            check_card(&conn, card_id)?;
+            None
+        }
+        (Some(code), Some(r), artwork) => {
+            let artwork = artwork.unwrap_or(0);
+            let exists = conn
+                .prepare(
+                    "SELECT 1 FROM card_sets
+                     WHERE card_id = ?1 AND set_code = ?2 AND set_rarity = ?3
+                       AND COALESCE(artwork, 0) = ?4",
+                )
+                .and_then(|mut stmt| stmt.exists((card_id, code, r, artwork)))
+                .map_err(|e| e.to_string())?;
+            if !exists {
+                return Err(format!("No printing {} / {} (artwork {}) for card {}", code, r, artwork, card_id));
+            }
+            Some(artwork)
+        }
+        _ => return Err("A printing note needs both the set code and the rarity".to_string()),
+    };
+
+    let note = note.trim();
+    if note.is_empty() {
+        conn.execute(
+            "DELETE FROM card_notes
+             WHERE card_id = ?1 AND set_code IS ?2 AND set_rarity IS ?3 AND artwork IS ?4",
+            (card_id, &set_code, &rarity, artwork),
+        )
+        .map_err(|e| e.to_string())?;
+        return Ok(None);
+    }
+    conn.execute(
+        "INSERT INTO card_notes (card_id, set_code, set_rarity, artwork, note)
+         VALUES (?1, ?2, ?3, ?4, ?5)
+         ON CONFLICT (card_id, COALESCE(set_code, ''), COALESCE(set_rarity, ''), COALESCE(artwork, -1))
+         DO UPDATE SET note = excluded.note, updated_at = datetime('now')",
+        (card_id, &set_code, &rarity, artwork, note),
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(card_notes(&conn, card_id)?
+        .into_iter()
+        .find(|n| n.set_code == set_code && n.set_rarity == rarity && n.artwork == artwork))
+}
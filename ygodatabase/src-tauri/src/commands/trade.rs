@@ -0,0 +1,393 @@
+use rusqlite::{Connection, OptionalExtension};
+use serde::Serialize;
+
+use crate::commands::export::{write_rows, ExportRow};
+use crate::commands::import::{match_file, ColumnMapping, ImportCandidate, ImportRow};
+use crate::currency::{latest_date, round2, ExchangeRates, BASE_CURRENCY};
+use crate::db::open_db;
+use crate::models::PrintingKey;
+
+// ---------------------------------------------------------------------------
+// Types
+// ---------------------------------------------------------------------------
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TradeEntry {
+    pub card_id: i64,
+    pub card_name: String,
+    pub set_code: String,
+    pub set_name: Option<String>,
+    pub set_rarity: String,
+    pub artwork: i64,
+    pub quantity: i64,
+    pub collection_amount: i64,
+    pub unit_price: Option<f64>,
+    pub currency: String, // source currency of unit_price
+}
+
+/// One line of a trade match: `quantity` copies of a printing changing hands.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TradeMatch {
+    pub line: usize, // line of the friend's file this came from
+    pub card_id: i64,
+    pub card_name: String,
+    pub set_code: String,
+    pub set_rarity: String,
+    pub artwork: i64,
+    pub quantity: i64,
+    pub unit_price: Option<f64>,
+    pub value: Option<f64>,
+}
+
+/// `they_offer`: cards from the friend's trade file that are on our wishlist.
+/// `we_offer`: cards from our trade list that are on the friend's want file.
+/// Prices are our local set_price, converted to `currency`.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TradeMatchReport {
+    pub they_offer: Vec<TradeMatch>,
+    pub we_offer: Vec<TradeMatch>,
+    pub they_offer_value: f64,
+    pub we_offer_value: f64,
+    pub currency: String,
+    pub rate_date: Option<String>,
+    /// Currencies without a stored rate; their amounts are left out
+    pub missing_rates: Vec<String>,
+    /// Rows that matched no printing: the trade file's first, then the want file's
+    pub unmatched: Vec<ImportRow>,
+}
+
+/// A wishlist entry with its still-unfilled quantity; None means "any".
+struct Wanted {
+    card_id: i64,
+    set_code: Option<String>,
+    set_rarity: Option<String>,
+    artwork: Option<i64>,
+    remaining: i64,
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Keeps the trade list within the owned amount after a collection change.
+/// Called by apply_collection_amount whenever owned copies go down.
+pub fn clamp_trade_amount(conn: &Connection, key: &PrintingKey, owned: i64) -> Result<(), String> {
+    if owned <= 0 {
+        conn.execute(
+            "DELETE FROM trade_list
+             WHERE card_id = ?1 AND set_code = ?2 AND set_rarity = ?3 AND artwork = ?4",
+            (key.card_id, &key.set_code, &key.set_rarity, key.artwork),
+        ).map_err(|e| e.to_string())?;
+    } else {
+        conn.execute(
+            "UPDATE trade_list SET quantity = ?5
+             WHERE card_id = ?1 AND set_code = ?2 AND set_rarity = ?3 AND artwork = ?4
+               AND quantity > ?5",
+            (key.card_id, &key.set_code, &key.set_rarity, key.artwork, owned),
+        ).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Reads a friend's file; matched and ambiguous rows are both usable (an
+/// ambiguous row just means "any of these printings"), in file order.
+fn read_friend_file(
+    conn: &Connection,
+    path: &str,
+    source: &str,
+    mapping: Option<ColumnMapping>,
+    unmatched: &mut Vec<ImportRow>,
+) -> Result<Vec<ImportRow>, String> {
+    let report = match_file(conn, path, source, mapping)?;
+    unmatched.extend(report.unmatched);
+    let mut rows: Vec<ImportRow> = report.matched.into_iter().chain(report.ambiguous).collect();
+    rows.sort_by_key(|r| r.line);
+    Ok(rows)
+}
+
+fn same_printing(a: &ImportCandidate, b: &ImportCandidate) -> bool {
+    a.card_id == b.card_id
+        && a.set_code == b.set_code
+        && a.set_rarity == b.set_rarity
+        && a.artwork == b.artwork
+}
+
+// ---------------------------------------------------------------------------
+// Commands
+// ---------------------------------------------------------------------------
+
+/// Marks `amount` copies of a printing as for trade (0 removes it from the
+/// trade list). Cannot exceed the owned amount.
+#[tauri::command]
+pub fn set_trade_amount(
+    card_id: i64,
+    set_code: String,
+    rarity: String,
+    artwork: i64,
+    amount: i64,
+) -> Result<(), String> {
+    let conn = open_db()?;
+    let owned: i64 = conn
+        .query_row(
+            "SELECT COALESCE(collection_amount, 0) FROM card_sets
+             WHERE card_id = ?1 AND set_code = ?2 AND set_rarity = ?3
+               AND COALESCE(artwork, 0) = ?4",
+            (card_id, &set_code, &rarity, artwork),
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!(
+            "No printing {} / {} (artwork {}) for card {}",
+            set_code, rarity, artwork, card_id
+        ))?;
+    if amount > owned {
+        return Err(format!("Only {} copies owned, cannot offer {}", owned, amount));
+    }
+
+    if amount <= 0 {
+        conn.execute(
+            "DELETE FROM trade_list
+             WHERE card_id = ?1 AND set_code = ?2 AND set_rarity = ?3 AND artwork = ?4",
+            (card_id, &set_code, &rarity, artwork),
+        ).map_err(|e| e.to_string())?;
+    } else {
+        conn.execute(
+            "INSERT OR REPLACE INTO trade_list (card_id, set_code, set_rarity, artwork, quantity)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            (card_id, &set_code, &rarity, artwork, amount),
+        ).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_trade_list() -> Result<Vec<TradeEntry>, String> {
+    let conn = open_db()?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT t.card_id, c.name, t.set_code, cs.set_name, t.set_rarity, t.artwork,
+                    t.quantity, COALESCE(cs.collection_amount, 0) AS collection_amount,
+                    NULLIF(CAST(cs.set_price AS REAL), 0) AS price,
+                    COALESCE(cs.price_currency, 'USD') AS price_currency
+             FROM trade_list t
+             JOIN card_sets cs
+               ON cs.card_id = t.card_id
+              AND cs.set_code = t.set_code
+              AND cs.set_rarity = t.set_rarity
+              AND COALESCE(cs.artwork, 0) = t.artwork
+             JOIN cards c ON c.id = t.card_id
+             ORDER BY c.name, t.set_code, t.set_rarity, t.artwork",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(TradeEntry {
+                card_id: row.get("card_id")?,
+                card_name: row.get("name")?,
+                set_code: row.get("set_code")?,
+                set_name: row.get("set_name")?,
+                set_rarity: row.get("set_rarity")?,
+                artwork: row.get("artwork")?,
+                quantity: row.get("quantity")?,
+                collection_amount: row.get("collection_amount")?,
+                unit_price: row.get("price")?,
+                currency: row.get("price_currency")?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<_, _>>().map_err(|e| e.to_string())
+}
+
+/// Writes the trade list to `path` in the export_collection file layout
+/// ("csv" or "json"), which match_trade_files reads back with source
+/// "ygodatabase". Returns the number of rows written.
+#[tauri::command]
+pub fn export_trade_list(path: String, format: String) -> Result<usize, String> {
+    let rows: Vec<ExportRow> = get_trade_list()?
+        .into_iter()
+        .map(|t| ExportRow {
+            card_name: t.card_name,
+            set_code: Some(t.set_code),
+            set_name: t.set_name,
+            rarity: Some(t.set_rarity),
+            artwork: t.artwork,
+            quantity: t.quantity,
+            unit_price: t.unit_price,
+            line_value: t.unit_price.map(|p| round2(p * t.quantity as f64)),
+            currency: t.currency,
+        })
+        .collect();
+    write_rows(&path, &format, &rows)?;
+    Ok(rows.len())
+}
+
+/// Compares a friend's files with our lists. `their_trades` is matched
+/// against our wishlist, `their_wants` against our trade list; either may be
+/// omitted. `source` / `mapping` describe the files as for the collection
+/// import (default source "ygodatabase"). Nothing is changed in the DB.
+#[tauri::command]
+pub fn match_trade_files(
+    their_trades: Option<String>,
+    their_wants: Option<String>,
+    source: Option<String>,
+    mapping: Option<ColumnMapping>,
+    currency: Option<String>,
+) -> Result<TradeMatchReport, String> {
+    if their_trades.is_none() && their_wants.is_none() {
+        return Err("Pick a trade file, a want file or both".to_string());
+    }
+    let source = source.unwrap_or_else(|| "ygodatabase".to_string());
+    let currency = currency.unwrap_or_else(|| BASE_CURRENCY.to_string());
+    let conn = open_db()?;
+    let rates = ExchangeRates::load(&conn)?;
+
+    let mut report = TradeMatchReport {
+        they_offer: Vec::new(),
+        we_offer: Vec::new(),
+        they_offer_value: 0.0,
+        we_offer_value: 0.0,
+        currency: currency.clone(),
+        rate_date: None,
+        missing_rates: Vec::new(),
+        unmatched: Vec::new(),
+    };
+
+    let mut price_stmt = conn
+        .prepare(
+            "SELECT CAST(set_price AS REAL), COALESCE(price_currency, 'USD')
+             FROM card_sets
+             WHERE card_id = ?1 AND set_code = ?2 AND set_rarity = ?3
+               AND COALESCE(artwork, 0) = ?4
+               AND CAST(set_price AS REAL) > 0",
+        )
+        .map_err(|e| e.to_string())?;
+    let mut price_of = |c: &ImportCandidate, rate_date: &mut Option<String>, missing: &mut Vec<String>| -> Result<Option<f64>, String> {
+        let price: Option<(f64, String)> = price_stmt
+            .query_row((c.card_id, &c.set_code, &c.set_rarity, c.artwork), |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .optional()
+            .map_err(|e| e.to_string())?;
+        match price {
+            Some((p, from)) => Ok(rates.convert(p, &from, &currency, missing).map(|(converted, date)| {
+                *rate_date = latest_date(rate_date.take(), date);
+                round2(converted)
+            })),
+            None => Ok(None),
+        }
+    };
+    let trade_match = |line: usize, c: &ImportCandidate, quantity: i64, unit_price: Option<f64>| TradeMatch {
+        line,
+        card_id: c.card_id,
+        card_name: c.card_name.clone(),
+        set_code: c.set_code.clone(),
+        set_rarity: c.set_rarity.clone(),
+        artwork: c.artwork,
+        quantity,
+        unit_price,
+        value: unit_price.map(|p| round2(p * quantity as f64)),
+    };
+
+    if let Some(path) = their_trades {
+        let rows = read_friend_file(&conn, &path, &source, mapping.clone(), &mut report.unmatched)?;
+
+        let mut wanted: Vec<Wanted> = {
+            let mut stmt = conn
+                .prepare(
+                    "SELECT card_id, set_code, set_rarity, artwork, quantity
+                     FROM wishlist
+                     ORDER BY set_code IS NULL, set_rarity IS NULL, artwork IS NULL, id",
+                )
+                .map_err(|e| e.to_string())?;
+            let rows = stmt
+                .query_map([], |row| {
+                    Ok(Wanted {
+                        card_id: row.get(0)?,
+                        set_code: row.get(1)?,
+                        set_rarity: row.get(2)?,
+                        artwork: row.get(3)?,
+                        remaining: row.get(4)?,
+                    })
+                })
+                .map_err(|e| e.to_string())?;
+            rows.collect::<Result<_, _>>().map_err(|e| e.to_string())?
+        };
+
+        for row in rows {
+            let mut left = row.quantity;
+            for w in wanted.iter_mut() {
+                if left == 0 {
+                    break;
+                }
+                if w.remaining == 0 {
+                    continue;
+                }
+                let fits = |c: &&ImportCandidate| {
+                    c.card_id == w.card_id
+                        && w.set_code.as_ref().is_none_or(|s| *s == c.set_code)
+                        && w.set_rarity.as_ref().is_none_or(|r| *r == c.set_rarity)
+                        && w.artwork.is_none_or(|a| a == c.artwork)
+                };
+                // An ambiguous row offers any of its printings; value it at the cheapest
+                let mut best: Option<(&ImportCandidate, Option<f64>)> = None;
+                for c in row.candidates.iter().filter(fits) {
+                    let price = price_of(c, &mut report.rate_date, &mut report.missing_rates)?;
+                    let better = match best {
+                        None => true,
+                        Some((_, best_price)) => price.is_some_and(|p| best_price.is_none_or(|b| p < b)),
+                    };
+                    if better {
+                        best = Some((c, price));
+                    }
+                }
+                if let Some((c, price)) = best {
+                    let n = left.min(w.remaining);
+                    report.they_offer.push(trade_match(row.line, c, n, price));
+                    w.remaining -= n;
+                    left -= n;
+                }
+            }
+        }
+    }
+
+    if let Some(path) = their_wants {
+        let rows = read_friend_file(&conn, &path, &source, mapping, &mut report.unmatched)?;
+
+        let mut offered: Vec<(ImportCandidate, i64)> = get_trade_list()?
+            .into_iter()
+            .map(|t| (ImportCandidate {
+                card_id: t.card_id,
+                card_name: t.card_name,
+                set_code: t.set_code,
+                set_name: t.set_name,
+                set_rarity: t.set_rarity,
+                artwork: t.artwork,
+            }, t.quantity))
+            .collect();
+
+        for row in rows {
+            let mut left = row.quantity;
+            for (ours, remaining) in offered.iter_mut() {
+                if left == 0 {
+                    break;
+                }
+                if *remaining == 0 || !row.candidates.iter().any(|c| same_printing(c, ours)) {
+                    continue;
+                }
+                let n = left.min(*remaining);
+                let price = price_of(ours, &mut report.rate_date, &mut report.missing_rates)?;
+                report.we_offer.push(trade_match(row.line, ours, n, price));
+                *remaining -= n;
+                left -= n;
+            }
+        }
+    }
+
+    report.they_offer_value = round2(report.they_offer.iter().filter_map(|m| m.value).sum());
+    report.we_offer_value = round2(report.we_offer.iter().filter_map(|m| m.value).sum());
+    Ok(report)
+}
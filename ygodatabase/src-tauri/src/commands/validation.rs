@@ -0,0 +1,388 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+
+use rusqlite::Connection;
+use serde::Serialize;
+
+use crate::commands::deck::{fetch_stubs_by_ids, format_ban_list, BanList, DeckStub};
+use crate::db::open_db;
+
+// ---------------------------------------------------------------------------
+// Types
+// ---------------------------------------------------------------------------
+
+/// One way a deck breaks the rules of a format. Serialized with a `kind`
+/// tag so the frontend can switch on it.
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(tag = "kind", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum DeckViolation {
+    MainSize { count: usize, min: usize, max: usize },
+    ExtraSize { count: usize, max: usize },
+    SideSize { count: usize, max: usize },
+    /// An id the card data does not know
+    UnknownCard { card_id: i64 },
+    /// A card outside the format's card pool
+    NotInFormat { card_id: i64, name: String },
+    /// A Fusion / Synchro / Xyz / Link monster in the main deck
+    ExtraDeckInMain { card_id: i64, name: String },
+    /// Anything else in the extra deck
+    MainDeckInExtra { card_id: i64, name: String },
+    /// More copies across main, extra and side than the ban list allows
+    TooManyCopies { card_id: i64, name: String, count: usize, limit: usize },
+    /// Genesys: the deck's points go over the cap
+    PointCap { total: i64, cap: i64 },
+}
+
+impl fmt::Display for DeckViolation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DeckViolation::MainSize { count, min, max } => {
+                write!(f, "Main deck has {} cards (needs {} to {})", count, min, max)
+            }
+            DeckViolation::ExtraSize { count, max } => {
+                write!(f, "Extra deck has {} cards (at most {})", count, max)
+            }
+            DeckViolation::SideSize { count, max } => {
+                write!(f, "Side deck has {} cards (at most {})", count, max)
+            }
+            DeckViolation::UnknownCard { card_id } => write!(f, "Unknown card id {}", card_id),
+            DeckViolation::NotInFormat { name, .. } => write!(f, "{} is not legal in this format", name),
+            DeckViolation::ExtraDeckInMain { name, .. } => {
+                write!(f, "{} belongs in the extra deck", name)
+            }
+            DeckViolation::MainDeckInExtra { name, .. } => {
+                write!(f, "{} cannot go in the extra deck", name)
+            }
+            DeckViolation::TooManyCopies { name, count, limit, .. } => {
+                write!(f, "{} copies of {} (at most {})", count, name, limit)
+            }
+            DeckViolation::PointCap { total, cap } => {
+                write!(f, "Deck is worth {} Genesys points (at most {})", total, cap)
+            }
+        }
+    }
+}
+
+/// Deck sizes and point cap of a format.
+#[derive(Debug, PartialEq)]
+pub struct FormatRules {
+    pub main_min: usize,
+    pub main_max: usize,
+    pub extra_max: usize,
+    pub side_max: usize,
+    pub point_cap: Option<i64>,
+}
+
+const STANDARD_RULES: FormatRules = FormatRules { main_min: 40, main_max: 60, extra_max: 15, side_max: 15, point_cap: None };
+const DUEL_LINKS_RULES: FormatRules = FormatRules { main_min: 20, main_max: 30, extra_max: 8, side_max: 8, point_cap: None };
+const GENESYS_RULES: FormatRules = FormatRules { point_cap: Some(100), ..STANDARD_RULES };
+
+// Frame types of the monsters that live in the extra deck
+const EXTRA_FRAMES: &[&str] = &["fusion", "synchro", "xyz", "link"];
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+fn is_extra_deck(stub: &DeckStub) -> bool {
+    stub.frame_type.as_deref().is_some_and(|f| {
+        let f = f.to_lowercase();
+        EXTRA_FRAMES.contains(&f.trim_end_matches("_pendulum"))
+    })
+}
+
+/// The rules of `format`; every format but Duel Links and Genesys plays
+/// with the standard deck sizes.
+pub fn format_rules(format: &str) -> FormatRules {
+    match format {
+        "Duel Links" => DUEL_LINKS_RULES,
+        "Genesys" => GENESYS_RULES,
+        _ => STANDARD_RULES,
+    }
+}
+
+// The cards of `ids` that are in the card pool of `format`, going by the
+// formats column. Genesys is played with the TCG card pool.
+fn format_pool(conn: &Connection, format: &str, ids: &[i64]) -> Result<HashSet<i64>, String> {
+    if ids.is_empty() {
+        return Ok(HashSet::new());
+    }
+    let pool_format = if format == "Genesys" { "TCG" } else { format };
+    let placeholders: Vec<String> = (1..=ids.len()).map(|i| format!("?{}", i)).collect();
+    let sql = format!(
+        "SELECT id, formats FROM cards WHERE formats IS NOT NULL AND id IN ({})",
+        placeholders.join(",")
+    );
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let params: Vec<&dyn rusqlite::types::ToSql> = ids.iter().map(|id| id as &dyn rusqlite::types::ToSql).collect();
+    let rows = stmt
+        .query_map(params.as_slice(), |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))
+        .map_err(|e| e.to_string())?;
+
+    let mut pool = HashSet::new();
+    for row in rows {
+        let (id, formats) = row.map_err(|e| e.to_string())?;
+        let formats: Vec<String> = serde_json::from_str(&formats).unwrap_or_default();
+        if formats.iter().any(|f| f == pool_format) {
+            pool.insert(id);
+        }
+    }
+    Ok(pool)
+}
+
+fn copy_limit(ban: &BanList, id: i64) -> usize {
+    if ban.forbidden.contains(&id) {
+        0
+    } else if ban.limited.contains(&id) {
+        1
+    } else if ban.semi_limited.contains(&id) {
+        2
+    } else {
+        3
+    }
+}
+
+// Every violation of a deck whose cards are `stubs`, in deck order: sizes,
+// then per-card problems, then the point cap.
+fn deck_violations(
+    rules: &FormatRules,
+    ban: &BanList,
+    pool: &HashSet<i64>,
+    stubs: &HashMap<i64, DeckStub>,
+    main_ids: &[i64],
+    extra_ids: &[i64],
+    side_ids: &[i64],
+) -> Vec<DeckViolation> {
+    let mut violations = Vec::new();
+    if !(rules.main_min..=rules.main_max).contains(&main_ids.len()) {
+        violations.push(DeckViolation::MainSize { count: main_ids.len(), min: rules.main_min, max: rules.main_max });
+    }
+    if extra_ids.len() > rules.extra_max {
+        violations.push(DeckViolation::ExtraSize { count: extra_ids.len(), max: rules.extra_max });
+    }
+    if side_ids.len() > rules.side_max {
+        violations.push(DeckViolation::SideSize { count: side_ids.len(), max: rules.side_max });
+    }
+
+    // Each card is reported once, however many copies are misplaced.
+    // The side deck may hold both kinds.
+    let mut reported = HashSet::new();
+    let mut counts: BTreeMap<i64, usize> = BTreeMap::new();
+    let sections = [(main_ids, Some(false)), (extra_ids, Some(true)), (side_ids, None)];
+    for (section, wants_extra) in sections {
+        for &id in section {
+            *counts.entry(id).or_insert(0) += 1;
+            let Some(stub) = stubs.get(&id) else {
+                if reported.insert(id) {
+                    violations.push(DeckViolation::UnknownCard { card_id: id });
+                }
+                continue;
+            };
+            if !pool.contains(&id) {
+                if reported.insert(id) {
+                    violations.push(DeckViolation::NotInFormat { card_id: id, name: stub.name.clone() });
+                }
+                continue;
+            }
+            let Some(wants_extra) = wants_extra else { continue };
+            if is_extra_deck(stub) == wants_extra || !reported.insert(id) {
+                continue;
+            }
+            let (card_id, name) = (stub.id, stub.name.clone());
+            violations.push(if wants_extra {
+                DeckViolation::MainDeckInExtra { card_id, name }
+            } else {
+                DeckViolation::ExtraDeckInMain { card_id, name }
+            });
+        }
+    }
+
+    // Cards outside the pool are already reported; no copy limit applies
+    for (id, count) in counts {
+        let Some(stub) = stubs.get(&id) else { continue };
+        if !pool.contains(&id) {
+            continue;
+        }
+        let limit = copy_limit(ban, id);
+        if count > limit {
+            violations.push(DeckViolation::TooManyCopies { card_id: id, name: stub.name.clone(), count, limit });
+        }
+    }
+
+    if let Some(cap) = rules.point_cap {
+        let total: i64 = main_ids
+            .iter()
+            .chain(extra_ids)
+            .chain(side_ids)
+            .filter_map(|id| stubs.get(id))
+            .map(|s| s.genesys_points)
+            .sum();
+        if total > cap {
+            violations.push(DeckViolation::PointCap { total, cap });
+        }
+    }
+    violations
+}
+
+/// Checks a deck against `format` (a ban list format name, or "Genesys")
+/// and returns every violation, in deck order: sizes, then per-card
+/// problems, then the Genesys point cap. An empty list means the deck is
+/// legal. Errors only on an unknown format or a database failure.
+pub fn check_deck(format: &str, main_ids: &[i64], extra_ids: &[i64], side_ids: &[i64]) -> Result<Vec<DeckViolation>, String> {
+    let conn = open_db()?;
+    // Genesys has no ban list: every card is at 3, the points cap the deck
+    let ban = if format == "Genesys" {
+        BanList::default()
+    } else {
+        format_ban_list(&conn, format)?
+    };
+
+    let mut ids: Vec<i64> = main_ids.iter().chain(extra_ids).chain(side_ids).copied().collect();
+    ids.sort();
+    ids.dedup();
+    let stubs = fetch_stubs_by_ids(&ids)?;
+    let pool = format_pool(&conn, format, &ids)?;
+    Ok(deck_violations(&format_rules(format), &ban, &pool, &stubs, main_ids, extra_ids, side_ids))
+}
+
+// ---------------------------------------------------------------------------
+// Commands
+// ---------------------------------------------------------------------------
+
+/// Checks a deck against the rules of `format`; see check_deck. The same
+/// check runs in save_deck when it is given a format.
+#[tauri::command]
+pub fn validate_deck(
+    format: String,
+    main_ids: Vec<i64>,
+    extra_ids: Vec<i64>,
+    side_ids: Vec<i64>,
+) -> Result<Vec<DeckViolation>, String> {
+    check_deck(&format, &main_ids, &extra_ids, &side_ids)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_db;
+
+    fn stub(id: i64, name: &str, frame_type: &str, genesys_points: i64) -> DeckStub {
+        DeckStub {
+            id,
+            name: name.to_string(),
+            img_path: None,
+            img_thumb_path: None,
+            frame_type: Some(frame_type.to_string()),
+            genesys_points,
+        }
+    }
+
+    // Blue-Eyes (1), Decode Talker (2), Pot of Greed (3, forbidden), Ash (4, limited)
+    fn cards() -> (HashMap<i64, DeckStub>, BanList, HashSet<i64>) {
+        let stubs = [
+            stub(1, "Blue-Eyes White Dragon", "normal", 0),
+            stub(2, "Decode Talker", "link", 0),
+            stub(3, "Pot of Greed", "spell", 0),
+            stub(4, "Ash Blossom & Joyous Spring", "effect", 40),
+        ]
+        .into_iter()
+        .map(|s| (s.id, s))
+        .collect();
+        let ban = BanList { forbidden: vec![3], limited: vec![4], semi_limited: vec![] };
+        (stubs, ban, HashSet::from([1, 2, 3, 4]))
+    }
+
+    #[test]
+    fn extra_deck_frames_include_pendulums() {
+        assert!(is_extra_deck(&stub(1, "x", "synchro_pendulum", 0)));
+        assert!(is_extra_deck(&stub(1, "x", "Link", 0)));
+        assert!(!is_extra_deck(&stub(1, "x", "effect_pendulum", 0)));
+        assert!(!is_extra_deck(&stub(1, "x", "spell", 0)));
+    }
+
+    #[test]
+    fn copy_limit_follows_the_ban_list() {
+        let (_, ban, _) = cards();
+        assert_eq!(copy_limit(&ban, 3), 0);
+        assert_eq!(copy_limit(&ban, 4), 1);
+        assert_eq!(copy_limit(&ban, 1), 3);
+    }
+
+    #[test]
+    fn legal_deck_has_no_violations() {
+        let (mut stubs, ban, mut pool) = cards();
+        // Fourteen more normal monsters, three copies each
+        for id in 10..24 {
+            stubs.insert(id, stub(id, "Vanilla", "normal", 0));
+            pool.insert(id);
+        }
+        let main: Vec<i64> = (10..24).flat_map(|id| [id; 3]).take(39).chain([4]).collect();
+        let v = deck_violations(&STANDARD_RULES, &ban, &pool, &stubs, &main, &[2], &[1, 1]);
+        assert_eq!(v, vec![]);
+        let v = deck_violations(&STANDARD_RULES, &ban, &pool, &stubs, &main[..20], &[2], &[]);
+        assert_eq!(v, vec![DeckViolation::MainSize { count: 20, min: 40, max: 60 }]);
+    }
+
+    #[test]
+    fn duel_links_decks_are_smaller() {
+        let (stubs, ban, pool) = cards();
+        let rules = format_rules("Duel Links");
+        let v = deck_violations(&rules, &ban, &pool, &stubs, &[1; 3], &[2; 9], &[]);
+        assert_eq!(v[0], DeckViolation::MainSize { count: 3, min: 20, max: 30 });
+        assert_eq!(v[1], DeckViolation::ExtraSize { count: 9, max: 8 });
+        assert_eq!(format_rules("TCG"), STANDARD_RULES);
+    }
+
+    #[test]
+    fn misplaced_and_unknown_cards_are_reported_once() {
+        let (stubs, ban, pool) = cards();
+        let v = deck_violations(&STANDARD_RULES, &ban, &pool, &stubs, &[2, 2, 99, 99], &[1, 1], &[2, 1]);
+        assert_eq!(&v[1..], &[
+            DeckViolation::ExtraDeckInMain { card_id: 2, name: "Decode Talker".to_string() },
+            DeckViolation::UnknownCard { card_id: 99 },
+            DeckViolation::MainDeckInExtra { card_id: 1, name: "Blue-Eyes White Dragon".to_string() },
+        ]);
+    }
+
+    #[test]
+    fn copies_count_across_sections() {
+        let (stubs, ban, pool) = cards();
+        let v = deck_violations(&STANDARD_RULES, &ban, &pool, &stubs, &[4, 3], &[], &[4]);
+        assert_eq!(&v[1..], &[
+            DeckViolation::TooManyCopies { card_id: 3, name: "Pot of Greed".to_string(), count: 1, limit: 0 },
+            DeckViolation::TooManyCopies { card_id: 4, name: "Ash Blossom & Joyous Spring".to_string(), count: 2, limit: 1 },
+        ]);
+    }
+
+    #[test]
+    fn cards_outside_the_pool_are_not_legal() {
+        let (stubs, ban, _) = cards();
+        let pool = HashSet::from([1, 2, 4]);
+        let v = deck_violations(&STANDARD_RULES, &ban, &pool, &stubs, &[3, 3, 3, 3], &[], &[]);
+        assert_eq!(&v[1..], &[DeckViolation::NotInFormat { card_id: 3, name: "Pot of Greed".to_string() }]);
+    }
+
+    #[test]
+    fn genesys_caps_points() {
+        let (stubs, _, pool) = cards();
+        let rules = format_rules("Genesys");
+        let v = deck_violations(&rules, &BanList::default(), &pool, &stubs, &[4, 4, 4], &[], &[]);
+        assert_eq!(v.last(), Some(&DeckViolation::PointCap { total: 120, cap: 100 }));
+    }
+
+    #[test]
+    fn format_pool_reads_the_formats_column() {
+        let conn = test_db();
+        conn.execute_batch(
+            r#"INSERT INTO cards (id, name, formats) VALUES (1, 'A', '["TCG","OCG GOAT"]');
+               INSERT INTO cards (id, name, formats) VALUES (2, 'B', '["OCG","Duel Links"]');
+               INSERT INTO cards (id, name) VALUES (3, 'C');"#,
+        )
+        .unwrap();
+        assert_eq!(format_pool(&conn, "TCG", &[1, 2, 3]).unwrap(), HashSet::from([1]));
+        assert_eq!(format_pool(&conn, "Genesys", &[1, 2]).unwrap(), HashSet::from([1]));
+        // OCG GOAT does not put a card in GOAT
+        assert!(format_pool(&conn, "GOAT", &[1, 2, 3]).unwrap().is_empty());
+        assert_eq!(format_pool(&conn, "Duel Links", &[2]).unwrap(), HashSet::from([2]));
+    }
+}
@@ -0,0 +1,356 @@
+use rusqlite::{named_params, Connection, OptionalExtension};
+use serde::Serialize;
+
+use crate::currency::{round2, ExchangeRates, BASE_CURRENCY};
+use crate::db::open_db;
+use crate::models::PrintingKey;
+
+// ---------------------------------------------------------------------------
+// Types
+// ---------------------------------------------------------------------------
+
+/// One wishlist row plus the cheapest printing currently matching it.
+/// None in set_code / set_rarity / artwork means "any".
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WishlistEntry {
+    pub id: i64,
+    pub card_id: i64,
+    pub card_name: String,
+    pub set_code: Option<String>,
+    pub set_rarity: Option<String>,
+    pub artwork: Option<i64>,
+    pub quantity: i64,
+    pub max_price: Option<f64>,
+    pub currency: String,
+    /// Cheapest matching set_price, converted to `currency`
+    pub lowest_price: Option<f64>,
+    pub lowest_price_set_code: Option<String>,
+    pub lowest_price_rarity: Option<String>,
+    pub added_at: String,
+}
+
+// Matches wishlist row `w` against card_sets row `cs`.
+const MATCHES_PRINTING: &str = "
+        w.card_id = cs.card_id
+    AND (w.set_code IS NULL OR w.set_code = cs.set_code)
+    AND (w.set_rarity IS NULL OR w.set_rarity = cs.set_rarity)
+    AND (w.artwork IS NULL OR w.artwork = COALESCE(cs.artwork, 0))
+";
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Loads every wishlist entry with its cheapest matching printing, in the
+/// order they were added.
+fn load_entries(conn: &Connection) -> Result<Vec<WishlistEntry>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT w.id, w.card_id, c.name, w.set_code, w.set_rarity, w.artwork,
+                    w.quantity, w.max_price, w.currency, w.added_at
+             FROM wishlist w
+             JOIN cards c ON c.id = w.card_id
+             ORDER BY w.id",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(WishlistEntry {
+                id: row.get("id")?,
+                card_id: row.get("card_id")?,
+                card_name: row.get("name")?,
+                set_code: row.get("set_code")?,
+                set_rarity: row.get("set_rarity")?,
+                artwork: row.get("artwork")?,
+                quantity: row.get("quantity")?,
+                max_price: row.get("max_price")?,
+                currency: row.get("currency")?,
+                lowest_price: None,
+                lowest_price_set_code: None,
+                lowest_price_rarity: None,
+                added_at: row.get("added_at")?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    let mut entries: Vec<WishlistEntry> = rows.collect::<Result<_, _>>().map_err(|e| e.to_string())?;
+
+    // Prices can be in different currencies, so the minimum is taken after
+    // converting each one to the entry's currency.
+    let rates = ExchangeRates::load(conn)?;
+    let mut stmt = conn
+        .prepare(&format!("
+            SELECT cs.set_code, cs.set_rarity,
+                   CAST(cs.set_price AS REAL) AS price,
+                   COALESCE(cs.price_currency, 'USD') AS price_currency
+            FROM wishlist w
+            JOIN card_sets cs ON {MATCHES_PRINTING}
+            WHERE w.id = ?1
+              AND CAST(cs.set_price AS REAL) > 0
+        "))
+        .map_err(|e| e.to_string())?;
+    for entry in &mut entries {
+        let rows = stmt
+            .query_map([entry.id], |row| {
+                Ok((
+                    row.get::<_, Option<String>>("set_code")?,
+                    row.get::<_, Option<String>>("set_rarity")?,
+                    row.get::<_, f64>("price")?,
+                    row.get::<_, String>("price_currency")?,
+                ))
+            })
+            .map_err(|e| e.to_string())?;
+        for r in rows {
+            let (set_code, rarity, price, price_currency) = r.map_err(|e| e.to_string())?;
+            // A price with no rate can't be compared with max_price
+            let Some((price, _)) = rates.convert(price, &price_currency, &entry.currency, &mut Vec::new()) else {
+                continue;
+            };
+            let price = round2(price);
+            if entry.lowest_price.is_none_or(|p| price < p) {
+                entry.lowest_price = Some(price);
+                entry.lowest_price_set_code = set_code;
+                entry.lowest_price_rarity = rarity;
+            }
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Takes `gained` newly owned copies of `key` off the wishlist: entries for
+/// exactly that printing first, then the broader ones ("any rarity",
+/// "any printing"), oldest first. Entries that reach 0 are removed.
+/// What was taken is recorded against the collection_history row
+/// `history_id`, for unfulfil_wishlist.
+/// Called by apply_collection_amount whenever owned copies go up.
+pub fn fulfil_wishlist(conn: &Connection, key: &PrintingKey, gained: i64, history_id: i64) -> Result<(), String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, quantity FROM wishlist
+             WHERE card_id = :card_id
+               AND (set_code IS NULL OR set_code = :set_code)
+               AND (set_rarity IS NULL OR set_rarity = :set_rarity)
+               AND (artwork IS NULL OR artwork = :artwork)
+             ORDER BY set_code IS NULL, set_rarity IS NULL, artwork IS NULL, id",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(
+            named_params! {
+                ":card_id": key.card_id,
+                ":set_code": key.set_code,
+                ":set_rarity": key.set_rarity,
+                ":artwork": key.artwork,
+            },
+            |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?)),
+        )
+        .map_err(|e| e.to_string())?;
+    let matches: Vec<(i64, i64)> = rows.collect::<Result<_, _>>().map_err(|e| e.to_string())?;
+
+    let mut remaining = gained;
+    for (id, quantity) in matches {
+        if remaining <= 0 {
+            break;
+        }
+        let taken = quantity.min(remaining);
+        conn.execute(
+            "INSERT INTO wishlist_fulfilments
+                (history_id, wishlist_id, card_id, set_code, set_rarity, artwork,
+                 quantity, max_price, currency, added_at)
+             SELECT ?1, id, card_id, set_code, set_rarity, artwork, ?2, max_price, currency, added_at
+             FROM wishlist WHERE id = ?3",
+            (history_id, taken, id),
+        )
+        .map_err(|e| e.to_string())?;
+        if quantity <= remaining {
+            conn.execute("DELETE FROM wishlist WHERE id = ?1", [id])
+                .map_err(|e| e.to_string())?;
+        } else {
+            conn.execute("UPDATE wishlist SET quantity = ?1 WHERE id = ?2", (quantity - remaining, id))
+                .map_err(|e| e.to_string())?;
+        }
+        remaining -= taken;
+    }
+    Ok(())
+}
+
+/// Puts back what the change `history_id` took off the wishlist: entries
+/// still there get the copies back, removed ones are recreated.
+/// Called by apply_collection_amount when that change is undone.
+pub fn unfulfil_wishlist(conn: &Connection, history_id: i64) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO wishlist (id, card_id, set_code, set_rarity, artwork, quantity, max_price, currency, added_at)
+         SELECT wishlist_id, card_id, set_code, set_rarity, artwork, quantity, max_price, currency, added_at
+         FROM wishlist_fulfilments WHERE history_id = ?1
+         ON CONFLICT(id) DO UPDATE SET quantity = quantity + excluded.quantity",
+        [history_id],
+    )
+    .map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM wishlist_fulfilments WHERE history_id = ?1", [history_id])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Commands
+// ---------------------------------------------------------------------------
+
+/// Adds a card to the wishlist. Leave set_code / rarity / artwork empty to
+/// accept any printing. `max_price` is in `currency` (default EUR).
+/// Adding an entry that already exists adds to its quantity and replaces its
+/// target price. Returns the entry id.
+#[tauri::command]
+pub fn add_wishlist_entry(
+    card_id: i64,
+    set_code: Option<String>,
+    rarity: Option<String>,
+    artwork: Option<i64>,
+    quantity: i64,
+    max_price: Option<f64>,
+    currency: Option<String>,
+) -> Result<i64, String> {
+    if quantity <= 0 {
+        return Err("Quantity must be at least 1".to_string());
+    }
+    let currency = currency.unwrap_or_else(|| BASE_CURRENCY.to_string());
+    let mut conn = open_db()?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let printing_exists = tx
+        .prepare(
+            "SELECT 1 FROM card_sets
+             WHERE card_id = :card_id
+               AND (:set_code IS NULL OR set_code = :set_code)
+               AND (:set_rarity IS NULL OR set_rarity = :set_rarity)
+               AND (:artwork IS NULL OR COALESCE(artwork, 0) = :artwork)",
+        )
+        .map_err(|e| e.to_string())?
+        .exists(named_params! {
+            ":card_id": card_id,
+            ":set_code": set_code,
+            ":set_rarity": rarity,
+            ":artwork": artwork,
+        })
+        .map_err(|e| e.to_string())?;
+    if !printing_exists {
+        return Err(format!("No printing of card {} matches this wishlist entry", card_id));
+    }
+
+    let existing: Option<i64> = tx
+        .query_row(
+            "SELECT id FROM wishlist
+             WHERE card_id = ?1 AND set_code IS ?2 AND set_rarity IS ?3 AND artwork IS ?4",
+            (card_id, &set_code, &rarity, artwork),
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+
+    let id = match existing {
+        Some(id) => {
+            tx.execute(
+                "UPDATE wishlist SET quantity = quantity + ?1, max_price = ?2, currency = ?3 WHERE id = ?4",
+                (quantity, max_price, &currency, id),
+            ).map_err(|e| e.to_string())?;
+            id
+        }
+        None => {
+            tx.execute(
+                "INSERT INTO wishlist (card_id, set_code, set_rarity, artwork, quantity, max_price, currency)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                (card_id, &set_code, &rarity, artwork, quantity, max_price, &currency),
+            ).map_err(|e| e.to_string())?;
+            tx.last_insert_rowid()
+        }
+    };
+
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(id)
+}
+
+#[tauri::command]
+pub fn remove_wishlist_entry(id: i64) -> Result<(), String> {
+    let mut conn = open_db()?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let removed = tx
+        .execute("DELETE FROM wishlist WHERE id = ?1", [id])
+        .map_err(|e| e.to_string())?;
+    if removed == 0 {
+        return Err(format!("No wishlist entry with id {}", id));
+    }
+    // A removed entry stays removed, even if a fulfilment of it is undone
+    tx.execute("DELETE FROM wishlist_fulfilments WHERE wishlist_id = ?1", [id])
+        .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn list_wishlist() -> Result<Vec<WishlistEntry>, String> {
+    let conn = open_db()?;
+    load_entries(&conn)
+}
+
+/// Returns the wishlist entries with a matching printing whose current
+/// set_price is at or below the entry's max_price. Entries without a target
+/// price are never included.
+#[tauri::command]
+pub fn get_wishlist_deals() -> Result<Vec<WishlistEntry>, String> {
+    let conn = open_db()?;
+    let entries = load_entries(&conn)?;
+    Ok(entries
+        .into_iter()
+        .filter(|e| matches!((e.lowest_price, e.max_price), (Some(p), Some(max)) if p <= max))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::collection::apply_collection_amount;
+    use crate::db::test_db;
+
+    fn quantities(conn: &Connection) -> Vec<(i64, i64)> {
+        conn.prepare("SELECT id, quantity FROM wishlist ORDER BY id")
+            .unwrap()
+            .query_map([], |r| Ok((r.get(0)?, r.get(1)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn undo_puts_fulfilled_entries_back() {
+        let conn = test_db();
+        conn.execute_batch(
+            "INSERT INTO cards (id, name) VALUES (1, 'Dark Magician');
+             INSERT INTO card_sets (card_id, set_code, set_rarity) VALUES (1, 'SDY-006', 'Ultra Rare');
+             INSERT INTO wishlist (id, card_id, set_code, quantity, max_price) VALUES (1, 1, 'SDY-006', 1, 5.0);
+             INSERT INTO wishlist (id, card_id, quantity) VALUES (2, 1, 3);",
+        )
+        .unwrap();
+        let key = PrintingKey {
+            card_id: 1,
+            set_code: "SDY-006".to_string(),
+            set_rarity: "Ultra Rare".to_string(),
+            artwork: 0,
+        };
+
+        apply_collection_amount(&conn, &key, 2, "manual", None).unwrap();
+        assert_eq!(quantities(&conn), vec![(2, 2)]);
+
+        let history_id: i64 = conn
+            .query_row("SELECT MAX(id) FROM collection_history", [], |r| r.get(0))
+            .unwrap();
+        apply_collection_amount(&conn, &key, 0, "undo", Some(history_id)).unwrap();
+        assert_eq!(quantities(&conn), vec![(1, 1), (2, 3)]);
+        let max_price: Option<f64> = conn
+            .query_row("SELECT max_price FROM wishlist WHERE id = 1", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(max_price, Some(5.0));
+        let left: i64 = conn
+            .query_row("SELECT COUNT(*) FROM wishlist_fulfilments", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(left, 0);
+    }
+}
@@ -0,0 +1,116 @@
+use rusqlite::Connection;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Currency the exchange_rates table is based on (frankfurter.app's default).
+pub const BASE_CURRENCY: &str = "EUR";
+
+/// An amount in a display currency plus the date of the exchange rate used
+/// to get there (None when no conversion was needed). Amounts in a currency
+/// without a stored rate are left out and that currency is listed in
+/// `missing_rates`.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MoneyValue {
+    pub amount: f64,
+    pub currency: String,
+    pub rate_date: Option<String>,
+    pub missing_rates: Vec<String>,
+}
+
+/// The latest stored rate per currency, as units of that currency per 1 EUR.
+/// Filled by update_prices.py during sync, so conversion works offline.
+pub struct ExchangeRates {
+    rates: HashMap<String, (f64, String)>,
+}
+
+impl ExchangeRates {
+    pub fn load(conn: &Connection) -> Result<Self, String> {
+        let mut stmt = conn
+            .prepare(
+                "SELECT currency, rate, rate_date
+                 FROM exchange_rates r
+                 WHERE rate_date = (
+                     SELECT MAX(rate_date) FROM exchange_rates WHERE currency = r.currency
+                 )",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get::<_, String>(0)?, (row.get(1)?, row.get(2)?))))
+            .map_err(|e| e.to_string())?;
+        let rates = rows.collect::<Result<_, _>>().map_err(|e| e.to_string())?;
+        Ok(ExchangeRates { rates })
+    }
+
+    fn rate(&self, currency: &str) -> Option<(f64, Option<&str>)> {
+        if currency == BASE_CURRENCY {
+            return Some((1.0, None));
+        }
+        self.rates
+            .get(currency)
+            .map(|(rate, date)| (*rate, Some(date.as_str())))
+    }
+
+    /// Converts `amount` from one currency to another through EUR.
+    /// Returns the converted amount and the newest rate date involved.
+    /// Fails when either currency has no stored rate.
+    pub fn try_convert(&self, amount: f64, from: &str, to: &str) -> Result<(f64, Option<String>), String> {
+        if from == to {
+            return Ok((amount, None));
+        }
+        let missing = |c: &str| format!("No exchange rate stored for {}. Run a sync to fetch rates.", c);
+        let (from_rate, from_date) = self.rate(from).ok_or_else(|| missing(from))?;
+        let (to_rate, to_date) = self.rate(to).ok_or_else(|| missing(to))?;
+        let date = from_date.max(to_date).map(|d| d.to_string());
+        Ok((amount / from_rate * to_rate, date))
+    }
+
+    /// try_convert for display: when a rate is missing (fresh install, failed
+    /// rate fetch) the amount can't be shown in `to`, so it comes back as None
+    /// and the currency without a rate is added to `missing` (once), instead
+    /// of failing the whole view. Responses carry that list as missing_rates.
+    pub fn convert(&self, amount: f64, from: &str, to: &str, missing: &mut Vec<String>) -> Option<(f64, Option<String>)> {
+        match self.try_convert(amount, from, to) {
+            Ok(converted) => Some(converted),
+            Err(_) => {
+                let currency = if self.rate(from).is_none() { from } else { to };
+                if !missing.iter().any(|c| c == currency) {
+                    missing.push(currency.to_string());
+                }
+                None
+            }
+        }
+    }
+}
+
+/// Keeps the newer of two optional rate dates (ISO dates compare as strings).
+pub fn latest_date(a: Option<String>, b: Option<String>) -> Option<String> {
+    a.max(b)
+}
+
+/// Rounds to cents for display.
+pub fn round2(v: f64) -> f64 {
+    (v * 100.0).round() / 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_db;
+
+    #[test]
+    fn convert_leaves_out_amounts_without_a_rate() {
+        let conn = test_db();
+        conn.execute("INSERT INTO exchange_rates VALUES ('USD', 1.25, '2026-10-01')", []).unwrap();
+        let rates = ExchangeRates::load(&conn).unwrap();
+        let mut missing = Vec::new();
+
+        assert_eq!(rates.convert(10.0, "USD", "EUR", &mut missing), Some((8.0, Some("2026-10-01".to_string()))));
+        assert_eq!(rates.convert(10.0, "EUR", "EUR", &mut missing), Some((10.0, None)));
+        assert_eq!(rates.convert(10.0, "GBP", "EUR", &mut missing), None);
+        assert_eq!(rates.convert(5.0, "GBP", "USD", &mut missing), None);
+        // The display currency can be the one missing a rate too
+        assert_eq!(rates.convert(5.0, "USD", "JPY", &mut missing), None);
+        assert_eq!(missing, vec!["GBP".to_string(), "JPY".to_string()]);
+    }
+}
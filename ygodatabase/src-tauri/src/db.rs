@@ -43,6 +43,339 @@ pub fn create_indexes(conn: &Connection) -> Result<()> {
     ")
 }
 
+/// Creates the tables the app itself owns (the card data tables are created
+/// by the sync scripts). IF NOT EXISTS makes this a no-op on later startups.
+pub fn create_app_tables(conn: &Connection) -> Result<()> {
+    conn.execute_batch("
+        -- Append-only ledger of every collection_amount change.
+        -- undo_of points at the entry an undo reverted; undo rows are never
+        -- themselves undone.
+        CREATE TABLE IF NOT EXISTS collection_history (
+            id         INTEGER PRIMARY KEY AUTOINCREMENT,
+            card_id    INTEGER NOT NULL,
+            set_code   TEXT NOT NULL,
+            set_rarity TEXT NOT NULL,
+            artwork    INTEGER NOT NULL DEFAULT 0,
+            old_amount INTEGER NOT NULL,
+            new_amount INTEGER NOT NULL,
+            source     TEXT NOT NULL DEFAULT 'manual',
+            undo_of    INTEGER REFERENCES collection_history(id),
+            changed_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+        CREATE INDEX IF NOT EXISTS idx_collection_history_card
+            ON collection_history(card_id, set_code, set_rarity, artwork);
+        CREATE INDEX IF NOT EXISTS idx_collection_history_undo_of
+            ON collection_history(undo_of);
+
+        -- Filled by update_prices.py (which creates the same tables when run
+        -- standalone). Change-only: a price holds until the next entry.
+        -- Prices are in their source currency (see the currency column).
+        CREATE TABLE IF NOT EXISTS price_history (
+            card_id     INTEGER NOT NULL,
+            set_code    TEXT NOT NULL,
+            set_rarity  TEXT NOT NULL,
+            artwork     INTEGER NOT NULL DEFAULT 0,
+            price       REAL NOT NULL,
+            recorded_on TEXT NOT NULL,
+            PRIMARY KEY (card_id, set_code, set_rarity, artwork, recorded_on)
+        );
+        -- One row per day in EUR, written after each price sync.
+        CREATE TABLE IF NOT EXISTS collection_value_history (
+            snapshot_on  TEXT PRIMARY KEY,
+            total_value  REAL NOT NULL,
+            total_copies INTEGER NOT NULL
+        );
+        -- Units of `currency` per 1 EUR, refreshed by update_prices.py.
+        CREATE TABLE IF NOT EXISTS exchange_rates (
+            currency  TEXT NOT NULL,
+            rate      REAL NOT NULL,
+            rate_date TEXT NOT NULL,
+            PRIMARY KEY (currency, rate_date)
+        );
+
+        -- Cards we are hunting for. NULL set_code / set_rarity / artwork
+        -- means any value, so a row with only card_id matches any printing.
+        -- max_price is in `currency`; NULL means no price limit.
+        CREATE TABLE IF NOT EXISTS wishlist (
+            id         INTEGER PRIMARY KEY AUTOINCREMENT,
+            card_id    INTEGER NOT NULL,
+            set_code   TEXT,
+            set_rarity TEXT,
+            artwork    INTEGER,
+            quantity   INTEGER NOT NULL CHECK (quantity > 0),
+            max_price  REAL,
+            currency   TEXT NOT NULL DEFAULT 'EUR',
+            added_at   TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+        CREATE INDEX IF NOT EXISTS idx_wishlist_card
+            ON wishlist(card_id);
+        -- What a collection change took off the wishlist, so undoing the
+        -- change can put it back. Holds the whole entry: one that reached
+        -- 0 was removed and is recreated with the same id.
+        CREATE TABLE IF NOT EXISTS wishlist_fulfilments (
+            history_id  INTEGER NOT NULL REFERENCES collection_history(id),
+            wishlist_id INTEGER NOT NULL,
+            card_id     INTEGER NOT NULL,
+            set_code    TEXT,
+            set_rarity  TEXT,
+            artwork     INTEGER,
+            quantity    INTEGER NOT NULL CHECK (quantity > 0),
+            max_price   REAL,
+            currency    TEXT NOT NULL,
+            added_at    TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_wishlist_fulfilments_history
+            ON wishlist_fulfilments(history_id);
+
+        -- Owned copies offered for trade; never more than collection_amount.
+        CREATE TABLE IF NOT EXISTS trade_list (
+            card_id    INTEGER NOT NULL,
+            set_code   TEXT NOT NULL,
+            set_rarity TEXT NOT NULL,
+            artwork    INTEGER NOT NULL DEFAULT 0,
+            quantity   INTEGER NOT NULL CHECK (quantity > 0),
+            PRIMARY KEY (card_id, set_code, set_rarity, artwork)
+        );
+
+        -- Purchase lots. `remaining` is what is still owned of the lot:
+        -- sales and removals consume the oldest lots first.
+        CREATE TABLE IF NOT EXISTS acquisitions (
+            id          INTEGER PRIMARY KEY AUTOINCREMENT,
+            card_id     INTEGER NOT NULL,
+            set_code    TEXT NOT NULL,
+            set_rarity  TEXT NOT NULL,
+            artwork     INTEGER NOT NULL DEFAULT 0,
+            quantity    INTEGER NOT NULL CHECK (quantity > 0),
+            remaining   INTEGER NOT NULL,
+            unit_cost   REAL NOT NULL,
+            currency    TEXT NOT NULL DEFAULT 'EUR',
+            acquired_on TEXT NOT NULL,
+            source      TEXT
+        );
+        CREATE INDEX IF NOT EXISTS idx_acquisitions_card
+            ON acquisitions(card_id, set_code, set_rarity, artwork);
+        -- cost_basis is the cost of the `costed_quantity` copies that came
+        -- from known lots, in the sale's currency.
+        CREATE TABLE IF NOT EXISTS sales (
+            id              INTEGER PRIMARY KEY AUTOINCREMENT,
+            card_id         INTEGER NOT NULL,
+            set_code        TEXT NOT NULL,
+            set_rarity      TEXT NOT NULL,
+            artwork         INTEGER NOT NULL DEFAULT 0,
+            quantity        INTEGER NOT NULL CHECK (quantity > 0),
+            unit_price      REAL NOT NULL,
+            currency        TEXT NOT NULL DEFAULT 'EUR',
+            sold_on         TEXT NOT NULL,
+            costed_quantity INTEGER NOT NULL,
+            cost_basis      REAL NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_sales_card
+            ON sales(card_id, set_code, set_rarity, artwork);
+        -- The lots a sale took its copies from, so an undone sale can
+        -- give them back.
+        CREATE TABLE IF NOT EXISTS sale_lots (
+            sale_id  INTEGER NOT NULL REFERENCES sales(id),
+            lot_id   INTEGER NOT NULL,
+            quantity INTEGER NOT NULL CHECK (quantity > 0)
+        );
+        CREATE INDEX IF NOT EXISTS idx_sale_lots_sale
+            ON sale_lots(sale_id);
+
+        -- Named card searches: a CardFilters JSON object plus an optional
+        -- search box query ANDed with it. Smart lists are re-counted after
+        -- every sync; match_count is the stub count at counted_at.
+        CREATE TABLE IF NOT EXISTS saved_searches (
+            id          INTEGER PRIMARY KEY AUTOINCREMENT,
+            name        TEXT NOT NULL UNIQUE COLLATE NOCASE,
+            filters     TEXT NOT NULL DEFAULT '{}',
+            query       TEXT,
+            sort        TEXT,
+            smart_list  INTEGER NOT NULL DEFAULT 0,
+            match_count INTEGER,
+            counted_at  TEXT,
+            created_at  TEXT NOT NULL DEFAULT (datetime('now')),
+            updated_at  TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+
+        -- Unopened products (booster boxes, structure decks, tins, ...).
+        -- set_name links to card_sets.set_name when the product belongs to
+        -- one set. Prices are per unit in `currency`; market_price counts
+        -- towards the collection value.
+        CREATE TABLE IF NOT EXISTS sealed_products (
+            id             INTEGER PRIMARY KEY AUTOINCREMENT,
+            name           TEXT NOT NULL,
+            product_type   TEXT,
+            set_name       TEXT,
+            quantity       INTEGER NOT NULL CHECK (quantity >= 0),
+            purchase_price REAL,
+            market_price   REAL,
+            currency       TEXT NOT NULL DEFAULT 'EUR',
+            added_at       TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+        -- One opened unit of a sealed product and the printings pulled from it
+        CREATE TABLE IF NOT EXISTS sealed_openings (
+            id         INTEGER PRIMARY KEY AUTOINCREMENT,
+            product_id INTEGER NOT NULL REFERENCES sealed_products(id),
+            opened_at  TEXT NOT NULL DEFAULT (datetime('now')),
+            note       TEXT
+        );
+        CREATE TABLE IF NOT EXISTS sealed_pulls (
+            opening_id INTEGER NOT NULL REFERENCES sealed_openings(id),
+            card_id    INTEGER NOT NULL,
+            set_code   TEXT NOT NULL,
+            set_rarity TEXT NOT NULL,
+            artwork    INTEGER NOT NULL DEFAULT 0,
+            quantity   INTEGER NOT NULL CHECK (quantity > 0),
+            PRIMARY KEY (opening_id, card_id, set_code, set_rarity, artwork)
+        );
+
+        -- Graded slabs, one row per slab. Kept apart from collection_amount
+        -- (raw copies), so deck tools and playset counts never see them.
+        -- price is the slab's own value in `currency`.
+        CREATE TABLE IF NOT EXISTS graded_cards (
+            id          INTEGER PRIMARY KEY AUTOINCREMENT,
+            card_id     INTEGER NOT NULL,
+            set_code    TEXT NOT NULL,
+            set_rarity  TEXT NOT NULL,
+            artwork     INTEGER NOT NULL DEFAULT 0,
+            company     TEXT NOT NULL,
+            grade       TEXT NOT NULL,
+            cert_number TEXT,
+            price       REAL,
+            currency    TEXT NOT NULL DEFAULT 'EUR',
+            added_at    TEXT NOT NULL DEFAULT (datetime('now')),
+            UNIQUE (company, cert_number)
+        );
+        CREATE INDEX IF NOT EXISTS idx_graded_cards_card
+            ON graded_cards(card_id, set_code, set_rarity, artwork);
+
+        -- Copies lent to someone. As in the wishlist, NULL set_code /
+        -- set_rarity / artwork means any printing (a deck card lent out).
+        -- Lent copies stay in collection_amount; returned_on NULL = still out.
+        CREATE TABLE IF NOT EXISTS loans (
+            id          INTEGER PRIMARY KEY AUTOINCREMENT,
+            card_id     INTEGER NOT NULL,
+            set_code    TEXT,
+            set_rarity  TEXT,
+            artwork     INTEGER,
+            quantity    INTEGER NOT NULL CHECK (quantity > 0),
+            borrower    TEXT NOT NULL,
+            lent_on     TEXT NOT NULL,
+            due_on      TEXT,
+            returned_on TEXT,
+            note        TEXT
+        );
+        CREATE INDEX IF NOT EXISTS idx_loans_card
+            ON loans(card_id);
+
+        -- Our own labels on cards ('staple', 'pet card', ...), unique
+        -- ignoring case. color is a CSS color for the chip, if any.
+        CREATE TABLE IF NOT EXISTS tags (
+            id         INTEGER PRIMARY KEY AUTOINCREMENT,
+            name       TEXT NOT NULL UNIQUE COLLATE NOCASE,
+            color      TEXT,
+            created_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+        CREATE TABLE IF NOT EXISTS card_tags (
+            card_id INTEGER NOT NULL,
+            tag_id  INTEGER NOT NULL REFERENCES tags(id),
+            PRIMARY KEY (card_id, tag_id)
+        );
+        CREATE INDEX IF NOT EXISTS idx_card_tags_tag
+            ON card_tags(tag_id);
+        -- Free-text notes, one per card (NULL set_code / set_rarity /
+        -- artwork) and one per card_sets printing.
+        CREATE TABLE IF NOT EXISTS card_notes (
+            id         INTEGER PRIMARY KEY AUTOINCREMENT,
+            card_id    INTEGER NOT NULL,
+            set_code   TEXT,
+            set_rarity TEXT,
+            artwork    INTEGER,
+            note       TEXT NOT NULL,
+            updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+        CREATE UNIQUE INDEX IF NOT EXISTS idx_card_notes_target
+            ON card_notes(card_id, COALESCE(set_code, ''), COALESCE(set_rarity, ''), COALESCE(artwork, -1));
+
+        -- Where copies physically are. Locations nest box > binder > page >
+        -- slot (a kind only holds kinds after it); `position` orders siblings.
+        CREATE TABLE IF NOT EXISTS storage_locations (
+            id         INTEGER PRIMARY KEY AUTOINCREMENT,
+            parent_id  INTEGER REFERENCES storage_locations(id),
+            name       TEXT NOT NULL,
+            kind       TEXT NOT NULL CHECK (kind IN ('box', 'binder', 'page', 'slot')),
+            position   INTEGER,
+            created_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+        CREATE UNIQUE INDEX IF NOT EXISTS idx_storage_locations_name
+            ON storage_locations(COALESCE(parent_id, 0), name COLLATE NOCASE);
+        -- Owned copies kept at a location. Per printing never more than
+        -- collection_amount across all locations.
+        CREATE TABLE IF NOT EXISTS stored_copies (
+            location_id INTEGER NOT NULL REFERENCES storage_locations(id),
+            card_id     INTEGER NOT NULL,
+            set_code    TEXT NOT NULL,
+            set_rarity  TEXT NOT NULL,
+            artwork     INTEGER NOT NULL DEFAULT 0,
+            quantity    INTEGER NOT NULL CHECK (quantity > 0),
+            PRIMARY KEY (location_id, card_id, set_code, set_rarity, artwork)
+        );
+        CREATE INDEX IF NOT EXISTS idx_stored_copies_card
+            ON stored_copies(card_id, set_code, set_rarity, artwork);
+
+        -- Saved decks. Names are unique ignoring case; format is the one
+        -- the deck was last saved for, folder a free path ('Locals/2024').
+        CREATE TABLE IF NOT EXISTS decks (
+            id            INTEGER PRIMARY KEY AUTOINCREMENT,
+            name          TEXT NOT NULL UNIQUE COLLATE NOCASE,
+            format        TEXT,
+            notes         TEXT,
+            cover_card_id INTEGER,
+            folder        TEXT,
+            created_at    TEXT NOT NULL DEFAULT (datetime('now')),
+            updated_at    TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+        -- One row per copy; position keeps the order within a section.
+        CREATE TABLE IF NOT EXISTS deck_cards (
+            deck_id  INTEGER NOT NULL REFERENCES decks(id),
+            section  TEXT NOT NULL CHECK (section IN ('main', 'extra', 'side')),
+            position INTEGER NOT NULL,
+            card_id  INTEGER NOT NULL,
+            PRIMARY KEY (deck_id, section, position)
+        );
+        -- Decks share the tags of the cards.
+        CREATE TABLE IF NOT EXISTS deck_tags (
+            deck_id INTEGER NOT NULL REFERENCES decks(id),
+            tag_id  INTEGER NOT NULL REFERENCES tags(id),
+            PRIMARY KEY (deck_id, tag_id)
+        );
+    ")?;
+    // Prices keep the currency they were fetched in. NULL is read as USD:
+    // both price sources (YGOPRODeck set prices, tcgcsv) are in USD.
+    // price_history rows from before the column were converted to EUR.
+    add_column_if_missing(conn, "card_sets", "price_currency", "TEXT")?;
+    add_column_if_missing(conn, "price_history", "currency", "TEXT NOT NULL DEFAULT 'EUR'")?;
+    // The purchase lot a slab's raw copy was taken from when it was graded:
+    // the copy's cost stays with the slab and goes back to the lot when the
+    // slab is cracked. No foreign key, the lot may be gone by then.
+    add_column_if_missing(conn, "graded_cards", "lot_id", "INTEGER")?;
+    // The collection_history row a purchase lot or sale was recorded with;
+    // undoing that row removes it. NULL for rows from before the column.
+    add_column_if_missing(conn, "acquisitions", "history_id", "INTEGER")?;
+    add_column_if_missing(conn, "sales", "history_id", "INTEGER")
+}
+
+/// ALTER TABLE ... ADD COLUMN, skipped when the column already exists.
+fn add_column_if_missing(conn: &Connection, table: &str, column: &str, definition: &str) -> Result<()> {
+    let exists = conn
+        .prepare(&format!("SELECT 1 FROM pragma_table_info('{}') WHERE name = ?1", table))?
+        .exists([column])?;
+    if !exists {
+        conn.execute_batch(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition))?;
+    }
+    Ok(())
+}
+
 /// Normalizes a local file path to a Tauri asset:// URL.
 pub fn normalize_img_path(path: Option<String>) -> Option<String> {
     path.map(|p| {
@@ -70,4 +403,34 @@ pub fn normalize_thumb_path(path: Option<&String>) -> Option<String> {
         return None;
     };
     Some(format!("asset://img_thumb/{}.webp", stem))
-}
\ No newline at end of file
+}
+
+/// An in-memory database with the card data tables as the sync scripts
+/// create them, plus the app tables. Tests insert the cards they need.
+#[cfg(test)]
+pub fn test_db() -> Connection {
+    let conn = Connection::open_in_memory().expect("in-memory database");
+    conn.execute_batch("
+        CREATE TABLE cards (
+            id INTEGER PRIMARY KEY,
+            name TEXT, type TEXT, typeline TEXT, frameType TEXT, desc TEXT,
+            atk INTEGER, def INTEGER, level INTEGER, scale INTEGER,
+            linkval INTEGER, linkmarkers TEXT, race TEXT, attribute TEXT,
+            archetype TEXT, banlist_info TEXT, formats TEXT,
+            ocg_date TEXT, tcg_date TEXT, genesys_points INTEGER,
+            md_rarity TEXT, has_effect INTEGER, treated_as TEXT,
+            has_alt_art INTEGER DEFAULT 0
+        );
+        CREATE TABLE card_images (
+            card_id INTEGER, image_id INTEGER, local_path TEXT,
+            PRIMARY KEY (card_id, image_id)
+        );
+        CREATE TABLE card_sets (
+            card_id INTEGER, set_code TEXT, set_name TEXT, set_rarity TEXT,
+            set_price TEXT, collection_amount INTEGER DEFAULT 0, artwork INTEGER DEFAULT 0,
+            UNIQUE(card_id, set_code, set_rarity, artwork)
+        );
+    ").expect("card data tables");
+    create_app_tables(&conn).expect("app tables");
+    conn
+}
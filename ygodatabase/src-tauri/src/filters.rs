@@ -0,0 +1,270 @@
+use rusqlite::types::{ToSql, Value};
+use serde::{Deserialize, Serialize};
+
+use crate::models::LinkMarker;
+
+/// The card search filters shared by `load_card_stubs` and every command that
+/// should honour "the current filtered view" (export, ...).
+/// Field names mirror the frontend's CardFilters / invoke arguments.
+/// None means "no filter applied".
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CardFilters {
+    pub name: Option<String>,
+    pub set: Option<String>,
+    pub category: Option<String>,   // "monster" | "spell" | "trap"
+    pub frame_type: Option<String>, // exact DB frameType (subcategory); overrides category
+    pub attribute: Option<String>,
+    pub race: Option<String>,
+    pub level: Option<i64>,
+    pub scale: Option<i64>,
+    pub atk: Option<i64>,
+    pub def: Option<i64>,
+    pub ban_status: Option<String>,
+    pub archetype: Option<String>,
+    pub genesys_points_min: Option<i64>,
+    pub genesys_points_max: Option<i64>,
+    pub format: Option<String>, // active format for card pool + ban key filtering
+
+    // Inclusive ranges. ATK / DEF ranges never match "?" values.
+    pub atk_min: Option<i64>,
+    pub atk_max: Option<i64>,
+    pub def_min: Option<i64>,
+    pub def_max: Option<i64>,
+    pub level_min: Option<i64>, // level and rank share the column
+    pub level_max: Option<i64>,
+    pub scale_min: Option<i64>,
+    pub scale_max: Option<i64>,
+    pub linkval: Option<i64>,
+    pub linkval_min: Option<i64>,
+    pub linkval_max: Option<i64>,
+    // "?" ATK / DEF (stored as -1): true = only "?", false = no "?"
+    pub atk_unknown: Option<bool>,
+    pub def_unknown: Option<bool>,
+
+    // Any-of lists; an empty list is no filter. frame_types overrides category.
+    pub attributes: Option<Vec<String>>,
+    pub races: Option<Vec<String>>,
+    pub frame_types: Option<Vec<String>>,
+    pub archetypes: Option<Vec<String>>,
+
+    pub link_markers: Option<Vec<LinkMarker>>, // arrows that must all be present
+    pub has_effect: Option<bool>, // effect / non-effect monsters; spells and traps never match
+    pub treated_as: Option<String>, // cards treated as this name (partial match)
+    pub owned: Option<bool>, // true = at least one copy owned, false = none owned
+    pub tags: Option<Vec<String>>, // user tags, any of them (case-insensitive)
+}
+
+/// A WHERE condition plus the named parameters it references.
+/// The condition expects the cards table aliased as `c` and card_sets as `cs`.
+pub struct SqlFilter {
+    pub clause: String,
+    pub params: Vec<(String, Value)>,
+}
+
+impl SqlFilter {
+    /// Borrowed view of the parameters in the shape rusqlite's query functions take.
+    pub fn params_ref(&self) -> Vec<(&str, &dyn ToSql)> {
+        self.params.iter().map(|(k, v)| (k.as_str(), v as &dyn ToSql)).collect()
+    }
+
+    /// Both conditions must hold. Parameter names must not collide.
+    pub fn and(mut self, other: SqlFilter) -> SqlFilter {
+        self.clause = format!("({}) AND ({})", self.clause, other.clause);
+        self.params.extend(other.params);
+        self
+    }
+}
+
+pub const MONSTER_FRAMES: &[&str] = &[
+    "normal", "effect", "ritual", "fusion", "synchro", "xyz", "link",
+    "normal_pendulum", "effect_pendulum", "ritual_pendulum",
+    "fusion_pendulum", "synchro_pendulum", "xyz_pendulum",
+];
+
+/// Maps a format display name to the banlist_info JSON key that governs it.
+/// Unknown formats (and Genesys, which has no ban list) fall back to TCG.
+pub fn ban_key_for_format(format: Option<&str>) -> &'static str {
+    match format.unwrap_or("") {
+        "TCG"            => "ban_tcg",
+        "OCG"            => "ban_ocg",
+        "Master Duel"    => "ban_ocg",
+        "GOAT"           => "ban_goat",
+        "OCG GOAT"       => "ban_goat",
+        "Edison"         => "ban_tcg",
+        "Common Charity" => "ban_tcg",
+        "Duel Links"     => "ban_tcg",
+        _                => "ban_tcg", // default / genesys (ban_status unused for genesys)
+    }
+}
+
+fn text(v: &Option<String>) -> Value {
+    v.clone().map(Value::Text).unwrap_or(Value::Null)
+}
+
+fn int(v: Option<i64>) -> Value {
+    v.map(Value::Integer).unwrap_or(Value::Null)
+}
+
+fn flag(v: Option<bool>) -> Value {
+    v.map(|b| Value::Integer(b as i64)).unwrap_or(Value::Null)
+}
+
+/// A list filter as a JSON array, for `IN (SELECT value FROM json_each(...))`.
+fn list(v: &Option<Vec<String>>) -> Value {
+    match v {
+        Some(items) if !items.is_empty() => {
+            Value::Text(serde_json::to_string(items).unwrap_or_else(|_| "[]".to_string()))
+        }
+        _ => Value::Null,
+    }
+}
+
+impl CardFilters {
+    pub fn to_sql(&self) -> SqlFilter {
+        let ban_key = ban_key_for_format(self.format.as_deref());
+
+        // Card pool filter: when a non-genesys format is active, restrict to cards
+        // that exist in that format's card pool (formats column is a JSON array).
+        let pool_clause = match self.format.as_deref() {
+            Some(fmt) if fmt != "Genesys" => {
+                format!("AND c.formats IS NOT NULL AND c.formats LIKE '%{}%'",
+                    fmt.replace('\'', "''"))
+            }
+            _ => String::new(), // Genesys uses the full card pool
+        };
+
+        let monster_frames = MONSTER_FRAMES.iter()
+            .map(|f| format!("'{}'", f))
+            .collect::<Vec<_>>().join(", ");
+
+        let frame_clause = if let Some(ref ft) = self.frame_type {
+            format!("AND c.frameType = '{}'", ft.replace('\'', "''"))
+        } else if self.frame_types.as_ref().is_some_and(|v| !v.is_empty()) {
+            String::new() // matched by :frame_types below
+        } else if let Some(ref cat) = self.category {
+            match cat.as_str() {
+                "monster" => format!("AND c.frameType IN ({})", monster_frames),
+                "spell" => "AND c.frameType = 'spell'".to_string(),
+                "trap"  => "AND c.frameType = 'trap'".to_string(),
+                _       => String::new(),
+            }
+        } else {
+            String::new()
+        };
+
+        // Ban status clause uses the correct key for the active format
+        let ban_key_path = format!("$.{}", ban_key);
+
+        let clause = format!("
+              (:name IS NULL OR c.name LIKE :name)
+          AND (:set IS NULL OR cs.set_name = :set)
+          {frame_clause}
+          {pool_clause}
+          AND (:attribute  IS NULL OR c.attribute = :attribute)
+          AND (:race IS NULL OR c.race = :race)
+          AND (:level IS NULL OR c.level = :level)
+          AND (:scale IS NULL OR c.scale = :scale)
+          AND (:atk IS NULL OR c.atk = :atk)
+          AND (:def IS NULL OR c.def = :def)
+          AND (:ban_status IS NULL OR (
+                LOWER(json_extract(c.banlist_info, '{ban_key_path}')) = LOWER(:ban_status)
+              ))
+          AND (:archetype IS NULL OR (
+                c.archetype IS NOT NULL AND
+                EXISTS (
+                    SELECT 1 FROM json_each(c.archetype)
+                    WHERE LOWER(value) = LOWER(:archetype)
+                )
+              ))
+          AND (:genesys_points_min IS NULL OR COALESCE(c.genesys_points, 0) >= :genesys_points_min)
+          AND (:genesys_points_max IS NULL OR COALESCE(c.genesys_points, 0) <= :genesys_points_max)
+          AND (:atk_min IS NULL OR c.atk >= :atk_min)
+          AND (:atk_max IS NULL OR (c.atk >= 0 AND c.atk <= :atk_max))
+          AND (:def_min IS NULL OR c.def >= :def_min)
+          AND (:def_max IS NULL OR (c.def >= 0 AND c.def <= :def_max))
+          AND (:atk_unknown IS NULL OR COALESCE(c.atk = -1, 0) = :atk_unknown)
+          AND (:def_unknown IS NULL OR COALESCE(c.def = -1, 0) = :def_unknown)
+          AND (:level_min IS NULL OR c.level >= :level_min)
+          AND (:level_max IS NULL OR c.level <= :level_max)
+          AND (:scale_min IS NULL OR c.scale >= :scale_min)
+          AND (:scale_max IS NULL OR c.scale <= :scale_max)
+          AND (:linkval IS NULL OR c.linkval = :linkval)
+          AND (:linkval_min IS NULL OR c.linkval >= :linkval_min)
+          AND (:linkval_max IS NULL OR c.linkval <= :linkval_max)
+          AND (:frame_types IS NULL OR c.frameType IN (SELECT value FROM json_each(:frame_types)))
+          AND (:attributes IS NULL OR c.attribute IN (SELECT value FROM json_each(:attributes)))
+          AND (:races IS NULL OR c.race IN (SELECT value FROM json_each(:races)))
+          AND (:archetypes IS NULL OR (
+                c.archetype IS NOT NULL AND
+                EXISTS (
+                    SELECT 1 FROM json_each(c.archetype) a
+                    JOIN json_each(:archetypes) w ON LOWER(w.value) = LOWER(a.value)
+                )
+              ))
+          AND (:link_markers IS NULL OR (
+                json_type(c.linkmarkers) = 'array' AND
+                NOT EXISTS (
+                    SELECT 1 FROM json_each(:link_markers) w
+                    WHERE w.value NOT IN (SELECT value FROM json_each(c.linkmarkers))
+                )
+              ))
+          AND (:has_effect IS NULL OR (
+                c.frameType IN ({monster_frames}) AND
+                COALESCE(c.has_effect, c.frameType NOT IN ('normal', 'normal_pendulum')) = :has_effect
+              ))
+          AND (:treated_as IS NULL OR c.treated_as LIKE :treated_as)
+          AND (:owned IS NULL OR EXISTS (
+                SELECT 1 FROM card_sets o WHERE o.card_id = c.id AND o.collection_amount > 0
+              ) = :owned)
+          AND (:tags IS NULL OR EXISTS (
+                SELECT 1 FROM card_tags ct
+                JOIN tags t ON t.id = ct.tag_id
+                JOIN json_each(:tags) w ON LOWER(w.value) = LOWER(t.name)
+                WHERE ct.card_id = c.id
+              ))
+        ");
+
+        let params = vec![
+            (":name", text(&self.name.as_ref().map(|v| format!("%{}%", v)))),
+            (":set", text(&self.set)),
+            (":attribute", text(&self.attribute)),
+            (":race", text(&self.race)),
+            (":level", int(self.level)),
+            (":scale", int(self.scale)),
+            (":atk", int(self.atk)),
+            (":def", int(self.def)),
+            (":ban_status", text(&self.ban_status)),
+            (":archetype", text(&self.archetype)),
+            (":genesys_points_min", int(self.genesys_points_min)),
+            (":genesys_points_max", int(self.genesys_points_max)),
+            (":atk_min", int(self.atk_min)),
+            (":atk_max", int(self.atk_max)),
+            (":def_min", int(self.def_min)),
+            (":def_max", int(self.def_max)),
+            (":atk_unknown", flag(self.atk_unknown)),
+            (":def_unknown", flag(self.def_unknown)),
+            (":level_min", int(self.level_min)),
+            (":level_max", int(self.level_max)),
+            (":scale_min", int(self.scale_min)),
+            (":scale_max", int(self.scale_max)),
+            (":linkval", int(self.linkval)),
+            (":linkval_min", int(self.linkval_min)),
+            (":linkval_max", int(self.linkval_max)),
+            (":attributes", list(&self.attributes)),
+            (":races", list(&self.races)),
+            (":frame_types", list(&self.frame_types)),
+            (":archetypes", list(&self.archetypes)),
+            (":link_markers", list(&self.link_markers.as_ref().map(|m| {
+                m.iter().map(|a| a.as_str().to_string()).collect()
+            }))),
+            (":has_effect", flag(self.has_effect)),
+            (":treated_as", text(&self.treated_as.as_ref().map(|v| format!("%{}%", v)))),
+            (":owned", flag(self.owned)),
+            (":tags", list(&self.tags)),
+        ];
+        let params = params.into_iter().map(|(k, v)| (k.to_string(), v)).collect();
+
+        SqlFilter { clause, params }
+    }
+}
@@ -1,9 +1,13 @@
+mod currency;
 mod db;
+mod filters;
 mod models;
+mod query;
 mod commands;
 
-use db::{create_indexes, get_db_path};
+use db::{create_app_tables, create_indexes, get_db_path};
 use rusqlite::Connection;
+use tauri::Manager;
 
 use commands::altart::{
     ensure_artwork_column,
@@ -17,20 +21,120 @@ use commands::collection::{
     get_all_archetypes,
     load_card_detail,
     load_card_stubs,
+    search_card_stubs,
+    search_card_query,
+    check_card_query,
     update_collection_amount,
 };
+use commands::completion::{
+    get_set_completion,
+    get_missing_set_cards,
+};
 use commands::deck::{
     get_ban_list,
     get_collection_amounts,
     get_collection_value,
+    get_deck_value,
     get_genesys_points,
     sync_banlist_from_db,
     list_decks,
     save_deck,
     delete_deck,
     load_deck,
+    rename_deck,
+    duplicate_deck,
+    update_deck_info,
+    import_deck_ydk,
+    export_deck_ydk,
+    migrate_ydk_decks,
+    get_ydk_migration,
+};
+use commands::export::export_collection;
+use commands::graded::{
+    add_graded_card,
+    update_graded_card,
+    remove_graded_card,
+    list_graded_cards,
+};
+use commands::history::{
+    get_collection_history,
+    undo_collection_changes,
+};
+use commands::import::import_collection_csv;
+use commands::loans::{
+    lend_cards,
+    return_loan,
+    delete_loan,
+    list_loans,
+    get_lent_amounts,
+};
+use commands::playset::{
+    get_excess_copies,
+    get_playset_shortfall,
+};
+use commands::prices::{
+    get_price_history,
+    get_collection_value_history,
+    get_price_movers,
+};
+use commands::profit::{
+    add_purchase,
+    record_sale,
+    get_sales,
+    get_profit_loss,
+};
+use commands::quickadd::{
+    quick_add,
+    quick_add_batch,
+};
+use commands::searches::{
+    save_search,
+    update_saved_search,
+    delete_saved_search,
+    list_saved_searches,
+    run_saved_search,
+};
+use commands::sealed::{
+    add_sealed_product,
+    update_sealed_product,
+    remove_sealed_product,
+    list_sealed_products,
+    open_sealed_product,
+    list_sealed_openings,
+};
+use commands::stats::get_collection_stats;
+use commands::storage::{
+    create_location,
+    update_location,
+    delete_location,
+    list_locations,
+    store_copies,
+    get_location_contents,
+    get_binder_layout,
 };
 use commands::sync::run_sync;
+use commands::tags::{
+    create_tag,
+    update_tag,
+    delete_tag,
+    list_tags,
+    add_card_tag,
+    remove_card_tag,
+    set_card_note,
+};
+use commands::trade::{
+    set_trade_amount,
+    get_trade_list,
+    export_trade_list,
+    match_trade_files,
+};
+use commands::validation::validate_deck;
+use commands::wishlist::{
+    add_wishlist_entry,
+    remove_wishlist_entry,
+    list_wishlist,
+    get_wishlist_deals,
+};
 
 #[tauri::command]
 fn exit_app(app: tauri::AppHandle) {
@@ -39,8 +143,8 @@ fn exit_app(app: tauri::AppHandle) {
 
 fn main() {
     tauri::Builder::default()
-        .setup(|_app| {
-            let conn = Connection::open(get_db_path())
+        .setup(|app| {
+            let mut conn = Connection::open(get_db_path())
                 .expect("Failed to open DB during setup");
             create_indexes(&conn)
                 .expect("Failed to create database indexes");
@@ -78,16 +182,104 @@ fn main() {
                     COMMIT;
                 ").expect("Failed to migrate card_sets schema");
             }
+            // After the card_sets rebuild above, which would drop added columns
+            create_app_tables(&conn)
+                .expect("Failed to create app tables");
+            // Decks used to be .ydk files in ressources/decks/. A file that
+            // fails to migrate stays there and is reported to the frontend
+            // through get_ydk_migration; the app still starts
+            app.manage(migrate_ydk_decks(&mut conn));
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             // Collection
             load_card_stubs,
+            search_card_stubs,
+            search_card_query,
+            check_card_query,
             load_card_detail,
             get_all_sets,
             get_all_archetypes,
             update_collection_amount,
             get_collection_value,
+            get_deck_value,
+            // Statistics
+            get_collection_stats,
+            // Price history
+            get_price_history,
+            get_collection_value_history,
+            get_price_movers,
+            // Set completion
+            get_set_completion,
+            get_missing_set_cards,
+            // Collection history
+            get_collection_history,
+            undo_collection_changes,
+            // Collection import
+            import_collection_csv,
+            // Collection export
+            export_collection,
+            // Wishlist
+            add_wishlist_entry,
+            remove_wishlist_entry,
+            list_wishlist,
+            get_wishlist_deals,
+            // Quick add
+            quick_add,
+            quick_add_batch,
+            // Purchases / sales
+            add_purchase,
+            record_sale,
+            get_sales,
+            get_profit_loss,
+            // Trade list
+            set_trade_amount,
+            get_trade_list,
+            export_trade_list,
+            match_trade_files,
+            // Excess copies / playsets
+            get_excess_copies,
+            get_playset_shortfall,
+            // Saved searches
+            save_search,
+            update_saved_search,
+            delete_saved_search,
+            list_saved_searches,
+            run_saved_search,
+            // Sealed products
+            add_sealed_product,
+            update_sealed_product,
+            remove_sealed_product,
+            list_sealed_products,
+            open_sealed_product,
+            list_sealed_openings,
+            // Graded cards
+            add_graded_card,
+            update_graded_card,
+            remove_graded_card,
+            list_graded_cards,
+            // Loans
+            lend_cards,
+            return_loan,
+            delete_loan,
+            list_loans,
+            get_lent_amounts,
+            // Tags / notes
+            create_tag,
+            update_tag,
+            delete_tag,
+            list_tags,
+            add_card_tag,
+            remove_card_tag,
+            set_card_note,
+            // Storage locations
+            create_location,
+            update_location,
+            delete_location,
+            list_locations,
+            store_copies,
+            get_location_contents,
+            get_binder_layout,
             // Deck / ban list
             get_ban_list,
             get_collection_amounts,
@@ -97,6 +289,13 @@ fn main() {
             save_deck,
             delete_deck,
             load_deck,
+            rename_deck,
+            duplicate_deck,
+            update_deck_info,
+            import_deck_ydk,
+            export_deck_ydk,
+            get_ydk_migration,
+            validate_deck,
             // Alt art editor
             ensure_artwork_column,
             get_alt_art_cards,
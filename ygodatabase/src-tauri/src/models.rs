@@ -1,4 +1,4 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
@@ -33,6 +33,7 @@ pub struct CardStub {
     pub level: Option<i64>,
     pub set_code: Option<String>,
     pub genesys_points: i64,
+    pub tags: Vec<String>,
 }
 
 #[derive(Serialize)]
@@ -56,8 +57,191 @@ pub struct CardDetail {
     pub scale: Option<i64>,
     pub linkval: Option<i64>,
     pub typeline: Option<Vec<String>>,
+    pub link_markers: Vec<LinkMarker>,
+    pub has_effect: Option<bool>,
+    pub treated_as: Option<String>, // the other card name this one counts as
 
     pub sets: Vec<CardSet>,
+    pub graded: Vec<GradedCard>,
+    pub loans: Vec<Loan>, // outstanding loans of the card
+    pub tags: Vec<String>,
+    pub notes: Vec<CardNote>,
+    pub locations: Vec<StoredCopies>, // where the owned copies are kept
+
+    /// Currency of every price above, and the exchange rate date used
+    pub currency: String,
+    pub rate_date: Option<String>,
+    /// Currencies without a stored rate; their amounts are left out
+    pub missing_rates: Vec<String>,
+}
+
+/// One graded slab of a printing. Not part of the printing's
+/// collection_amount, which only counts raw copies.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GradedCard {
+    pub id: i64,
+    pub card_id: i64,
+    pub set_code: String,
+    pub set_rarity: String,
+    pub artwork: i64,
+    pub company: String,
+    pub grade: String,
+    pub cert_number: Option<String>,
+    pub price: Option<f64>,
+    pub currency: String,
+    /// What the raw copy cost, when it came out of a purchase lot
+    pub cost: Option<f64>,
+    pub cost_currency: Option<String>,
+    pub added_at: String,
+}
+
+/// Copies lent to someone. None in set_code / set_rarity / artwork means
+/// any printing of the card.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Loan {
+    pub id: i64,
+    pub card_id: i64,
+    pub card_name: String,
+    pub set_code: Option<String>,
+    pub set_rarity: Option<String>,
+    pub artwork: Option<i64>,
+    pub quantity: i64,
+    pub borrower: String,
+    pub lent_on: String,
+    pub due_on: Option<String>,
+    pub returned_on: Option<String>,
+    pub overdue: bool,
+    pub note: Option<String>,
+}
+
+/// A user tag with the number of cards carrying it.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Tag {
+    pub id: i64,
+    pub name: String,
+    pub color: Option<String>,
+    pub card_count: i64,
+}
+
+/// A free-text note on a card, or on one printing when set_code /
+/// set_rarity / artwork are given.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CardNote {
+    pub card_id: i64,
+    pub set_code: Option<String>,
+    pub set_rarity: Option<String>,
+    pub artwork: Option<i64>,
+    pub note: String,
+    pub updated_at: String,
+}
+
+/// Copies of one printing kept at a storage location. `path` names the
+/// location with its parents, e.g. "Box A / Binder 2 / Page 3".
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StoredCopies {
+    pub location_id: i64,
+    pub path: String,
+    pub card_id: i64,
+    pub card_name: String,
+    pub set_code: String,
+    pub set_rarity: String,
+    pub artwork: i64,
+    pub quantity: i64,
+}
+
+/// A Link arrow, serialized with the names the card data uses ("Bottom-Left").
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LinkMarker {
+    #[serde(rename = "Top-Left")]
+    TopLeft,
+    Top,
+    #[serde(rename = "Top-Right")]
+    TopRight,
+    Left,
+    Right,
+    #[serde(rename = "Bottom-Left")]
+    BottomLeft,
+    Bottom,
+    #[serde(rename = "Bottom-Right")]
+    BottomRight,
+}
+
+impl LinkMarker {
+    /// Name as stored in cards.linkmarkers.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LinkMarker::TopLeft => "Top-Left",
+            LinkMarker::Top => "Top",
+            LinkMarker::TopRight => "Top-Right",
+            LinkMarker::Left => "Left",
+            LinkMarker::Right => "Right",
+            LinkMarker::BottomLeft => "Bottom-Left",
+            LinkMarker::Bottom => "Bottom",
+            LinkMarker::BottomRight => "Bottom-Right",
+        }
+    }
+
+    /// Lenient parse: "Bottom-Left", "bottom left", "bottomleft" and "bl" all work.
+    pub fn parse(s: &str) -> Option<LinkMarker> {
+        let key: String = s
+            .chars()
+            .filter(|c| !matches!(c, '-' | '_' | ' '))
+            .collect::<String>()
+            .to_lowercase();
+        Some(match key.as_str() {
+            "topleft" | "tl" => LinkMarker::TopLeft,
+            "top" | "t" => LinkMarker::Top,
+            "topright" | "tr" => LinkMarker::TopRight,
+            "left" | "l" => LinkMarker::Left,
+            "right" | "r" => LinkMarker::Right,
+            "bottomleft" | "bl" => LinkMarker::BottomLeft,
+            "bottom" | "b" => LinkMarker::Bottom,
+            "bottomright" | "br" => LinkMarker::BottomRight,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FacetCount {
+    pub value: Option<String>,
+    pub count: i64,
+}
+
+/// Stub counts per value among all matches (not just the current page).
+#[derive(Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct CardFacets {
+    pub frame_types: Vec<FacetCount>,
+    pub attributes: Vec<FacetCount>,
+    pub races: Vec<FacetCount>,
+}
+
+/// One page of `search_card_stubs`; `total` counts stubs across all pages.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CardPage {
+    pub stubs: Vec<CardStub>,
+    pub total: i64,
+    pub offset: i64,
+    pub limit: i64,
+    pub facets: CardFacets,
+}
+
+/// Identifies one `card_sets` row — the unit collection amounts are kept at.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PrintingKey {
+    pub card_id: i64,
+    pub set_code: String,
+    pub set_rarity: String,
+    pub artwork: i64,
 }
 
 // Raw query row helpers — not serialized, used only internally
@@ -75,6 +259,7 @@ pub struct RawStubRow {
     pub level: Option<i64>,
     pub set_code: Option<String>,
     pub genesys_points: i64,
+    pub tags: Vec<String>,
 }
 
 #[derive(Debug)]
@@ -98,7 +283,37 @@ pub struct RawDetailRow {
     pub scale: Option<i64>,
     pub linkval: Option<i64>,
     pub typeline: Option<Vec<String>>,
+    pub link_markers: Vec<LinkMarker>,
+    pub has_effect: Option<bool>,
+    pub treated_as: Option<String>,
     pub collection_amount: Option<i64>,
     pub set_price: Option<f64>,
+    pub price_currency: String,
     pub artwork: i64,
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn link_marker_parse_is_lenient() {
+        for s in ["Bottom-Left", "bottom left", "bottomleft", "BOTTOM_LEFT", "bl"] {
+            assert_eq!(LinkMarker::parse(s), Some(LinkMarker::BottomLeft), "{s}");
+        }
+        assert_eq!(LinkMarker::parse("T"), Some(LinkMarker::Top));
+        assert_eq!(LinkMarker::parse("diagonal"), None);
+        assert_eq!(LinkMarker::parse(""), None);
+    }
+
+    #[test]
+    fn link_marker_parse_round_trips_the_stored_name() {
+        let all = [
+            LinkMarker::TopLeft, LinkMarker::Top, LinkMarker::TopRight, LinkMarker::Left,
+            LinkMarker::Right, LinkMarker::BottomLeft, LinkMarker::Bottom, LinkMarker::BottomRight,
+        ];
+        for m in all {
+            assert_eq!(LinkMarker::parse(m.as_str()), Some(m));
+        }
+    }
+}
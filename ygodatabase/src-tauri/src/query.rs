@@ -0,0 +1,645 @@
+use rusqlite::types::Value;
+use serde::Serialize;
+use std::fmt;
+
+use crate::filters::{ban_key_for_format, SqlFilter, MONSTER_FRAMES};
+use crate::models::LinkMarker;
+
+// Search box query language, compiled to the same kind of WHERE condition
+// CardFilters::to_sql builds (cards aliased `c`, card_sets `cs`).
+//
+//   dark magician              words match the card name
+//   "dark magician"            quoted phrase
+//   atk>=2500 level:7-8        numeric fields take = : != > >= < <= and a-b ranges
+//   atk:?                      "?" ATK / DEF
+//   attr:dark race:dragon      text fields, case-insensitive
+//   arch:"Blue-Eyes"           quotes keep spaces and dashes in values
+//   -banned:tcg  NOT owned>0   negation
+//   -rarity:"secret rare"      cards with no such printing (also -set:)
+//   (race:dragon OR race:wyrm) grouping; OR binds weaker than the implicit AND
+//
+// Fields:
+//   name n · desc text · attr attribute · race · type · frame · kind category
+//   arch archetype · set · rarity · atk · def · level lv rank · link · scale
+//   owned · genesys points · ban · banned limited semi
+//   marker arrow (marker:bl,br = both arrows) · effect (yes / no) · treated
+//   tag (user tags)
+
+/// A query that failed to parse, with the character range of the offending
+/// token so the search box can point at it.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryError {
+    pub message: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} (column {})", self.message, self.start + 1)
+    }
+}
+
+#[derive(Debug)]
+enum Tok {
+    LParen,
+    RParen,
+    Not,
+    Or,
+    And,
+    Term {
+        field: Option<String>,
+        op: String,
+        value: String,
+        quoted: bool,
+    },
+}
+
+#[derive(Debug)]
+struct Token {
+    tok: Tok,
+    start: usize,
+    end: usize,
+}
+
+const OPERATORS: &[&str] = &[">=", "<=", "!=", ":", "=", ">", "<"];
+
+fn error(message: impl Into<String>, start: usize, end: usize) -> QueryError {
+    QueryError { message: message.into(), start, end }
+}
+
+// ---------------------------------------------------------------------------
+// Tokenizer
+// ---------------------------------------------------------------------------
+
+fn tokenize(input: &str) -> Result<Vec<Token>, QueryError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        if c == '(' || c == ')' {
+            let tok = if c == '(' { Tok::LParen } else { Tok::RParen };
+            tokens.push(Token { tok, start, end: i + 1 });
+            i += 1;
+            continue;
+        }
+        if c == '-' && chars.get(i + 1).is_some_and(|n| !n.is_whitespace()) {
+            tokens.push(Token { tok: Tok::Not, start, end: i + 1 });
+            i += 1;
+            continue;
+        }
+
+        // A term runs to the next space or parenthesis; quoted parts may
+        // contain both. `bare_len` is how much came before the first quote,
+        // which is the only part an operator is looked for in.
+        let mut raw = String::new();
+        let mut quoted = false;
+        let mut bare_len: Option<usize> = None;
+        while i < chars.len() && !chars[i].is_whitespace() && chars[i] != '(' && chars[i] != ')' {
+            if chars[i] == '"' {
+                let close = chars[i + 1..]
+                    .iter()
+                    .position(|&ch| ch == '"')
+                    .map(|p| i + 1 + p)
+                    .ok_or_else(|| error("Unterminated quote", i, chars.len()))?;
+                bare_len.get_or_insert(raw.chars().count());
+                raw.extend(&chars[i + 1..close]);
+                quoted = true;
+                i = close + 1;
+            } else {
+                raw.push(chars[i]);
+                i += 1;
+            }
+        }
+        let end = i;
+
+        let tok = match raw.as_str() {
+            "OR" | "|" if !quoted => Tok::Or,
+            "AND" if !quoted => Tok::And,
+            "NOT" if !quoted => Tok::Not,
+            _ => split_term(&raw, bare_len.unwrap_or(raw.chars().count()), quoted),
+        };
+        tokens.push(Token { tok, start, end });
+    }
+    Ok(tokens)
+}
+
+/// "atk>=2500" → field "atk", op ">=", value "2500". Anything without a
+/// leading `word<op>` (outside quotes) is a bare name search.
+fn split_term(raw: &str, bare_len: usize, quoted: bool) -> Tok {
+    let field_len = raw
+        .chars()
+        .take_while(|c| c.is_ascii_alphabetic() || *c == '_')
+        .count();
+    if field_len > 0 {
+        let rest: String = raw.chars().skip(field_len).collect();
+        if let Some(op) = OPERATORS.iter().find(|op| rest.starts_with(**op)) {
+            if field_len + op.len() <= bare_len {
+                return Tok::Term {
+                    field: Some(raw.chars().take(field_len).collect::<String>().to_lowercase()),
+                    op: op.to_string(),
+                    value: rest[op.len()..].to_string(),
+                    quoted,
+                };
+            }
+        }
+    }
+    Tok::Term { field: None, op: ":".to_string(), value: raw.to_string(), quoted }
+}
+
+// ---------------------------------------------------------------------------
+// Parser
+// ---------------------------------------------------------------------------
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    input_len: usize,
+    params: Vec<(String, Value)>,
+    ban_key: &'static str,
+    // Inside an odd number of negations, see printing_condition
+    negated: bool,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Tok> {
+        self.tokens.get(self.pos).map(|t| &t.tok)
+    }
+
+    /// Range of the current token, or the end of the input.
+    fn here(&self) -> (usize, usize) {
+        self.tokens
+            .get(self.pos)
+            .map(|t| (t.start, t.end))
+            .unwrap_or((self.input_len, self.input_len))
+    }
+
+    fn param(&mut self, value: Value) -> String {
+        let name = format!(":q{}", self.params.len());
+        self.params.push((name.clone(), value));
+        name
+    }
+
+    // or := and ("OR" and)*
+    fn parse_or(&mut self) -> Result<String, QueryError> {
+        let mut parts = vec![self.parse_and()?];
+        while matches!(self.peek(), Some(Tok::Or)) {
+            self.pos += 1;
+            parts.push(self.parse_and()?);
+        }
+        Ok(if parts.len() == 1 {
+            parts.remove(0)
+        } else {
+            format!("({})", parts.join(" OR "))
+        })
+    }
+
+    // and := unary (["AND"] unary)*
+    fn parse_and(&mut self) -> Result<String, QueryError> {
+        let mut parts = Vec::new();
+        loop {
+            match self.peek() {
+                None | Some(Tok::RParen) | Some(Tok::Or) => break,
+                Some(Tok::And) => self.pos += 1,
+                _ => parts.push(self.parse_unary()?),
+            }
+        }
+        if parts.is_empty() {
+            let (start, end) = self.here();
+            return Err(error("Expected a search term", start, end));
+        }
+        Ok(format!("({})", parts.join(" AND ")))
+    }
+
+    // unary := ("-" | "NOT") unary | "(" or ")" | term
+    fn parse_unary(&mut self) -> Result<String, QueryError> {
+        let (start, end) = self.here();
+        match self.peek() {
+            Some(Tok::Not) => {
+                self.pos += 1;
+                if matches!(self.peek(), None | Some(Tok::RParen) | Some(Tok::Or)) {
+                    return Err(error("Nothing to negate", start, end));
+                }
+                self.negated = !self.negated;
+                let inner = self.parse_unary();
+                self.negated = !self.negated;
+                Ok(format!("NOT {}", inner?))
+            }
+            Some(Tok::LParen) => {
+                self.pos += 1;
+                let inner = self.parse_or()?;
+                if !matches!(self.peek(), Some(Tok::RParen)) {
+                    return Err(error("Missing closing parenthesis", start, end));
+                }
+                self.pos += 1;
+                Ok(inner)
+            }
+            Some(Tok::Term { field, op, value, quoted }) => {
+                let (field, op, value, quoted) = (field.clone(), op.clone(), value.clone(), *quoted);
+                self.pos += 1;
+                self.term(field, &op, value, quoted, start, end)
+            }
+            _ => Err(error("Unexpected token", start, end)),
+        }
+    }
+
+    /// A condition on one printing, `cond` written against the alias it is
+    /// given. Matched as is, it picks the card_sets row (so only matching
+    /// rarities are listed); negated, by "-" / NOT or "!=", it has to hold
+    /// for no printing of the card, or the card's other printings would
+    /// still match.
+    fn printing_condition(&self, op: &str, cond: impl Fn(&str) -> String) -> String {
+        if self.negated != (op == "!=") {
+            format!(
+                "EXISTS (SELECT 1 FROM card_sets ps WHERE ps.card_id = c.id AND ({}))",
+                cond("ps")
+            )
+        } else {
+            cond("cs")
+        }
+    }
+
+    /// Compiles one `field op value` term. Every condition is wrapped in
+    /// COALESCE(…, 0) so NULL columns count as "no match" and negation works.
+    fn term(
+        &mut self,
+        field: Option<String>,
+        op: &str,
+        value: String,
+        quoted: bool,
+        start: usize,
+        end: usize,
+    ) -> Result<String, QueryError> {
+        let Some(field) = field else {
+            let p = self.param(Value::Text(format!("%{}%", value)));
+            return Ok(format!("COALESCE(c.name LIKE {p}, 0)"));
+        };
+        let field_end = start + field.chars().count();
+        if value.is_empty() && !quoted {
+            return Err(error(format!("Missing value for {}", field), start, end));
+        }
+
+        let text_ops = |allowed: &[&str]| -> Result<(), QueryError> {
+            if allowed.contains(&op) {
+                Ok(())
+            } else {
+                Err(error(format!("'{}' does not work with {}; use {}", op, field, allowed.join(" ")), start, end))
+            }
+        };
+        // Wraps an equality condition, turning "!=" into its negation
+        let eq = |cond: String| -> String {
+            if op == "!=" {
+                format!("NOT COALESCE(({}), 0)", cond)
+            } else {
+                format!("COALESCE(({}), 0)", cond)
+            }
+        };
+
+        let sql = match field.as_str() {
+            "name" | "n" => {
+                text_ops(&[":", "=", "!="])?;
+                if op == "=" {
+                    let p = self.param(Value::Text(value));
+                    eq(format!("c.name = {p} COLLATE NOCASE"))
+                } else {
+                    let p = self.param(Value::Text(format!("%{}%", value)));
+                    eq(format!("c.name LIKE {p}"))
+                }
+            }
+            "desc" | "text" => {
+                text_ops(&[":", "!="])?;
+                let p = self.param(Value::Text(format!("%{}%", value)));
+                eq(format!("c.desc LIKE {p}"))
+            }
+            "type" => {
+                text_ops(&[":", "!="])?;
+                let p = self.param(Value::Text(format!("%{}%", value)));
+                eq(format!("c.type LIKE {p}"))
+            }
+            "attr" | "attribute" | "race" | "frame" => {
+                text_ops(&[":", "=", "!="])?;
+                let column = match field.as_str() {
+                    "race" => "c.race",
+                    "frame" => "c.frameType",
+                    _ => "c.attribute",
+                };
+                let p = self.param(Value::Text(value));
+                eq(format!("{column} = {p} COLLATE NOCASE"))
+            }
+            "rarity" => {
+                text_ops(&[":", "=", "!="])?;
+                let p = self.param(Value::Text(value));
+                eq(self.printing_condition(op, |t| format!("{t}.set_rarity = {p} COLLATE NOCASE")))
+            }
+            "kind" | "category" => {
+                text_ops(&[":", "=", "!="])?;
+                let frames = match value.to_lowercase().as_str() {
+                    "monster" => MONSTER_FRAMES.iter().map(|f| format!("'{}'", f)).collect::<Vec<_>>().join(", "),
+                    "spell" => "'spell'".to_string(),
+                    "trap" => "'trap'".to_string(),
+                    _ => return Err(error(
+                        format!("Unknown kind '{}'. Use monster, spell or trap", value),
+                        field_end + op.len(),
+                        end,
+                    )),
+                };
+                eq(format!("c.frameType IN ({frames})"))
+            }
+            "arch" | "archetype" => {
+                text_ops(&[":", "=", "!="])?;
+                let p = self.param(Value::Text(value));
+                eq(format!(
+                    "c.archetype IS NOT NULL AND EXISTS (
+                        SELECT 1 FROM json_each(c.archetype) WHERE LOWER(value) = LOWER({p})
+                    )"
+                ))
+            }
+            "tag" => {
+                text_ops(&[":", "=", "!="])?;
+                let p = self.param(Value::Text(value));
+                eq(format!(
+                    "EXISTS (
+                        SELECT 1 FROM card_tags ct JOIN tags t ON t.id = ct.tag_id
+                        WHERE ct.card_id = c.id AND t.name = {p} COLLATE NOCASE
+                    )"
+                ))
+            }
+            "set" => {
+                text_ops(&[":", "=", "!="])?;
+                let p = self.param(Value::Text(value));
+                eq(self.printing_condition(op, |t| {
+                    format!("{t}.set_name = {p} COLLATE NOCASE OR {t}.set_code LIKE {p} || '-%'")
+                }))
+            }
+            "ban" => {
+                text_ops(&[":", "=", "!="])?;
+                let status = ban_status(&value)
+                    .ok_or_else(|| error(
+                        format!("Unknown ban status '{}'. Use banned, limited, semi or unlimited", value),
+                        field_end + op.len(),
+                        end,
+                    ))?;
+                eq(ban_condition(self.ban_key, status))
+            }
+            "marker" | "arrow" => {
+                text_ops(&[":", "=", "!="])?;
+                let mut markers = Vec::new();
+                for part in value.split(',') {
+                    let marker = LinkMarker::parse(part).ok_or_else(|| error(
+                        format!("Unknown link arrow '{}'. Use e.g. top, bottom-left or bl", part),
+                        field_end + op.len(),
+                        end,
+                    ))?;
+                    markers.push(marker.as_str());
+                }
+                let p = self.param(Value::Text(serde_json::to_string(&markers).unwrap_or_default()));
+                eq(format!(
+                    "json_type(c.linkmarkers) = 'array' AND NOT EXISTS (
+                        SELECT 1 FROM json_each({p}) w
+                        WHERE w.value NOT IN (SELECT value FROM json_each(c.linkmarkers))
+                    )"
+                ))
+            }
+            "effect" => {
+                text_ops(&[":", "="])?;
+                let wanted = match value.to_lowercase().as_str() {
+                    "yes" | "true" | "1" => 1,
+                    "no" | "false" | "0" => 0,
+                    _ => return Err(error(
+                        format!("Expected yes or no for effect, got '{}'", value),
+                        field_end + op.len(),
+                        end,
+                    )),
+                };
+                let frames = MONSTER_FRAMES.iter().map(|f| format!("'{}'", f)).collect::<Vec<_>>().join(", ");
+                eq(format!(
+                    "c.frameType IN ({frames}) AND
+                     COALESCE(c.has_effect, c.frameType NOT IN ('normal', 'normal_pendulum')) = {wanted}"
+                ))
+            }
+            "treated" => {
+                text_ops(&[":", "!="])?;
+                let p = self.param(Value::Text(format!("%{}%", value)));
+                eq(format!("c.treated_as LIKE {p}"))
+            }
+            "banned" | "limited" | "semi" => {
+                text_ops(&[":", "="])?;
+                let key = match value.to_lowercase().as_str() {
+                    "tcg" => "ban_tcg",
+                    "ocg" => "ban_ocg",
+                    "goat" => "ban_goat",
+                    _ => return Err(error(
+                        format!("Unknown ban list '{}'. Use tcg, ocg or goat", value),
+                        field_end + op.len(),
+                        end,
+                    )),
+                };
+                eq(ban_condition(key, ban_status(&field).unwrap()))
+            }
+            _ => {
+                let Some(column) = numeric_column(&field) else {
+                    return Err(error(format!("Unknown field '{}'", field), start, field_end));
+                };
+                let value_start = field_end + op.len();
+                let number = |s: &str| -> Result<i64, QueryError> {
+                    s.trim().parse::<i64>().map_err(|_| error(
+                        format!("Expected a number for {}, got '{}'", field, s),
+                        value_start,
+                        end,
+                    ))
+                };
+                // "?" ATK / DEF is stored as -1; comparisons leave it out
+                let has_unknown = column == "c.atk" || column == "c.def";
+                if has_unknown && value == "?" {
+                    text_ops(&[":", "=", "!="])?;
+                    return Ok(eq(format!("{column} = -1")));
+                }
+                let known = if has_unknown { format!("{column} >= 0 AND ") } else { String::new() };
+                match op {
+                    ":" | "=" | "!=" => match value.split_once('-').filter(|(a, _)| !a.is_empty()) {
+                        Some((lo, hi)) if op != "!=" => {
+                            let (lo, hi) = (number(lo)?, number(hi)?);
+                            let (lo, hi) = (self.param(Value::Integer(lo)), self.param(Value::Integer(hi)));
+                            eq(format!("{column} BETWEEN {lo} AND {hi}"))
+                        }
+                        _ => {
+                            let p = self.param(Value::Integer(number(&value)?));
+                            eq(format!("{column} = {p}"))
+                        }
+                    },
+                    _ => {
+                        let p = self.param(Value::Integer(number(&value)?));
+                        format!("COALESCE(({known}{column} {op} {p}), 0)")
+                    }
+                }
+            }
+        };
+        Ok(sql)
+    }
+}
+
+fn numeric_column(field: &str) -> Option<&'static str> {
+    Some(match field {
+        "atk" => "c.atk",
+        "def" => "c.def",
+        "level" | "lv" | "rank" => "c.level",
+        "link" => "c.linkval",
+        "scale" => "c.scale",
+        "genesys" | "points" => "COALESCE(c.genesys_points, 0)",
+        "owned" => "(SELECT COALESCE(SUM(o.collection_amount), 0) FROM card_sets o WHERE o.card_id = c.id)",
+        _ => return None,
+    })
+}
+
+/// Maps the words accepted by ban / banned / limited / semi to the
+/// banlist_info value; None in the result means "not on the list".
+fn ban_status(word: &str) -> Option<Option<&'static str>> {
+    match word.to_lowercase().as_str() {
+        "banned" | "forbidden" => Some(Some("Banned")),
+        "limited" => Some(Some("Limited")),
+        "semi" | "semi-limited" => Some(Some("Semi-Limited")),
+        "unlimited" => Some(None),
+        _ => None,
+    }
+}
+
+fn ban_condition(key: &str, status: Option<&str>) -> String {
+    match status {
+        // Case-insensitive, as the ban_status filter of CardFilters
+        Some(s) => format!("LOWER(json_extract(c.banlist_info, '$.{key}')) = LOWER('{s}')"),
+        None => format!("json_extract(c.banlist_info, '$.{key}') IS NULL"),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Entry point
+// ---------------------------------------------------------------------------
+
+/// Parses a search box query. `format` picks the ban list `ban:` refers to,
+/// as for CardFilters. An empty query matches every card.
+pub fn parse_query(input: &str, format: Option<&str>) -> Result<SqlFilter, QueryError> {
+    let mut parser = Parser {
+        tokens: tokenize(input)?,
+        pos: 0,
+        input_len: input.chars().count(),
+        params: Vec::new(),
+        ban_key: ban_key_for_format(format),
+        negated: false,
+    };
+    if parser.tokens.is_empty() {
+        return Ok(SqlFilter { clause: "1".to_string(), params: Vec::new() });
+    }
+
+    let clause = parser.parse_or()?;
+    if parser.pos < parser.tokens.len() {
+        let (start, end) = parser.here();
+        return Err(error("Unmatched ')'", start, end));
+    }
+    Ok(SqlFilter { clause, params: parser.params })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_db;
+
+    // Card ids matching `query`, run the way the stub queries join card_sets
+    fn matching(query: &str) -> Vec<i64> {
+        let conn = test_db();
+        conn.execute_batch(
+            r#"INSERT INTO cards (id, name, atk, banlist_info) VALUES
+                   (1, 'Dark Magician', 2500, NULL),
+                   (2, 'Blue-Eyes White Dragon', 3000, '{"ban_tcg":"limited"}'),
+                   (3, 'Dark Magician Girl', 2000, NULL);
+               INSERT INTO card_sets (card_id, set_code, set_name, set_rarity) VALUES
+                   (1, 'SDY-006', 'Starter Deck: Yugi', 'Ultra Rare'),
+                   (1, 'LOB-005', 'Legend of Blue Eyes White Dragon', 'Secret Rare'),
+                   (2, 'LOB-001', 'Legend of Blue Eyes White Dragon', 'Ultra Rare'),
+                   (3, 'MFC-000', 'Magician''s Force', 'Secret Rare');"#,
+        )
+        .unwrap();
+        let filter = parse_query(query, None).unwrap();
+        let sql = format!(
+            "SELECT DISTINCT c.id FROM cards c LEFT JOIN card_sets cs ON cs.card_id = c.id
+             WHERE {} ORDER BY c.id",
+            filter.clause
+        );
+        let mut stmt = conn.prepare(&sql).unwrap();
+        let ids = stmt
+            .query_map(filter.params_ref().as_slice(), |r| r.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        ids
+    }
+
+    fn error_span(query: &str) -> (usize, usize) {
+        let e = parse_query(query, None).err().expect("query should not parse");
+        (e.start, e.end)
+    }
+
+    #[test]
+    fn tokenizes_terms_operators_and_groups() {
+        let tokens = tokenize(r#"atk>=2500 "dark magician" -banned:tcg (a OR b)"#).unwrap();
+        let spans: Vec<(usize, usize)> = tokens.iter().map(|t| (t.start, t.end)).collect();
+        assert_eq!(spans, vec![(0, 9), (10, 25), (26, 27), (27, 37), (38, 39), (39, 40), (41, 43), (44, 45), (45, 46)]);
+        assert!(matches!(&tokens[0].tok, Tok::Term { field: Some(f), op, value, .. } if f == "atk" && op == ">=" && value == "2500"));
+        assert!(matches!(&tokens[1].tok, Tok::Term { field: None, value, quoted: true, .. } if value == "dark magician"));
+        assert!(matches!(tokens[2].tok, Tok::Not));
+        assert!(matches!(tokens[6].tok, Tok::Or));
+    }
+
+    #[test]
+    fn quotes_keep_operators_in_values() {
+        let tokens = tokenize(r#"arch:"Blue-Eyes" "a:b""#).unwrap();
+        assert!(matches!(&tokens[0].tok, Tok::Term { field: Some(f), value, quoted: true, .. } if f == "arch" && value == "Blue-Eyes"));
+        // The ':' is inside the quotes, so this is a name search
+        assert!(matches!(&tokens[1].tok, Tok::Term { field: None, value, .. } if value == "a:b"));
+    }
+
+    #[test]
+    fn errors_point_at_the_bad_token() {
+        assert_eq!(error_span("atk>=abc"), (5, 8));
+        assert_eq!(error_span("level:4 foo:1"), (8, 11));
+        assert_eq!(error_span(r#"name:"dark"#), (5, 10));
+        assert_eq!(error_span("(atk>1"), (0, 1));
+        assert_eq!(error_span("atk>1 )"), (6, 7));
+        assert_eq!(error_span("dark NOT"), (5, 8));
+        assert_eq!(error_span("ban:sometimes"), (4, 13));
+        assert_eq!(error_span("rarity>ultra"), (0, 12));
+    }
+
+    #[test]
+    fn negation_and_grouping() {
+        assert_eq!(matching("dark magician"), vec![1, 3]);
+        assert_eq!(matching("dark -girl"), vec![1]);
+        assert_eq!(matching("dark NOT atk>=2500"), vec![3]);
+        assert_eq!(matching("atk>2400 (blue OR girl)"), vec![2]);
+        assert_eq!(matching("-(dark OR blue)"), Vec::<i64>::new());
+    }
+
+    #[test]
+    fn negated_printing_fields_exclude_the_card() {
+        assert_eq!(matching(r#"rarity:"secret rare""#), vec![1, 3]);
+        // Card 1 also has an Ultra Rare, but it has a Secret Rare
+        assert_eq!(matching(r#"-rarity:"secret rare""#), vec![2]);
+        assert_eq!(matching(r#"rarity!="ultra rare""#), vec![3]);
+        assert_eq!(matching("-set:LOB"), vec![3]);
+        assert_eq!(matching("NOT -set:LOB"), vec![1, 2]);
+    }
+
+    #[test]
+    fn ban_status_ignores_case() {
+        assert_eq!(matching("ban:limited"), vec![2]);
+        assert_eq!(matching("limited:tcg"), vec![2]);
+        assert_eq!(matching("ban:unlimited"), vec![1, 3]);
+    }
+}
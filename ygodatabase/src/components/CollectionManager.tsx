@@ -2,7 +2,7 @@ import { useEffect, useState, useCallback, useMemo, useRef } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import { List } from "react-window";
 
-import type { CardStub, CardDetail, CardSet, CardSetRarity } from "../types/cards";
+import type { CardStub, CardDetail, CardSet, CardSetRarity, MoneyValue } from "../types/cards";
 import AltArtEditor from "./AltArtEditor";
 import { getRarityGroup, getFrameBackground, formatTypeline } from "../utils/cardUtils";
 import { rarityGroupColors, rarityGroupIcons } from "../constants/rarity";
@@ -22,13 +22,18 @@ export default function CollectionManager({ onBack }: CollectionManagerProps) {
   const [altArtMode, setAltArtMode] = useState(false);
   const [detailLoading, setDetailLoading] = useState(false);
   const [collectionValue, setCollectionValue] = useState<number>(0);
+  // Currencies left out of the value for lack of an exchange rate
+  const [missingRates, setMissingRates] = useState<string[]>([]);
   const gridRef = useRef<HTMLDivElement>(null);
   const [gridWidth, setGridWidth] = useState(0);
 
   useEffect(() => {
     invoke<string[]>("get_all_sets").then(setSets);
     invoke("ensure_artwork_column").catch(() => {});
-    invoke<number>("get_collection_value").then(setCollectionValue).catch(() => {});
+    invoke<MoneyValue>("get_collection_value").then((v) => {
+      setCollectionValue(v.amount);
+      setMissingRates(v.missingRates);
+    }).catch(() => {});
   }, []);
 
   useEffect(() => {
@@ -39,15 +44,11 @@ export default function CollectionManager({ onBack }: CollectionManagerProps) {
   const latestRequestId = useRef(0);
   useEffect(() => {
     const requestId = ++latestRequestId.current;
-    const params: Record<string, string> = {};
-    if (search.trim().length > 0) params.name = search;
-    if (selectedSet !== "ALL") {
-      params.set  = selectedSet;
-      params.sort = "set";
-    } else {
-      params.sort = "type";
-    }
-    invoke<CardStub[]>("load_card_stubs", params).then((result) => {
+    const filters: Record<string, string> = {};
+    if (search.trim().length > 0) filters.name = search;
+    if (selectedSet !== "ALL") filters.set = selectedSet;
+    const sort = selectedSet !== "ALL" ? "set" : "type";
+    invoke<CardStub[]>("load_card_stubs", { filters, sort }).then((result) => {
       if (requestId === latestRequestId.current) setCards(result);
     });
   }, [search, selectedSet]);
@@ -95,7 +96,10 @@ export default function CollectionManager({ onBack }: CollectionManagerProps) {
       return;
     }
     // Refresh total value after any collection change
-    invoke<number>("get_collection_value").then(setCollectionValue).catch(() => {});
+    invoke<MoneyValue>("get_collection_value").then((v) => {
+      setCollectionValue(v.amount);
+      setMissingRates(v.missingRates);
+    }).catch(() => {});
     setSelectedCard((prev) => {
       if (!prev) return prev;
       return {
@@ -341,6 +345,9 @@ export default function CollectionManager({ onBack }: CollectionManagerProps) {
             letterSpacing: "0.05em",
           }}>
             {collectionValue.toLocaleString("en-US", { minimumFractionDigits: 2, maximumFractionDigits: 2 })}€
+            {missingRates.length > 0 && (
+              <span title={`Prices in ${missingRates.join(", ")} are left out: no exchange rate stored. Run a sync to fetch rates.`}> *</span>
+            )}
           </span>
         </div>
 
@@ -32,9 +32,64 @@ interface BanList {
   semiLimited: number[];
 }
 
-// Shape returned by the load_deck command
-interface LoadedDeck {
+// A saved deck without its cards, as returned by list_decks
+interface DeckSummary {
+  id: number;
   name: string;
+  format?: string;
+  notes?: string;
+  coverCardId?: number;
+  coverThumbPath?: string;
+  folder?: string;      // e.g. "Locals/2024"
+  tags: string[];
+  mainCount: number;
+  extraCount: number;
+  sideCount: number;
+  createdAt: string;
+  updatedAt: string;
+}
+
+// Startup import of the old .ydk deck files, as returned by get_ydk_migration
+interface YdkMigration {
+  migrated: number;
+  skipped: { file: string; reason: string }[];
+  error?: string;
+}
+
+// One way a deck breaks its format's rules, as returned by validate_deck
+type DeckViolation =
+  | { kind: "mainSize"; count: number; min: number; max: number }
+  | { kind: "extraSize"; count: number; max: number }
+  | { kind: "sideSize"; count: number; max: number }
+  | { kind: "unknownCard"; cardId: number }
+  | { kind: "notInFormat"; cardId: number; name: string }
+  | { kind: "extraDeckInMain"; cardId: number; name: string }
+  | { kind: "mainDeckInExtra"; cardId: number; name: string }
+  | { kind: "tooManyCopies"; cardId: number; name: string; count: number; limit: number }
+  | { kind: "pointCap"; total: number; cap: number };
+
+function violationText(v: DeckViolation): string {
+  switch (v.kind) {
+    case "mainSize":        return `Main deck has ${v.count} cards (needs ${v.min} to ${v.max})`;
+    case "extraSize":       return `Extra deck has ${v.count} cards (at most ${v.max})`;
+    case "sideSize":        return `Side deck has ${v.count} cards (at most ${v.max})`;
+    case "unknownCard":     return `Unknown card id ${v.cardId}`;
+    case "notInFormat":     return `${v.name} is not legal in this format`;
+    case "extraDeckInMain": return `${v.name} belongs in the extra deck`;
+    case "mainDeckInExtra": return `${v.name} cannot go in the extra deck`;
+    case "tooManyCopies":   return `${v.count} copies of ${v.name} (at most ${v.limit})`;
+    case "pointCap":        return `Deck is worth ${v.total} Genesys points (at most ${v.cap})`;
+  }
+}
+
+// save_deck's reply: the deck (null when refused as illegal), and why it
+// is not legal in its format
+interface SavedDeck {
+  deck: DeckSummary | null;
+  violations: DeckViolation[];
+}
+// Shape returned by the load_deck command
+interface LoadedDeck extends DeckSummary {
   main:  DeckStub[];
   extra: DeckStub[];
   side:  DeckStub[];
@@ -110,9 +165,18 @@ export default function Deckbuilder({ onBack }: DeckbuilderProps) {
   const [deckList, setDeckList] = useState<string[]>([]);
   const [deckName, setDeckName] = useState("");
   const [saveStatus, setSaveStatus] = useState<"idle" | "saved" | "error">("idle");
+  const [saveError, setSaveError] = useState("");  // why the last save failed
+  // Why the deck is not legal in its format, and whether it was still
+  // saved as a draft or refused
+  const [violations, setViolations] = useState<DeckViolation[]>([]);
+  const [savedAsDraft, setSavedAsDraft] = useState(false);
+  // .ydk files the startup migration could not import, until dismissed
+  const [migration, setMigration] = useState<YdkMigration | null>(null);
 
   // ── Collection comparison ─────────────────────────────────────────────────
   const [collectionAmounts, setCollectionAmounts] = useState<Record<number, number>>({});
+  // Copies lent out: still owned, but not on hand to play
+  const [lentAmounts, setLentAmounts] = useState<Record<number, number>>({});
   const [compareMode, setCompareMode] = useState(false);
 
   // ── Genesys points: card_id → points, loaded once, always available ───────
@@ -143,8 +207,12 @@ export default function Deckbuilder({ onBack }: DeckbuilderProps) {
     syncAndReload(banFormat);
     refreshDeckList();
     invoke<Record<number, number>>("get_collection_amounts").then(setCollectionAmounts).catch(() => {});
+    invoke<Record<number, number>>("get_lent_amounts").then(setLentAmounts).catch(() => {});
     // Load genesys points map once — exactly like the ban list
     invoke<Record<number, number>>("get_genesys_points").then(setGenesysPts).catch(() => {});
+    invoke<YdkMigration>("get_ydk_migration").then((m) => {
+      if (m.skipped.length > 0 || m.error) setMigration(m);
+    }).catch(() => {});
   }, []);
 
   // Re-sync whenever the user switches format
@@ -154,7 +222,7 @@ export default function Deckbuilder({ onBack }: DeckbuilderProps) {
   }, [banFormat]);
 
   function refreshDeckList() {
-    invoke<string[]>("list_decks").then(setDeckList).catch(() => {});
+    invoke<DeckSummary[]>("list_decks").then((decks) => setDeckList(decks.map((d) => d.name))).catch(() => {});
   }
 
   // ── Search debounce ───────────────────────────────────────────────────────
@@ -168,7 +236,7 @@ export default function Deckbuilder({ onBack }: DeckbuilderProps) {
   useEffect(() => {
     const reqId = ++latestReq.current;
     setCardLoading(true);
-    const params = { ...filtersToParams({ ...filters, name: search }, banFormat === "Genesys" ? undefined : banFormat), sort: "type" };
+    const params = { filters: filtersToParams({ ...filters, name: search }, banFormat === "Genesys" ? undefined : banFormat), sort: "type" };
     invoke<CardStub[]>("load_card_stubs", params).then((r) => {
       if (reqId === latestReq.current) { setCards(r); setCardLoading(false); }
     }).catch(() => setCardLoading(false));
@@ -243,20 +311,34 @@ export default function Deckbuilder({ onBack }: DeckbuilderProps) {
   }
 
   // ── Save ──────────────────────────────────────────────────────────────────
-  async function handleSave() {
+  async function handleSave(draft = false) {
     const name = deckName.trim();
     if (!name) return;
     try {
-      await invoke("save_deck", {
+      // An illegal deck is refused unless saved as a draft; either way
+      // the violations come back
+      const saved = await invoke<SavedDeck>("save_deck", {
         name,
         mainIds:  deck.main.map((e) => e.id),
         extraIds: deck.extra.map((e) => e.id),
         sideIds:  deck.side.map((e) => e.id),
+        format:   banFormat,
+        draft,
       });
+      setViolations(saved.violations);
+      setSavedAsDraft(saved.deck !== null);
+      if (!saved.deck) {
+        setSaveError(`Not legal in ${banFormat}`);
+        setSaveStatus("error");
+        setTimeout(() => setSaveStatus("idle"), 2000);
+        return;
+      }
+      setSaveError("");
       setSaveStatus("saved");
       refreshDeckList();
       setTimeout(() => setSaveStatus("idle"), 2000);
-    } catch {
+    } catch (err) {
+      setSaveError(String(err));
       setSaveStatus("error");
       setTimeout(() => setSaveStatus("idle"), 2000);
     }
@@ -273,6 +355,8 @@ export default function Deckbuilder({ onBack }: DeckbuilderProps) {
         side:  loaded.side.map(stubToDeckEntry),
       });
       setDeckName(loaded.name);
+      setViolations([]);
+      if (loaded.format) setBanFormat(loaded.format);
     } catch (err) {
       console.error("Failed to load deck:", err);
     }
@@ -287,6 +371,7 @@ export default function Deckbuilder({ onBack }: DeckbuilderProps) {
       await invoke("delete_deck", { name });
       setDeck({ main: [], extra: [], side: [] });
       setDeckName("");
+      setViolations([]);
       refreshDeckList();
     } catch (err) {
       console.error("Failed to delete deck:", err);
@@ -452,6 +537,9 @@ export default function Deckbuilder({ onBack }: DeckbuilderProps) {
             {grouped.map(({ entry, count }) => {
               const owned   = collectionAmounts[entry.id] ?? 0;
               const missing = compareMode ? Math.max(0, count - owned) : 0;
+              const lent    = lentAmounts[entry.id] ?? 0;
+              // Owned in full, but some of the copies are lent out right now
+              const lentShort = compareMode && missing === 0 && owned - lent < count;
               const pts     = genesysPts[entry.id] ?? 0;
               const totalPts = pts * count; // total cost of all copies of this card
 
@@ -459,7 +547,7 @@ export default function Deckbuilder({ onBack }: DeckbuilderProps) {
                 ? `${owned}/${count}`
                 : count > 1 ? `×${count}` : null;
               const badgeColor = compareMode
-                ? (missing > 0 ? "#e74c3c" : "#4caf50")
+                ? (missing > 0 ? "#e74c3c" : lentShort ? "#f0a500" : "#4caf50")
                 : "#f0d060";
 
               // Build tooltip
@@ -467,7 +555,7 @@ export default function Deckbuilder({ onBack }: DeckbuilderProps) {
               if (banFormat === "Genesys" && pts > 0)
                 tooltipParts.push(`${pts}pts${count > 1 ? ` × ${count} = ${totalPts}pts` : ""}`);
               if (compareMode)
-                tooltipParts.push(`owned: ${owned}, in deck: ${count}`);
+                tooltipParts.push(`owned: ${owned}${lent > 0 ? ` (${lent} lent out)` : ""}, in deck: ${count}`);
               else if (count > 1)
                 tooltipParts.push(`×${count}`);
               tooltipParts.push("right-click to remove");
@@ -515,6 +603,7 @@ export default function Deckbuilder({ onBack }: DeckbuilderProps) {
   const CARD_WIDTH = 140;
   const CARD_HEIGHT = 180;
 
+  const duelLinks = banFormat === "Duel Links";  // smaller decks, see format_rules
   const saveLabel = saveStatus === "saved" ? "Saved ✓" : saveStatus === "error" ? "Error ✕" : "Save";
   const saveBg    = saveStatus === "saved" ? "rgba(76,175,80,0.2)" : saveStatus === "error" ? "rgba(220,50,50,0.2)" : "transparent";
 
@@ -590,6 +679,17 @@ export default function Deckbuilder({ onBack }: DeckbuilderProps) {
         .deck-delete-btn:hover { color:#e05555; border-color:rgba(200,80,80,0.6); background:rgba(200,50,50,0.08); }
         .deck-delete-btn:disabled { opacity:0.3; cursor:default; }
 
+        .deck-violations {
+          margin-bottom:12px; padding:8px 10px; font-size:12px;
+          border:1px solid rgba(220,50,50,0.4); border-radius:6px;
+          background:rgba(220,50,50,0.08); color:#e0a0a0;
+        }
+        .deck-violations-title { font-weight:600; color:#e74c3c; margin-bottom:4px; }
+        .deck-violations ul { margin:0; padding-left:18px; }
+        .deck-violations-close {
+          float:right; background:none; border:none; color:inherit; cursor:pointer; font-size:12px;
+        }
+
         /* ── Target tabs + search ── */
         .db-topbar-filters { display:flex; align-items:center; gap:8px; }
 
@@ -778,7 +878,8 @@ export default function Deckbuilder({ onBack }: DeckbuilderProps) {
             <button
               className="deck-action-btn deck-save-btn"
               style={{ background: saveBg }}
-              onClick={handleSave}
+              title={saveError || undefined}
+              onClick={() => handleSave()}
               disabled={!deckName.trim()}
             >
               {saveLabel}
@@ -946,9 +1047,36 @@ export default function Deckbuilder({ onBack }: DeckbuilderProps) {
             onDragLeave={(e) => { e.currentTarget.classList.remove("drag-over"); }}
             onDrop={(e) => { e.currentTarget.classList.remove("drag-over"); handleDeckDrop(e); }}
           >
-            {renderDeckSection("Main Deck",  "main",  60)}
-            {renderDeckSection("Extra Deck", "extra", 15)}
-            {renderDeckSection("Side Deck",  "side",  15)}
+            {/* ── Deck files the startup migration left behind ── */}
+            {migration && (
+              <div className="deck-violations">
+                <button className="deck-violations-close" onClick={() => setMigration(null)}>✕</button>
+                <div className="deck-violations-title">Some saved deck files were not imported</div>
+                <ul>
+                  {migration.error && <li>{migration.error}</li>}
+                  {migration.skipped.map((s) => <li key={s.file}>{s.file}: {s.reason}</li>)}
+                </ul>
+              </div>
+            )}
+
+            {/* ── Why the deck was refused, or saved as a draft ── */}
+            {violations.length > 0 && (
+              <div className="deck-violations">
+                <div className="deck-violations-title">
+                  {savedAsDraft ? "Saved as a draft" : "Not saved"}: not legal in {banFormat}
+                </div>
+                <ul>
+                  {violations.map((v, i) => <li key={i}>{violationText(v)}</li>)}
+                </ul>
+                {!savedAsDraft && (
+                  <button className="deck-action-btn" onClick={() => handleSave(true)}>Save as draft</button>
+                )}
+              </div>
+            )}
+
+            {renderDeckSection("Main Deck",  "main",  duelLinks ? 30 : 60)}
+            {renderDeckSection("Extra Deck", "extra", duelLinks ? 8 : 15)}
+            {renderDeckSection("Side Deck",  "side",  duelLinks ? 8 : 15)}
 
             {/* ── Genesys points bar ── */}
             {banFormat === "Genesys" && (
@@ -12,6 +12,7 @@ export interface CardStub {
   totalCollectionAmount: number;
   level?: number;
   setCode?: string;
+  tags: string[];         // user tags
 }
 
 export interface CardSetRarity {
@@ -27,6 +28,10 @@ export interface CardSet {
   rarities: CardSetRarity[];
 }
 
+export type LinkMarker =
+  | "Top-Left" | "Top" | "Top-Right" | "Left"
+  | "Right" | "Bottom-Left" | "Bottom" | "Bottom-Right";
+
 export interface CardDetail {
   id: number;
   name: string;
@@ -46,6 +51,117 @@ export interface CardDetail {
   scale?: number;
   linkval?: number;
   typeline?: string[];
+  linkMarkers: LinkMarker[];
+  hasEffect?: boolean;
+  treatedAs?: string;  // the other card name this one counts as
 
   sets: CardSet[];
+  graded: GradedCard[];
+  loans: Loan[];      // outstanding loans of this card
+  tags: string[];
+  notes: CardNote[];
+  locations: StoredCopies[];  // where the owned copies are kept
+
+  currency: string;   // currency of every price
+  rateDate?: string;  // exchange rate date used for the conversion
+  missingRates: string[];  // currencies with no stored rate; those prices are left out
+}
+
+// One graded slab; not part of the printing's collectionAmount
+export interface GradedCard {
+  id: number;
+  cardId: number;
+  setCode: string;
+  setRarity: string;
+  artwork: number;
+  company: string;
+  grade: string;
+  certNumber?: string;
+  price?: number;
+  currency: string;
+  cost?: number;          // what the raw copy cost, from its purchase lot
+  costCurrency?: string;
+  addedAt: string;
+}
+
+// Copies lent out; no setCode / setRarity / artwork means any printing
+export interface Loan {
+  id: number;
+  cardId: number;
+  cardName: string;
+  setCode?: string;
+  setRarity?: string;
+  artwork?: number;
+  quantity: number;
+  borrower: string;
+  lentOn: string;
+  dueOn?: string;
+  returnedOn?: string;
+  overdue: boolean;
+  note?: string;
+}
+
+export interface Tag {
+  id: number;
+  name: string;
+  color?: string;
+  cardCount: number;
+}
+
+// A note on the card, or on one printing when setCode / setRarity / artwork are set
+export interface CardNote {
+  cardId: number;
+  setCode?: string;
+  setRarity?: string;
+  artwork?: number;
+  note: string;
+  updatedAt: string;
+}
+
+// A box, binder, page or slot; copies counts what is stored directly in it
+export interface StorageLocation {
+  id: number;
+  parentId?: number;
+  name: string;
+  kind: "box" | "binder" | "page" | "slot";
+  position?: number;
+  path: string;       // e.g. "Box A / Binder 2 / Page 3"
+  copies: number;
+}
+
+// Copies of one printing kept at one location
+export interface StoredCopies {
+  locationId: number;
+  path: string;
+  cardId: number;
+  cardName: string;
+  setCode: string;
+  setRarity: string;
+  artwork: number;
+  quantity: number;
+}
+
+export interface BinderSlot {
+  slot: number;       // 1-based pocket on the page
+  cardId: number;
+  cardName: string;
+  setCode: string;
+  setName?: string;
+  setRarity: string;
+  artwork: number;
+  owned: number;
+}
+
+export interface BinderLayout {
+  pages: { page: number; slots: BinderSlot[] }[];
+  pocketsPerPage: number;
+  printings: number;
+  ownedPrintings: number;
+}
+
+export interface MoneyValue {
+  amount: number;
+  currency: string;
+  rateDate?: string;
+  missingRates: string[];  // currencies with no stored rate, left out of amount
 }
\ No newline at end of file
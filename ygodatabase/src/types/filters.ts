@@ -1,4 +1,4 @@
-// All optional filter values passed to load_card_stubs.
+// The filter bar's state; filtersToParams turns it into load_card_stubs' filters.
 // null means "no filter applied".
 export interface CardFilters {
   name: string;
@@ -3,7 +3,7 @@ import { FRAME_COLORS } from "../constants/frames";
 import type { CardDetail } from "../types/cards";
 import type { CardFilters } from "../types/filters";
 
-/** Converts a CardFilters object into the params record expected by load_card_stubs. */
+/** Converts the filter bar's CardFilters into the `filters` argument of load_card_stubs. */
 export function filtersToParams(f: CardFilters, format?: string): Record<string, string | number> {
   const p: Record<string, string | number> = {};
   if (f.name.trim())   p.name      = f.name.trim();